@@ -0,0 +1,274 @@
+use crate::transaction::{Action, FunctionCallAction};
+use crate::AdminError;
+use serde_json::{json, Value};
+
+/// Owner-only contract calls the admin tool can build.
+///
+/// Amounts are yoctoNEAR, JSON args pass them as strings like the contract's `U128`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AdminCommand {
+    AddSignerKey {
+        pub_key: String,
+    },
+    RemoveSignerKey {
+        pub_key: String,
+    },
+    SetPrices {
+        mint: u128,
+        upgrade: u128,
+        repair_per_point: u128,
+        rename: u128,
+        palette: u128,
+        finish: u128,
+    },
+    AddBlockedNames {
+        words: Vec<String>,
+    },
+    RemoveBlockedNames {
+        words: Vec<String>,
+    },
+    BackfillTokenIndex {
+        limit: u64,
+    },
+    BackfillKartNames {
+        limit: u64,
+    },
+    ArchiveSeasonBatch {
+        limit: u64,
+    },
+    SetLootTable {
+        loot_table: Value,
+    },
+    SetSeasonRewards {
+        season_id: u32,
+        tiers: Value,
+    },
+    WithdrawTreasury {
+        receiver_id: String,
+        amount: u128,
+    },
+    ReclaimRaidPool {
+        raid_id: u64,
+    },
+}
+
+fn assert_signer_key(pub_key: &str) -> Result<(), AdminError> {
+    // Signer keys are stored as the hex of the 32 byte ed25519 public key
+    if pub_key.len() != 64 || !pub_key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AdminError::InvalidArgument(format!(
+            "signer key {} is not a hex ed25519 public key",
+            pub_key
+        )));
+    }
+    Ok(())
+}
+
+impl AdminCommand {
+    /// Check arguments the contract would reject before anything is signed.
+    pub fn validate(&self) -> Result<(), AdminError> {
+        match self {
+            AdminCommand::AddSignerKey { pub_key } | AdminCommand::RemoveSignerKey { pub_key } => {
+                assert_signer_key(pub_key)
+            }
+            AdminCommand::SetPrices { mint, upgrade, .. } if *mint == 0 || *upgrade == 0 => Err(
+                AdminError::InvalidArgument("mint and upgrade prices must not be 0".to_string()),
+            ),
+            AdminCommand::AddBlockedNames { words }
+            | AdminCommand::RemoveBlockedNames { words }
+                if words.is_empty() =>
+            {
+                Err(AdminError::InvalidArgument("no words given".to_string()))
+            }
+            AdminCommand::BackfillTokenIndex { limit } if *limit == 0 => Err(
+                AdminError::InvalidArgument("backfill limit must not be 0".to_string()),
+            ),
+            AdminCommand::BackfillKartNames { limit } if *limit == 0 => Err(
+                AdminError::InvalidArgument("backfill limit must not be 0".to_string()),
+            ),
+            AdminCommand::ArchiveSeasonBatch { limit } if *limit == 0 => Err(
+                AdminError::InvalidArgument("archive limit must not be 0".to_string()),
+            ),
+            AdminCommand::SetLootTable { loot_table } if !loot_table.is_object() => Err(
+                AdminError::InvalidArgument("loot table must be a json object".to_string()),
+            ),
+            AdminCommand::SetSeasonRewards { tiers, .. } if !tiers.is_array() => Err(
+                AdminError::InvalidArgument("season reward tiers must be a json array".to_string()),
+            ),
+            AdminCommand::WithdrawTreasury { amount, .. } if *amount == 0 => Err(
+                AdminError::InvalidArgument("withdraw amount must not be 0".to_string()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn method_name(&self) -> &'static str {
+        match self {
+            AdminCommand::AddSignerKey { .. } => "add_signer_key",
+            AdminCommand::RemoveSignerKey { .. } => "remove_signer_key",
+            AdminCommand::SetPrices { .. } => "set_prices",
+            AdminCommand::AddBlockedNames { .. } => "add_blocked_names",
+            AdminCommand::RemoveBlockedNames { .. } => "remove_blocked_names",
+            AdminCommand::BackfillTokenIndex { .. } => "backfill_token_index",
+            AdminCommand::BackfillKartNames { .. } => "backfill_kart_names",
+            AdminCommand::ArchiveSeasonBatch { .. } => "archive_season_batch",
+            AdminCommand::SetLootTable { .. } => "set_loot_table",
+            AdminCommand::SetSeasonRewards { .. } => "set_season_rewards",
+            AdminCommand::WithdrawTreasury { .. } => "withdraw_treasury",
+            AdminCommand::ReclaimRaidPool { .. } => "reclaim_raid_pool",
+        }
+    }
+
+    pub fn args(&self) -> Value {
+        match self {
+            AdminCommand::AddSignerKey { pub_key } | AdminCommand::RemoveSignerKey { pub_key } => {
+                json!({ "pub_key": pub_key })
+            }
+            AdminCommand::SetPrices {
+                mint,
+                upgrade,
+                repair_per_point,
+                rename,
+                palette,
+                finish,
+            } => json!({
+                "prices": {
+                    "mint": mint.to_string(),
+                    "upgrade": upgrade.to_string(),
+                    "repair_per_point": repair_per_point.to_string(),
+                    "rename": rename.to_string(),
+                    "palette": palette.to_string(),
+                    "finish": finish.to_string(),
+                }
+            }),
+            AdminCommand::AddBlockedNames { words }
+            | AdminCommand::RemoveBlockedNames { words } => {
+                json!({ "words": words })
+            }
+            AdminCommand::BackfillTokenIndex { limit } => json!({ "limit": limit }),
+            AdminCommand::BackfillKartNames { limit } => json!({ "limit": limit }),
+            AdminCommand::ArchiveSeasonBatch { limit } => json!({ "limit": limit }),
+            AdminCommand::SetLootTable { loot_table } => json!({ "loot_table": loot_table }),
+            AdminCommand::SetSeasonRewards { season_id, tiers } => {
+                json!({ "season_id": season_id, "tiers": tiers })
+            }
+            AdminCommand::WithdrawTreasury {
+                receiver_id,
+                amount,
+            } => json!({ "receiver_id": receiver_id, "amount": amount.to_string() }),
+            AdminCommand::ReclaimRaidPool { raid_id } => json!({ "raid_id": raid_id }),
+        }
+    }
+
+    /// Treasury withdrawals need exactly one yoctoNEAR attached.
+    pub fn deposit(&self) -> u128 {
+        match self {
+            AdminCommand::WithdrawTreasury { .. } => 1,
+            _ => 0,
+        }
+    }
+
+    pub fn action(&self, gas: u64) -> Action {
+        Action::FunctionCall(FunctionCallAction {
+            method_name: self.method_name().to_string(),
+            args: self.args().to_string().into_bytes(),
+            gas,
+            deposit: self.deposit(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUB_KEY: &str = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+
+    #[test]
+    fn test_add_signer_key_args() {
+        let command = AdminCommand::AddSignerKey {
+            pub_key: PUB_KEY.to_string(),
+        };
+
+        assert!(command.validate().is_ok());
+        assert_eq!(command.method_name(), "add_signer_key");
+        assert_eq!(
+            command.args().to_string(),
+            format!(r#"{{"pub_key":"{}"}}"#, PUB_KEY)
+        );
+        assert_eq!(command.deposit(), 0);
+    }
+
+    #[test]
+    fn test_invalid_signer_key() {
+        let command = AdminCommand::RemoveSignerKey {
+            pub_key: "ed25519:abc".to_string(),
+        };
+        assert!(command.validate().is_err());
+    }
+
+    #[test]
+    fn test_set_prices_args() {
+        let command = AdminCommand::SetPrices {
+            mint: 200_000_000_000_000_000_000_000,
+            upgrade: 100_000_000_000_000_000_000_000,
+            repair_per_point: 100_000_000_000_000_000_000,
+            rename: 50_000_000_000_000_000_000_000,
+            palette: 50_000_000_000_000_000_000_000,
+            finish: 200_000_000_000_000_000_000_000,
+        };
+
+        assert_eq!(
+            command.args(),
+            json!({"prices": {
+                "mint": "200000000000000000000000",
+                "upgrade": "100000000000000000000000",
+                "repair_per_point": "100000000000000000000",
+                "rename": "50000000000000000000000",
+                "palette": "50000000000000000000000",
+                "finish": "200000000000000000000000"
+            }})
+        );
+    }
+
+    #[test]
+    fn test_blocked_names_args() {
+        let command = AdminCommand::AddBlockedNames {
+            words: vec!["badword".to_string(), "worse".to_string()],
+        };
+        assert_eq!(command.method_name(), "add_blocked_names");
+        assert_eq!(command.args(), json!({"words": ["badword", "worse"]}));
+
+        let command = AdminCommand::RemoveBlockedNames { words: Vec::new() };
+        assert!(command.validate().is_err());
+    }
+
+    #[test]
+    fn test_withdraw_treasury_attaches_one_yocto() {
+        let command = AdminCommand::WithdrawTreasury {
+            receiver_id: "muhindogalien.testnet".to_string(),
+            amount: 5,
+        };
+
+        assert_eq!(
+            command.args(),
+            json!({"receiver_id": "muhindogalien.testnet", "amount": "5"})
+        );
+        match command.action(30) {
+            Action::FunctionCall(call) => {
+                assert_eq!(call.method_name, "withdraw_treasury");
+                assert_eq!(call.deposit, 1);
+                assert_eq!(call.gas, 30);
+            }
+            _ => panic!("expected a function call"),
+        }
+    }
+
+    #[test]
+    fn test_set_season_rewards_requires_array() {
+        let command = AdminCommand::SetSeasonRewards {
+            season_id: 1,
+            tiers: json!({"max_rank": 1}),
+        };
+        assert!(command.validate().is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum AdminError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    InvalidKey(String),
+    InvalidArgument(String),
+    Rpc(String),
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminError::Io(e) => write!(f, "io error: {}", e),
+            AdminError::Json(e) => write!(f, "invalid json: {}", e),
+            AdminError::InvalidKey(msg) => write!(f, "invalid key: {}", msg),
+            AdminError::InvalidArgument(msg) => write!(f, "invalid argument: {}", msg),
+            AdminError::Rpc(msg) => write!(f, "rpc error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AdminError {}
+
+impl From<io::Error> for AdminError {
+    fn from(e: io::Error) -> Self {
+        AdminError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for AdminError {
+    fn from(e: serde_json::Error) -> Self {
+        AdminError::Json(e)
+    }
+}
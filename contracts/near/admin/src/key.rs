@@ -0,0 +1,124 @@
+use crate::AdminError;
+use ed25519_dalek::{Keypair, PublicKey};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+const ED25519_PREFIX: &str = "ed25519:";
+
+/// NEAR credentials file, e.g. `~/.near-credentials/testnet/<account>.json`.
+#[derive(Clone, Deserialize, Debug)]
+pub struct KeyFile {
+    pub account_id: String,
+    pub public_key: String,
+    // Older near-cli versions write `private_key`, newer ones `secret_key`
+    #[serde(alias = "secret_key")]
+    pub private_key: String,
+}
+
+fn decode_ed25519(key: &str) -> Result<Vec<u8>, AdminError> {
+    if !key.starts_with(ED25519_PREFIX) {
+        return Err(AdminError::InvalidKey(format!(
+            "expected an {} key",
+            ED25519_PREFIX
+        )));
+    }
+
+    bs58::decode(&key[ED25519_PREFIX.len()..])
+        .into_vec()
+        .map_err(|e| AdminError::InvalidKey(e.to_string()))
+}
+
+/// Format a public key the way NEAR RPC and near-cli expect it.
+pub fn format_public_key(public_key: &PublicKey) -> String {
+    format!(
+        "{}{}",
+        ED25519_PREFIX,
+        bs58::encode(public_key.as_bytes()).into_string()
+    )
+}
+
+impl KeyFile {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, AdminError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// The signing keypair, checked against the `public_key` of the file.
+    pub fn keypair(&self) -> Result<Keypair, AdminError> {
+        let keypair = Keypair::from_bytes(&decode_ed25519(&self.private_key)?)
+            .map_err(|e| AdminError::InvalidKey(e.to_string()))?;
+
+        let public_key = PublicKey::from_bytes(&decode_ed25519(&self.public_key)?)
+            .map_err(|e| AdminError::InvalidKey(e.to_string()))?;
+        if public_key != keypair.public {
+            return Err(AdminError::InvalidKey(
+                "public_key does not match private_key".to_string(),
+            ));
+        }
+
+        Ok(keypair)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ed25519_dalek::SecretKey;
+
+    pub(crate) fn test_keypair() -> Keypair {
+        let secret = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    pub(crate) fn test_key_file() -> KeyFile {
+        let keypair = test_keypair();
+        KeyFile {
+            account_id: "muhindogalien.testnet".to_string(),
+            public_key: format_public_key(&keypair.public),
+            private_key: format!(
+                "{}{}",
+                ED25519_PREFIX,
+                bs58::encode(keypair.to_bytes().to_vec()).into_string()
+            ),
+        }
+    }
+
+    #[test]
+    fn test_keypair_from_key_file() {
+        let keypair = test_key_file().keypair().unwrap();
+        assert_eq!(keypair.public, test_keypair().public);
+    }
+
+    #[test]
+    fn test_key_file_secret_key_alias() {
+        let key_file = test_key_file();
+        let json = format!(
+            r#"{{"account_id":"{}","public_key":"{}","secret_key":"{}"}}"#,
+            key_file.account_id, key_file.public_key, key_file.private_key
+        );
+        let parsed: KeyFile = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.private_key, key_file.private_key);
+    }
+
+    #[test]
+    fn test_keypair_mismatched_public_key() {
+        let mut key_file = test_key_file();
+        key_file.public_key = format!(
+            "{}{}",
+            ED25519_PREFIX,
+            bs58::encode([1u8; 32]).into_string()
+        );
+
+        assert!(key_file.keypair().is_err());
+    }
+
+    #[test]
+    fn test_keypair_requires_prefix() {
+        let mut key_file = test_key_file();
+        key_file.private_key = key_file.private_key.replace(ED25519_PREFIX, "");
+
+        assert!(key_file.keypair().is_err());
+    }
+}
@@ -0,0 +1,20 @@
+/*
+Admin transactions for the NEAR Karts contract.
+NOTES:
+  - Transactions are built and signed locally with a NEAR credentials keyfile, the same JSON
+    `near login` writes to `~/.near-credentials`.
+  - Only the nonce and a recent block hash come from the network. Both can be passed in to build
+    a transaction fully offline and submit it later.
+  - The borsh layout of transactions and actions follows `near-primitives`, only the actions
+    the admin commands need are encoded.
+*/
+pub mod commands;
+pub mod error;
+pub mod key;
+pub mod rpc;
+pub mod transaction;
+
+pub use crate::commands::AdminCommand;
+pub use crate::error::AdminError;
+pub use crate::key::KeyFile;
+pub use crate::transaction::{Action, SignedTransaction, Transaction};
@@ -0,0 +1,254 @@
+/*
+nearkarts-admin
+
+USAGE:
+  nearkarts-admin [options] add-signer-key <hex_pub_key>
+  nearkarts-admin [options] remove-signer-key <hex_pub_key>
+  nearkarts-admin [options] set-prices <mint> <upgrade> <repair_per_point> <rename> <palette> <finish>
+  nearkarts-admin [options] add-blocked-names <word>...
+  nearkarts-admin [options] remove-blocked-names <word>...
+  nearkarts-admin [options] backfill-token-index <limit>
+  nearkarts-admin [options] backfill-kart-names <limit>
+  nearkarts-admin [options] archive-season-batch <limit>
+  nearkarts-admin [options] set-loot-table <loot_table.json>
+  nearkarts-admin [options] set-season-rewards <season_id> <tiers.json>
+  nearkarts-admin [options] withdraw-treasury <receiver_id> <amount>
+  nearkarts-admin [options] reclaim-raid-pool <raid_id>
+
+Amounts are in yoctoNEAR. Without --send the signed transaction is printed as JSON and nothing
+is submitted. With both --nonce and --block-hash no network access is needed at all, e.g. to sign
+on an offline machine and broadcast the printed `signed_transaction` elsewhere.
+*/
+use borsh::BorshSerialize;
+use nearkarts_admin::key::format_public_key;
+use nearkarts_admin::rpc::{decode_block_hash, AccessKeyInfo, RpcClient, DEFAULT_RPC_URL};
+use nearkarts_admin::transaction::{PublicKey, TGAS};
+use nearkarts_admin::{AdminCommand, AdminError, KeyFile, Transaction};
+use serde_json::{json, Value};
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: nearkarts-admin [options] <command>
+
+commands:
+  add-signer-key <hex_pub_key>
+  remove-signer-key <hex_pub_key>
+  set-prices <mint> <upgrade> <repair_per_point> <rename> <palette> <finish>
+  add-blocked-names <word>...
+  remove-blocked-names <word>...
+  backfill-token-index <limit>
+  backfill-kart-names <limit>
+  archive-season-batch <limit>
+  set-loot-table <loot_table.json>
+  set-season-rewards <season_id> <tiers.json>
+  withdraw-treasury <receiver_id> <amount>
+  reclaim-raid-pool <raid_id>
+
+options:
+  --contract <account_id>   contract account (required)
+  --key-file <path>         owner credentials keyfile (required)
+  --rpc <url>               rpc endpoint, default https://rpc.testnet.near.org
+                            use http://localhost:3030 for a local sandbox
+  --nonce <n>               nonce to sign with, skips the access key lookup
+  --block-hash <base58>     recent block hash, skips the access key lookup
+  --gas <tgas>              attached gas in Tgas, default 30
+  --send                    broadcast the transaction and print the outcome";
+
+struct Options {
+    contract: Option<String>,
+    key_file: Option<String>,
+    rpc: String,
+    nonce: Option<u64>,
+    block_hash: Option<String>,
+    gas: u64,
+    send: bool,
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, AdminError> {
+    value
+        .parse()
+        .map_err(|_| AdminError::InvalidArgument(format!("{} is not a number: {}", name, value)))
+}
+
+fn read_json(path: &str) -> Result<Value, AdminError> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn parse_args(args: &[String]) -> Result<(Options, Vec<&str>), AdminError> {
+    let mut options = Options {
+        contract: None,
+        key_file: None,
+        rpc: DEFAULT_RPC_URL.to_string(),
+        nonce: None,
+        block_hash: None,
+        gas: 30,
+        send: false,
+    };
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--send" {
+            options.send = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            positional.push(arg.as_str());
+            continue;
+        }
+
+        let value = iter
+            .next()
+            .ok_or_else(|| AdminError::InvalidArgument(format!("{} needs a value", arg)))?;
+        match arg.as_str() {
+            "--contract" => options.contract = Some(value.clone()),
+            "--key-file" => options.key_file = Some(value.clone()),
+            "--rpc" => options.rpc = value.clone(),
+            "--nonce" => options.nonce = Some(parse_number("nonce", value)?),
+            "--block-hash" => options.block_hash = Some(value.clone()),
+            "--gas" => options.gas = parse_number("gas", value)?,
+            _ => {
+                return Err(AdminError::InvalidArgument(format!(
+                    "unknown option {}",
+                    arg
+                )))
+            }
+        }
+    }
+
+    Ok((options, positional))
+}
+
+fn parse_command(positional: &[&str]) -> Result<Option<AdminCommand>, AdminError> {
+    let command = match positional {
+        ["add-signer-key", pub_key] => AdminCommand::AddSignerKey {
+            pub_key: pub_key.to_string(),
+        },
+        ["remove-signer-key", pub_key] => AdminCommand::RemoveSignerKey {
+            pub_key: pub_key.to_string(),
+        },
+        ["set-prices", mint, upgrade, repair_per_point, rename, palette, finish] => {
+            AdminCommand::SetPrices {
+                mint: parse_number("mint", mint)?,
+                upgrade: parse_number("upgrade", upgrade)?,
+                repair_per_point: parse_number("repair_per_point", repair_per_point)?,
+                rename: parse_number("rename", rename)?,
+                palette: parse_number("palette", palette)?,
+                finish: parse_number("finish", finish)?,
+            }
+        }
+        ["add-blocked-names", words @ ..] => AdminCommand::AddBlockedNames {
+            words: words.iter().map(|word| word.to_string()).collect(),
+        },
+        ["remove-blocked-names", words @ ..] => AdminCommand::RemoveBlockedNames {
+            words: words.iter().map(|word| word.to_string()).collect(),
+        },
+        ["backfill-token-index", limit] => AdminCommand::BackfillTokenIndex {
+            limit: parse_number("limit", limit)?,
+        },
+        ["backfill-kart-names", limit] => AdminCommand::BackfillKartNames {
+            limit: parse_number("limit", limit)?,
+        },
+        ["archive-season-batch", limit] => AdminCommand::ArchiveSeasonBatch {
+            limit: parse_number("limit", limit)?,
+        },
+        ["set-loot-table", path] => AdminCommand::SetLootTable {
+            loot_table: read_json(path)?,
+        },
+        ["set-season-rewards", season_id, path] => AdminCommand::SetSeasonRewards {
+            season_id: parse_number("season_id", season_id)?,
+            tiers: read_json(path)?,
+        },
+        ["withdraw-treasury", receiver_id, amount] => AdminCommand::WithdrawTreasury {
+            receiver_id: receiver_id.to_string(),
+            amount: parse_number("amount", amount)?,
+        },
+        ["reclaim-raid-pool", raid_id] => AdminCommand::ReclaimRaidPool {
+            raid_id: parse_number("raid_id", raid_id)?,
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(command))
+}
+
+fn run(options: Options, command: AdminCommand) -> Result<(), AdminError> {
+    command.validate()?;
+
+    let contract = options
+        .contract
+        .ok_or_else(|| AdminError::InvalidArgument("--contract is required".to_string()))?;
+    let key_file = options
+        .key_file
+        .ok_or_else(|| AdminError::InvalidArgument("--key-file is required".to_string()))?;
+    let key_file = KeyFile::load(key_file)?;
+    let keypair = key_file.keypair()?;
+
+    let rpc = RpcClient::new(&options.rpc);
+    let (nonce, block_hash) = match (options.nonce, options.block_hash) {
+        (Some(nonce), Some(block_hash)) => (nonce, decode_block_hash(&block_hash)?),
+        (None, None) => {
+            // The access key holds the last used nonce, the next transaction takes the one after
+            let AccessKeyInfo { nonce, block_hash } =
+                rpc.view_access_key(&key_file.account_id, &format_public_key(&keypair.public))?;
+            (nonce + 1, block_hash)
+        }
+        _ => {
+            return Err(AdminError::InvalidArgument(
+                "--nonce and --block-hash must be given together".to_string(),
+            ))
+        }
+    };
+
+    let action = command.action(options.gas * TGAS);
+    let transaction = Transaction {
+        signer_id: key_file.account_id.clone(),
+        public_key: PublicKey::Ed25519(keypair.public.to_bytes()),
+        nonce,
+        receiver_id: contract.clone(),
+        block_hash,
+        actions: vec![action.clone()],
+    };
+    let hash = transaction.hash();
+    let signed = transaction.sign(&keypair);
+    let signed_transaction = base64::encode(signed.try_to_vec().unwrap());
+
+    if options.send {
+        let outcome = rpc.broadcast_tx_commit(&signed_transaction)?;
+        println!("{}", serde_json::to_string_pretty(&outcome)?);
+        return Ok(());
+    }
+
+    let output = json!({
+        "signer_id": key_file.account_id,
+        "receiver_id": contract,
+        "method_name": command.method_name(),
+        "args": command.args(),
+        "deposit": command.deposit().to_string(),
+        "gas": (options.gas * TGAS).to_string(),
+        "nonce": nonce,
+        "action": base64::encode(action.try_to_vec().unwrap()),
+        "hash": bs58::encode(hash).into_string(),
+        "signed_transaction": signed_transaction,
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result =
+        parse_args(&args).and_then(|(options, positional)| match parse_command(&positional)? {
+            Some(command) => run(options, command),
+            None => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        });
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
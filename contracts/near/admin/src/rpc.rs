@@ -0,0 +1,114 @@
+use crate::AdminError;
+use serde_json::{json, Value};
+
+pub const DEFAULT_RPC_URL: &str = "https://rpc.testnet.near.org";
+
+/// Minimal JSON-RPC client, works against public RPC nodes and a local sandbox.
+pub struct RpcClient {
+    url: String,
+}
+
+/// Nonce and block hash needed to build a transaction for an access key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessKeyInfo {
+    pub nonce: u64,
+    pub block_hash: [u8; 32],
+}
+
+pub fn decode_block_hash(block_hash: &str) -> Result<[u8; 32], AdminError> {
+    let bytes = bs58::decode(block_hash)
+        .into_vec()
+        .map_err(|e| AdminError::InvalidArgument(format!("block hash: {}", e)))?;
+    if bytes.len() != 32 {
+        return Err(AdminError::InvalidArgument(
+            "block hash must be 32 bytes".to_string(),
+        ));
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&bytes);
+    Ok(hash)
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        RpcClient {
+            url: url.to_string(),
+        }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value, AdminError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "nearkarts-admin",
+            "method": method,
+            "params": params,
+        });
+
+        let response: Value = ureq::post(&self.url)
+            .send_json(request)
+            .map_err(|e| AdminError::Rpc(e.to_string()))?
+            .into_json()?;
+
+        if let Some(error) = response.get("error") {
+            return Err(AdminError::Rpc(error.to_string()));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| AdminError::Rpc("response has no result".to_string()))
+    }
+
+    pub fn view_access_key(
+        &self,
+        account_id: &str,
+        public_key: &str,
+    ) -> Result<AccessKeyInfo, AdminError> {
+        let result = self.call(
+            "query",
+            json!({
+                "request_type": "view_access_key",
+                "finality": "final",
+                "account_id": account_id,
+                "public_key": public_key,
+            }),
+        )?;
+
+        if let Some(error) = result.get("error") {
+            return Err(AdminError::Rpc(error.to_string()));
+        }
+        let nonce = result["nonce"]
+            .as_u64()
+            .ok_or_else(|| AdminError::Rpc("access key has no nonce".to_string()))?;
+        let block_hash = result["block_hash"]
+            .as_str()
+            .ok_or_else(|| AdminError::Rpc("response has no block_hash".to_string()))?;
+
+        Ok(AccessKeyInfo {
+            nonce,
+            block_hash: decode_block_hash(block_hash)?,
+        })
+    }
+
+    /// Submit a base64 encoded signed transaction and wait for its outcome.
+    pub fn broadcast_tx_commit(&self, signed_transaction: &str) -> Result<Value, AdminError> {
+        self.call("broadcast_tx_commit", json!([signed_transaction]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_block_hash() {
+        let encoded = bs58::encode([9u8; 32]).into_string();
+        assert_eq!(decode_block_hash(&encoded).unwrap(), [9u8; 32]);
+    }
+
+    #[test]
+    fn test_decode_block_hash_wrong_length() {
+        let encoded = bs58::encode([9u8; 16]).into_string();
+        assert!(decode_block_hash(&encoded).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use borsh::BorshSerialize;
+use ed25519_dalek::{Keypair, Signer};
+use sha2::{Digest, Sha256};
+
+pub const TGAS: u64 = 1_000_000_000_000;
+
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub enum PublicKey {
+    Ed25519([u8; 32]),
+}
+
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub enum Signature {
+    Ed25519([u8; 64]),
+}
+
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub struct CreateAccountAction {}
+
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub struct DeployContractAction {
+    pub code: Vec<u8>,
+}
+
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub struct FunctionCallAction {
+    pub method_name: String,
+    pub args: Vec<u8>,
+    pub gas: u64,
+    pub deposit: u128,
+}
+
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub struct TransferAction {
+    pub deposit: u128,
+}
+
+/// Variant order matches `near-primitives`, the borsh tag is the variant index.
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub enum Action {
+    CreateAccount(CreateAccountAction),
+    DeployContract(DeployContractAction),
+    FunctionCall(FunctionCallAction),
+    Transfer(TransferAction),
+}
+
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub struct Transaction {
+    pub signer_id: String,
+    pub public_key: PublicKey,
+    pub nonce: u64,
+    pub receiver_id: String,
+    pub block_hash: [u8; 32],
+    pub actions: Vec<Action>,
+}
+
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub struct SignedTransaction {
+    pub transaction: Transaction,
+    pub signature: Signature,
+}
+
+impl Transaction {
+    /// sha256 of the borsh encoded transaction, the transaction hash shown by explorers.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&Sha256::digest(&self.try_to_vec().unwrap()));
+        hash
+    }
+
+    pub fn sign(self, keypair: &Keypair) -> SignedTransaction {
+        let signature = keypair.sign(&self.hash());
+        SignedTransaction {
+            transaction: self,
+            signature: Signature::Ed25519(signature.to_bytes()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::tests::test_keypair;
+    use ed25519_dalek::Verifier;
+
+    fn transaction() -> Transaction {
+        Transaction {
+            signer_id: "a".to_string(),
+            public_key: PublicKey::Ed25519([1u8; 32]),
+            nonce: 5,
+            receiver_id: "b".to_string(),
+            block_hash: [2u8; 32],
+            actions: vec![Action::FunctionCall(FunctionCallAction {
+                method_name: "m".to_string(),
+                args: b"{}".to_vec(),
+                gas: 30 * TGAS,
+                deposit: 1,
+            })],
+        }
+    }
+
+    #[test]
+    fn test_function_call_layout() {
+        let action = Action::FunctionCall(FunctionCallAction {
+            method_name: "m".to_string(),
+            args: b"{}".to_vec(),
+            gas: 1,
+            deposit: 2,
+        });
+
+        let mut expected = vec![2u8];
+        expected.extend_from_slice(&[1, 0, 0, 0, b'm']);
+        expected.extend_from_slice(&[2, 0, 0, 0, b'{', b'}']);
+        expected.extend_from_slice(&1u64.to_le_bytes());
+        expected.extend_from_slice(&2u128.to_le_bytes());
+        assert_eq!(action.try_to_vec().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_transaction_layout() {
+        let bytes = transaction().try_to_vec().unwrap();
+
+        // signer_id
+        assert_eq!(&bytes[0..5], &[1, 0, 0, 0, b'a']);
+        // public key tag and bytes
+        assert_eq!(bytes[5], 0);
+        assert_eq!(&bytes[6..38], &[1u8; 32]);
+        // nonce
+        assert_eq!(&bytes[38..46], &5u64.to_le_bytes());
+        // receiver_id
+        assert_eq!(&bytes[46..51], &[1, 0, 0, 0, b'b']);
+        // block hash
+        assert_eq!(&bytes[51..83], &[2u8; 32]);
+        // one action
+        assert_eq!(&bytes[83..87], &[1, 0, 0, 0]);
+        assert_eq!(bytes[87], 2);
+    }
+
+    #[test]
+    fn test_sign() {
+        let keypair = test_keypair();
+        let tx = transaction();
+        let hash = tx.hash();
+        let signed = tx.sign(&keypair);
+
+        let Signature::Ed25519(bytes) = signed.signature.clone();
+        let signature = ed25519_dalek::Signature::from_bytes(&bytes).unwrap();
+        assert!(keypair.public.verify(&hash, &signature).is_ok());
+
+        let encoded = signed.try_to_vec().unwrap();
+        assert_eq!(
+            encoded.len(),
+            signed.transaction.try_to_vec().unwrap().len() + 65
+        );
+    }
+}
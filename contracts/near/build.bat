@@ -1,6 +1,6 @@
 @echo off
 
 title NFT build
-cargo build --all --target wasm32-unknown-unknown --release
+cargo build --all --exclude nearkarts-admin --exclude nearkarts-indexer --exclude nearkarts-renderer --exclude nearkarts-signer --target wasm32-unknown-unknown --release
 xcopy %CD%\target\wasm32-unknown-unknown\release\*.wasm %CD%\res /Y
 pause
\ No newline at end of file
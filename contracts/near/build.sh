@@ -2,5 +2,5 @@
 set -e
 cd "`dirname $0`"
 source flags.sh
-cargo build --all --target wasm32-unknown-unknown --release
+cargo build --all --exclude nearkarts-admin --exclude nearkarts-indexer --exclude nearkarts-renderer --exclude nearkarts-signer --target wasm32-unknown-unknown --release
 cp target/wasm32-unknown-unknown/release/*.wasm ./res/
\ No newline at end of file
@@ -0,0 +1,38 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum IndexerError {
+    Io(io::Error),
+    Json {
+        line: usize,
+        source: serde_json::Error,
+    },
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for IndexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexerError::Io(e) => write!(f, "io error: {}", e),
+            IndexerError::Json { line, source } => {
+                write!(f, "invalid json on line {}: {}", line, source)
+            }
+            IndexerError::Sqlite(e) => write!(f, "sqlite error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for IndexerError {}
+
+impl From<io::Error> for IndexerError {
+    fn from(e: io::Error) -> Self {
+        IndexerError::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for IndexerError {
+    fn from(e: rusqlite::Error) -> Self {
+        IndexerError::Sqlite(e)
+    }
+}
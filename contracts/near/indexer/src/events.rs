@@ -0,0 +1,55 @@
+pub use nearkarts_types::{EventLog, EventLogVariant};
+
+const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
+/// Parse a log line emitted by the contract.
+///
+/// Returns `None` for logs that are not NEP-297 events, and an error for event logs that do not
+/// match any known event.
+pub fn parse_event_log(log: &str) -> Option<Result<EventLog, serde_json::Error>> {
+    if !log.starts_with(EVENT_JSON_PREFIX) {
+        return None;
+    }
+
+    Some(serde_json::from_str(&log[EVENT_JSON_PREFIX.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_battle_event() {
+        let log = r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"battle","data":[{"home_token_id":"megakart","away_token_id":"fluffykart","winner":0,"battle":1234,"prize":"3","extra":""}]}"#;
+        let event = parse_event_log(log).unwrap().unwrap();
+
+        assert_eq!(event.standard, "nearkarts");
+        match event.event {
+            EventLogVariant::Battle(battles) => {
+                assert_eq!(battles[0].home_token_id, "megakart");
+                assert_eq!(battles[0].prize, "3");
+            }
+            _ => panic!("expected a battle event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_round_trips_contract_output() {
+        let log = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"alice.near","token_ids":["megakart"]}]}"#;
+        let event = parse_event_log(log).unwrap().unwrap();
+
+        assert_eq!(event.to_string(), log);
+    }
+
+    #[test]
+    fn test_parse_ignores_plain_logs() {
+        assert!(parse_event_log("Transfer 1 from alice.near").is_none());
+    }
+
+    #[test]
+    fn test_parse_unknown_event() {
+        let log =
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"explode","data":[]}"#;
+        assert!(parse_event_log(log).unwrap().is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::IndexerError;
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+
+/// One receipt of the stream, as written to the JSON-lines fixture.
+///
+/// `block_timestamp` is in nanoseconds like `env::block_timestamp()`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ReceiptRecord {
+    pub block_height: u64,
+    pub block_timestamp: u64,
+    pub receipt_id: String,
+    pub predecessor_id: String,
+    pub receiver_id: String,
+    #[serde(default)]
+    pub logs: Vec<String>,
+}
+
+/// Read every receipt of a fixture, skipping blank lines.
+pub fn read_fixture<R: BufRead>(reader: R) -> Result<Vec<ReceiptRecord>, IndexerError> {
+    let mut receipts = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let receipt = serde_json::from_str(&line).map_err(|source| IndexerError::Json {
+            line: i + 1,
+            source,
+        })?;
+        receipts.push(receipt);
+    }
+
+    Ok(receipts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_fixture() {
+        let fixture = r#"{"block_height":1,"block_timestamp":10,"receipt_id":"r1","predecessor_id":"alice.near","receiver_id":"karts.near","logs":["hello"]}
+
+{"block_height":2,"block_timestamp":20,"receipt_id":"r2","predecessor_id":"bob.near","receiver_id":"karts.near"}
+"#;
+        let receipts = read_fixture(fixture.as_bytes()).unwrap();
+
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].logs, vec!["hello".to_string()]);
+        assert_eq!(receipts[1].receipt_id, "r2");
+        assert!(receipts[1].logs.is_empty());
+    }
+
+    #[test]
+    fn test_read_fixture_reports_line() {
+        let fixture = "{\"block_height\":1}\n";
+        match read_fixture(fixture.as_bytes()) {
+            Err(IndexerError::Json { line, .. }) => assert_eq!(line, 1),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+/*
+Off-chain indexer for the NEAR Karts contract.
+NOTES:
+  - Input is a JSON-lines fixture, one receipt per line with the logs its execution produced.
+  - `EVENT_JSON:` logs are parsed into the same `EventLog` types the contract emits, so the
+    indexer can't drift from the contract's event shapes.
+  - Events are projected into SQLite tables of karts (owner, name, media, level), battles and
+    unlocked decals. Receipts are recorded as they are applied, so re-running a fixture is a no-op.
+  - Reconciliation compares the projection against an `nft_tokens` view snapshot and reports
+    every field that differs.
+*/
+pub mod error;
+pub mod events;
+pub mod fixture;
+pub mod projection;
+pub mod reconcile;
+
+pub use crate::error::IndexerError;
+pub use crate::fixture::ReceiptRecord;
+pub use crate::projection::Projection;
@@ -0,0 +1,76 @@
+/*
+nearkarts-indexer
+
+USAGE:
+  nearkarts-indexer index <receipts.jsonl> <db.sqlite> [contract_id]
+  nearkarts-indexer reconcile <db.sqlite> <nft_tokens.json>
+
+The reconcile snapshot is the output of the `nft_tokens` view method, e.g.
+  near view $ID nft_tokens '{"from_index": "0", "limit": 1000}'
+*/
+use nearkarts_indexer::fixture::read_fixture;
+use nearkarts_indexer::reconcile::{read_snapshot, reconcile};
+use nearkarts_indexer::{IndexerError, Projection};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::process;
+
+const USAGE: &str = "usage:
+  nearkarts-indexer index <receipts.jsonl> <db.sqlite> [contract_id]
+  nearkarts-indexer reconcile <db.sqlite> <nft_tokens.json>";
+
+fn index(fixture: &str, db: &str, contract_id: Option<&str>) -> Result<(), IndexerError> {
+    let receipts = read_fixture(BufReader::new(File::open(fixture)?))?;
+    let mut projection = Projection::open(db)?;
+    let stats = projection.index(&receipts, contract_id)?;
+
+    println!(
+        "applied {} receipts, {} events, {} unknown events",
+        stats.receipts, stats.events, stats.unknown_events
+    );
+    Ok(())
+}
+
+fn run_reconcile(db: &str, snapshot: &str) -> Result<bool, IndexerError> {
+    let projection = Projection::open(db)?;
+    let snapshot = read_snapshot(&fs::read_to_string(snapshot)?)?;
+    let mismatches = reconcile(&projection, &snapshot)?;
+
+    for m in mismatches.iter() {
+        println!(
+            "{}: {} projected={:?} snapshot={:?}",
+            m.token_id, m.field, m.projected, m.snapshot
+        );
+    }
+    println!(
+        "{} tokens checked, {} mismatches",
+        snapshot.len(),
+        mismatches.len()
+    );
+
+    Ok(mismatches.is_empty())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+
+    let result = match args.as_slice() {
+        ["index", fixture, db] => index(fixture, db, None).map(|_| true),
+        ["index", fixture, db, contract_id] => index(fixture, db, Some(contract_id)).map(|_| true),
+        ["reconcile", db, snapshot] => run_reconcile(db, snapshot),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crate::events::{parse_event_log, EventLog, EventLogVariant};
+use crate::{IndexerError, ReceiptRecord};
+use rusqlite::{params, Connection, OptionalExtension, NO_PARAMS};
+use std::path::Path;
+
+// Every kart starts with the NEAR decal unlocked
+const MINT_DECAL: &str = "7";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS receipts (
+    receipt_id TEXT PRIMARY KEY,
+    block_height INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS karts (
+    token_id TEXT PRIMARY KEY,
+    owner_id TEXT NOT NULL,
+    name TEXT NOT NULL DEFAULT '',
+    media TEXT NOT NULL DEFAULT '',
+    level INTEGER NOT NULL DEFAULT 1
+);
+CREATE TABLE IF NOT EXISTS battles (
+    receipt_id TEXT NOT NULL,
+    block_height INTEGER NOT NULL,
+    block_timestamp INTEGER NOT NULL,
+    home_token_id TEXT NOT NULL,
+    away_token_id TEXT NOT NULL,
+    winner INTEGER NOT NULL,
+    battle INTEGER NOT NULL,
+    prize TEXT NOT NULL,
+    extra TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS battles_home ON battles (home_token_id);
+CREATE TABLE IF NOT EXISTS decals (
+    token_id TEXT NOT NULL,
+    decal TEXT NOT NULL,
+    PRIMARY KEY (token_id, decal)
+);
+";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KartRow {
+    pub token_id: String,
+    pub owner_id: String,
+    pub name: String,
+    pub media: String,
+    pub level: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BattleRow {
+    pub receipt_id: String,
+    pub block_height: u64,
+    pub block_timestamp: u64,
+    pub home_token_id: String,
+    pub away_token_id: String,
+    pub winner: u8,
+    pub battle: u32,
+    pub prize: String,
+    pub extra: String,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct IndexStats {
+    pub receipts: usize,
+    pub events: usize,
+    /// `EVENT_JSON:` logs that did not match any known event
+    pub unknown_events: usize,
+}
+
+/// SQLite projection of the contract state rebuilt from its events.
+pub struct Projection {
+    conn: Connection,
+}
+
+impl Projection {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, IndexerError> {
+        Projection::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, IndexerError> {
+        Projection::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, IndexerError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Projection { conn })
+    }
+
+    /// Apply receipts in order. Receipts sent to other accounts than `contract_id` and receipts
+    /// that were already applied are skipped.
+    pub fn index(
+        &mut self,
+        receipts: &[ReceiptRecord],
+        contract_id: Option<&str>,
+    ) -> Result<IndexStats, IndexerError> {
+        let mut stats = IndexStats::default();
+
+        for receipt in receipts {
+            if let Some(contract_id) = contract_id {
+                if receipt.receiver_id != contract_id {
+                    continue;
+                }
+            }
+
+            let tx = self.conn.transaction()?;
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO receipts (receipt_id, block_height) VALUES (?1, ?2)",
+                params![receipt.receipt_id, receipt.block_height as i64],
+            )?;
+            if inserted == 0 {
+                continue;
+            }
+
+            for log in receipt.logs.iter() {
+                match parse_event_log(log) {
+                    Some(Ok(event)) => {
+                        apply_event(&tx, receipt, &event)?;
+                        stats.events += 1;
+                    }
+                    Some(Err(_)) => stats.unknown_events += 1,
+                    None => {}
+                }
+            }
+
+            tx.commit()?;
+            stats.receipts += 1;
+        }
+
+        Ok(stats)
+    }
+
+    pub fn kart(&self, token_id: &str) -> Result<Option<KartRow>, IndexerError> {
+        let kart = self
+            .conn
+            .query_row(
+                "SELECT token_id, owner_id, name, media, level FROM karts WHERE token_id = ?1",
+                params![token_id],
+                kart_row,
+            )
+            .optional()?;
+        Ok(kart)
+    }
+
+    pub fn karts(&self) -> Result<Vec<KartRow>, IndexerError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT token_id, owner_id, name, media, level FROM karts ORDER BY token_id",
+        )?;
+        let karts = stmt
+            .query_map(NO_PARAMS, kart_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(karts)
+    }
+
+    pub fn karts_for_owner(&self, owner_id: &str) -> Result<Vec<KartRow>, IndexerError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT token_id, owner_id, name, media, level FROM karts WHERE owner_id = ?1
+             ORDER BY token_id",
+        )?;
+        let karts = stmt
+            .query_map(params![owner_id], kart_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(karts)
+    }
+
+    /// Unlocked decals of a kart in ascending order.
+    pub fn decals(&self, token_id: &str) -> Result<Vec<String>, IndexerError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT decal FROM decals WHERE token_id = ?1 ORDER BY CAST(decal AS INTEGER), decal",
+        )?;
+        let decals = stmt
+            .query_map(params![token_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(decals)
+    }
+
+    /// Battles a kart started, oldest first.
+    pub fn battles(&self, token_id: &str) -> Result<Vec<BattleRow>, IndexerError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT receipt_id, block_height, block_timestamp, home_token_id, away_token_id,
+                    winner, battle, prize, extra
+             FROM battles WHERE home_token_id = ?1 ORDER BY block_height, rowid",
+        )?;
+        let battles = stmt
+            .query_map(params![token_id], |row| {
+                Ok(BattleRow {
+                    receipt_id: row.get(0)?,
+                    block_height: row.get::<_, i64>(1)? as u64,
+                    block_timestamp: row.get::<_, i64>(2)? as u64,
+                    home_token_id: row.get(3)?,
+                    away_token_id: row.get(4)?,
+                    winner: row.get::<_, i64>(5)? as u8,
+                    battle: row.get::<_, i64>(6)? as u32,
+                    prize: row.get(7)?,
+                    extra: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(battles)
+    }
+}
+
+fn kart_row(row: &rusqlite::Row) -> rusqlite::Result<KartRow> {
+    Ok(KartRow {
+        token_id: row.get(0)?,
+        owner_id: row.get(1)?,
+        name: row.get(2)?,
+        media: row.get(3)?,
+        level: row.get::<_, i64>(4)? as u32,
+    })
+}
+
+fn unlock_decal(conn: &Connection, token_id: &str, decal: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT OR IGNORE INTO decals (token_id, decal) VALUES (?1, ?2)",
+        params![token_id, decal],
+    )
+}
+
+fn apply_event(
+    conn: &Connection,
+    receipt: &ReceiptRecord,
+    event: &EventLog,
+) -> Result<(), IndexerError> {
+    match &event.event {
+        EventLogVariant::NftMint(mints) => {
+            for mint in mints {
+                for token_id in mint.token_ids.iter() {
+                    conn.execute(
+                        "INSERT OR REPLACE INTO karts (token_id, owner_id) VALUES (?1, ?2)",
+                        params![token_id, mint.owner_id],
+                    )?;
+                    unlock_decal(conn, token_id, MINT_DECAL)?;
+                }
+            }
+        }
+        EventLogVariant::NftTransfer(transfers) => {
+            for transfer in transfers {
+                for token_id in transfer.token_ids.iter() {
+                    conn.execute(
+                        "UPDATE karts SET owner_id = ?2 WHERE token_id = ?1",
+                        params![token_id, transfer.new_owner_id],
+                    )?;
+                }
+            }
+        }
+        EventLogVariant::NftBurn(burns) => {
+            for burn in burns {
+                for token_id in burn.token_ids.iter() {
+                    conn.execute("DELETE FROM karts WHERE token_id = ?1", params![token_id])?;
+                    conn.execute("DELETE FROM decals WHERE token_id = ?1", params![token_id])?;
+                }
+            }
+        }
+        EventLogVariant::Configure(metas) | EventLogVariant::Upgrade(metas) => {
+            for meta in metas {
+                conn.execute(
+                    "UPDATE karts SET name = ?2, media = ?3 WHERE token_id = ?1",
+                    params![meta.token_id, meta.name, meta.media],
+                )?;
+            }
+        }
+        EventLogVariant::LevelUp(level_ups) => {
+            for level_up in level_ups {
+                conn.execute(
+                    "UPDATE karts SET level = ?2 WHERE token_id = ?1",
+                    params![level_up.token_id, level_up.level as i64],
+                )?;
+            }
+        }
+        EventLogVariant::Battle(battles) => {
+            for battle in battles {
+                conn.execute(
+                    "INSERT INTO battles (receipt_id, block_height, block_timestamp, home_token_id,
+                        away_token_id, winner, battle, prize, extra)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        receipt.receipt_id,
+                        receipt.block_height as i64,
+                        receipt.block_timestamp as i64,
+                        battle.home_token_id,
+                        battle.away_token_id,
+                        battle.winner as i64,
+                        battle.battle as i64,
+                        battle.prize,
+                        battle.extra,
+                    ],
+                )?;
+            }
+        }
+        EventLogVariant::KartRename(renames) => {
+            for rename in renames {
+                conn.execute(
+                    "UPDATE karts SET name = ?2 WHERE token_id = ?1",
+                    params![rename.token_id, rename.new_name],
+                )?;
+            }
+        }
+        EventLogVariant::Prize(prizes) => {
+            for prize in prizes {
+                if prize.decal != "0" && !prize.decal.is_empty() {
+                    unlock_decal(conn, &prize.token_id, &prize.decal)?;
+                }
+            }
+        }
+        // Achievements and quests only grant repair kits, and operators are not projected
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::read_fixture;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/receipts.jsonl");
+
+    fn indexed() -> Projection {
+        let receipts = read_fixture(FIXTURE.as_bytes()).unwrap();
+        let mut projection = Projection::open_in_memory().unwrap();
+        projection.index(&receipts, Some("karts.near")).unwrap();
+        projection
+    }
+
+    #[test]
+    fn test_index_fixture() {
+        let projection = indexed();
+
+        let kart = projection.kart("megakart").unwrap().unwrap();
+        assert_eq!(kart.owner_id, "bob.near");
+        assert_eq!(kart.name, "Turbo Kart");
+        assert_eq!(
+            kart.media,
+            "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm"
+        );
+        assert_eq!(kart.level, 3);
+
+        assert_eq!(projection.decals("megakart").unwrap(), vec!["3", "7"]);
+        assert_eq!(projection.karts_for_owner("bob.near").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_index_battles() {
+        let projection = indexed();
+
+        let battles = projection.battles("megakart").unwrap();
+        assert_eq!(battles.len(), 2);
+        assert_eq!(battles[0].away_token_id, "fluffykart");
+        assert_eq!(battles[0].prize, "3");
+        assert_eq!(battles[1].winner, 1);
+    }
+
+    #[test]
+    fn test_index_rename() {
+        let receipts = read_fixture(FIXTURE.as_bytes()).unwrap();
+        let (rename, before) = receipts.split_last().unwrap();
+        assert_eq!(rename.receipt_id, "rename-megakart");
+
+        let mut projection = Projection::open_in_memory().unwrap();
+        projection.index(before, Some("karts.near")).unwrap();
+        assert_eq!(
+            projection.kart("megakart").unwrap().unwrap().name,
+            "MegaKart"
+        );
+
+        projection
+            .index(&[rename.clone()], Some("karts.near"))
+            .unwrap();
+        assert_eq!(
+            projection.kart("megakart").unwrap().unwrap().name,
+            "Turbo Kart"
+        );
+    }
+
+    #[test]
+    fn test_index_burn() {
+        let projection = indexed();
+
+        assert!(projection.kart("fluffykart").unwrap().is_none());
+        assert!(projection.decals("fluffykart").unwrap().is_empty());
+        assert_eq!(projection.karts().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_index_is_idempotent() {
+        let receipts = read_fixture(FIXTURE.as_bytes()).unwrap();
+        let mut projection = Projection::open_in_memory().unwrap();
+
+        let first = projection.index(&receipts, Some("karts.near")).unwrap();
+        let second = projection.index(&receipts, Some("karts.near")).unwrap();
+
+        assert_eq!(first.unknown_events, 1);
+        assert_eq!(second.receipts, 0);
+        assert_eq!(projection.battles("megakart").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_index_skips_other_contracts() {
+        let projection = indexed();
+
+        assert!(projection.kart("otherkart").unwrap().is_none());
+    }
+}
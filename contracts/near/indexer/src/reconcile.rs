@@ -0,0 +1,235 @@
+use crate::{IndexerError, Projection};
+use nearkarts_types::NearKart;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+/// Token as returned by the `nft_tokens` / `nft_token` view methods.
+///
+/// Only the fields the projection tracks are read.
+#[derive(Clone, Deserialize, Debug)]
+pub struct SnapshotToken {
+    pub token_id: String,
+    pub owner_id: String,
+    #[serde(default)]
+    pub metadata: Option<SnapshotMetadata>,
+}
+
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct SnapshotMetadata {
+    pub title: Option<String>,
+    pub media: Option<String>,
+    pub extra: Option<String>,
+}
+
+/// A field where the projection and the view snapshot disagree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    pub token_id: String,
+    pub field: String,
+    pub projected: String,
+    pub snapshot: String,
+}
+
+fn mismatch(token_id: &str, field: &str, projected: String, snapshot: String) -> Mismatch {
+    Mismatch {
+        token_id: token_id.to_string(),
+        field: field.to_string(),
+        projected,
+        snapshot,
+    }
+}
+
+/// Parse the JSON output of `nft_tokens`.
+pub fn read_snapshot(json: &str) -> Result<Vec<SnapshotToken>, IndexerError> {
+    serde_json::from_str(json).map_err(|source| IndexerError::Json { line: 1, source })
+}
+
+/// Compare every kart of the projection with the snapshot.
+///
+/// Karts missing on either side are reported with a `token` field mismatch.
+pub fn reconcile(
+    projection: &Projection,
+    snapshot: &[SnapshotToken],
+) -> Result<Vec<Mismatch>, IndexerError> {
+    let mut mismatches = Vec::new();
+    let mut seen = BTreeSet::new();
+
+    for token in snapshot {
+        seen.insert(token.token_id.clone());
+
+        let kart = match projection.kart(&token.token_id)? {
+            Some(kart) => kart,
+            None => {
+                mismatches.push(mismatch(
+                    &token.token_id,
+                    "token",
+                    "missing".to_string(),
+                    "present".to_string(),
+                ));
+                continue;
+            }
+        };
+
+        let metadata = token.metadata.clone().unwrap_or_default();
+        let nk = match NearKart::from_data(&metadata.extra.unwrap_or_default()) {
+            Ok(nk) => nk,
+            Err(e) => {
+                mismatches.push(mismatch(
+                    &token.token_id,
+                    "extra",
+                    "kart".to_string(),
+                    e.id().to_string(),
+                ));
+                continue;
+            }
+        };
+
+        if kart.owner_id != token.owner_id {
+            mismatches.push(mismatch(
+                &token.token_id,
+                "owner_id",
+                kart.owner_id.clone(),
+                token.owner_id.clone(),
+            ));
+        }
+
+        let title = metadata.title.unwrap_or_default();
+        if kart.name != title {
+            mismatches.push(mismatch(&token.token_id, "name", kart.name.clone(), title));
+        }
+
+        let media = metadata.media.unwrap_or_default();
+        if kart.media != media {
+            mismatches.push(mismatch(
+                &token.token_id,
+                "media",
+                kart.media.clone(),
+                media,
+            ));
+        }
+
+        if kart.level != nk.level {
+            mismatches.push(mismatch(
+                &token.token_id,
+                "level",
+                kart.level.to_string(),
+                nk.level.to_string(),
+            ));
+        }
+
+        let projected_decals: BTreeSet<String> =
+            projection.decals(&token.token_id)?.into_iter().collect();
+        let snapshot_decals: BTreeSet<String> = nk
+            .extra1
+            .split(",")
+            .filter(|decal| !decal.is_empty())
+            .map(|decal| decal.to_string())
+            .collect();
+        if projected_decals != snapshot_decals {
+            mismatches.push(mismatch(
+                &token.token_id,
+                "decals",
+                join(&projected_decals),
+                join(&snapshot_decals),
+            ));
+        }
+    }
+
+    for kart in projection.karts()? {
+        if !seen.contains(&kart.token_id) {
+            mismatches.push(mismatch(
+                &kart.token_id,
+                "token",
+                "present".to_string(),
+                "missing".to_string(),
+            ));
+        }
+    }
+
+    Ok(mismatches)
+}
+
+fn join(decals: &BTreeSet<String>) -> String {
+    decals.iter().cloned().collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::read_fixture;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/receipts.jsonl");
+    const CID: &str = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+
+    fn indexed() -> Projection {
+        let receipts = read_fixture(FIXTURE.as_bytes()).unwrap();
+        let mut projection = Projection::open_in_memory().unwrap();
+        projection.index(&receipts, Some("karts.near")).unwrap();
+        projection
+    }
+
+    fn snapshot_token(owner_id: &str, level: u32, decals: &str) -> SnapshotToken {
+        let mut nk = NearKart::new();
+        nk.level = level;
+        nk.extra1 = decals.to_string();
+
+        SnapshotToken {
+            token_id: "megakart".to_string(),
+            owner_id: owner_id.to_string(),
+            metadata: Some(SnapshotMetadata {
+                title: Some("Turbo Kart".to_string()),
+                media: Some(CID.to_string()),
+                extra: Some(nk.serialize()),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_reconcile_in_sync() {
+        let projection = indexed();
+        let snapshot = vec![snapshot_token("bob.near", 3, "7,3")];
+
+        assert_eq!(reconcile(&projection, &snapshot).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_reconcile_reports_drift() {
+        let projection = indexed();
+        let snapshot = vec![snapshot_token("alice.near", 4, "7,3,5")];
+
+        let fields: Vec<String> = reconcile(&projection, &snapshot)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.field)
+            .collect();
+        assert_eq!(fields, vec!["owner_id", "level", "decals"]);
+    }
+
+    #[test]
+    fn test_reconcile_reports_missing_tokens() {
+        let projection = indexed();
+        let mut unknown = snapshot_token("bob.near", 1, "7");
+        unknown.token_id = "newkart".to_string();
+
+        let mismatches = reconcile(&projection, &[unknown]).unwrap();
+        assert_eq!(
+            mismatches,
+            vec![
+                mismatch("newkart", "token", "missing".into(), "present".into()),
+                mismatch("megakart", "token", "present".into(), "missing".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_snapshot() {
+        let json = r#"[{"token_id":"megakart","owner_id":"bob.near","metadata":{"title":"MegaKart","description":"NEAR Karts Series 1","media":"x","extra":""},"approved_account_ids":{}}]"#;
+        let snapshot = read_snapshot(json).unwrap();
+
+        assert_eq!(snapshot[0].owner_id, "bob.near");
+        assert_eq!(
+            snapshot[0].metadata.as_ref().unwrap().media,
+            Some("x".into())
+        );
+    }
+}
@@ -0,0 +1,303 @@
+/*
+Achievements and daily quests.
+NOTES:
+  - Conditions are checked after each mint, upgrade and battle. Completed achievements are
+    stored per token and per owning account, and may grant a reward to the kart that earned them.
+  - The daily quest counts the battles an account wins during a UTC day and rewards the kart
+    that completes it, once per day.
+*/
+use crate::*;
+
+pub(crate) const NANOS_PER_DAY: u64 = 86_400_000_000_000;
+const DAILY_QUEST_WINS: u32 = 3;
+const DAILY_QUEST_REPAIR_KITS: u32 = 1;
+const WEAPON_FLAMETHROWER: u8 = 4;
+
+pub const ACHIEVEMENT_FIRST_WIN: &str = "first_win";
+pub const ACHIEVEMENT_WIN_10: &str = "win_10";
+pub const ACHIEVEMENT_FLAMETHROWER_WIN: &str = "flamethrower_win";
+pub const ACHIEVEMENT_LEVEL_20: &str = "level_20";
+pub const ACHIEVEMENT_ALL_DECALS: &str = "all_decals";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub reward_repair_kits: u32,
+}
+
+#[derive(Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct DailyQuest {
+    pub day: u64,
+    pub wins: u32,
+    pub completed: bool,
+}
+
+pub enum AchievementTrigger {
+    Mint,
+    Upgrade,
+    Battle { won: bool },
+}
+
+fn achievement(id: &str, name: &str, reward_repair_kits: u32) -> Achievement {
+    Achievement {
+        id: id.to_string(),
+        name: name.to_string(),
+        reward_repair_kits: reward_repair_kits,
+    }
+}
+
+fn has_all_decals(nk: &NearKart) -> bool {
+    let unlocked: Vec<&str> = nk.extra1.split(",").collect();
+    (1..=NUM_DECALS).all(|decal| unlocked.contains(&decal.to_string().as_str()))
+}
+
+fn has_flamethrower(nk: &NearKart) -> bool {
+    nk.left == WEAPON_FLAMETHROWER || nk.right == WEAPON_FLAMETHROWER
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_achievements_catalog(&self) -> Vec<Achievement> {
+        vec![
+            achievement(ACHIEVEMENT_FIRST_WIN, "First Blood", 0),
+            achievement(ACHIEVEMENT_WIN_10, "Win 10 Battles", 1),
+            achievement(ACHIEVEMENT_FLAMETHROWER_WIN, "Feel The Burn", 0),
+            achievement(ACHIEVEMENT_LEVEL_20, "Reach Level 20", 2),
+            achievement(ACHIEVEMENT_ALL_DECALS, "Unlock All Decals", 3),
+        ]
+    }
+
+    pub fn get_token_achievements(&self, token_id: TokenId) -> Vec<String> {
+        return self
+            .achievements_by_token
+            .get(&token_id)
+            .unwrap_or(Vec::new());
+    }
+
+    pub fn get_account_achievements(&self, account_id: ValidAccountId) -> Vec<String> {
+        return self
+            .achievements_by_account
+            .get(&account_id.to_string())
+            .unwrap_or(Vec::new());
+    }
+
+    pub fn get_kart_wins(&self, token_id: TokenId) -> u32 {
+        return self.kart_wins.get(&token_id).unwrap_or(0);
+    }
+
+    pub fn get_daily_quest(&self, account_id: ValidAccountId) -> DailyQuest {
+        return self.daily_quest_for(&account_id.to_string());
+    }
+}
+
+impl Contract {
+    fn daily_quest_for(&self, account_id: &AccountId) -> DailyQuest {
+        let today = env::block_timestamp() / NANOS_PER_DAY;
+        let quest = self.daily_quests.get(account_id);
+
+        match quest {
+            Some(quest) if quest.day == today => quest,
+            _ => DailyQuest {
+                day: today,
+                wins: 0,
+                completed: false,
+            },
+        }
+    }
+
+    /// Record progress for the trigger and grant any newly completed achievements.
+    pub(crate) fn check_achievements(&mut self, token_id: &TokenId, trigger: AchievementTrigger) {
+        let owner_id = match self.token_owner(token_id.clone()) {
+            Some(owner_id) => owner_id,
+            None => return,
+        };
+        let nk = self.near_kart_get_config(token_id.clone());
+        let mut completed: Vec<&str> = Vec::new();
+
+        match trigger {
+            AchievementTrigger::Battle { won: true } => {
+                let wins = self.get_kart_wins(token_id.clone()) + 1;
+                self.kart_wins.insert(token_id, &wins);
+
+                completed.push(ACHIEVEMENT_FIRST_WIN);
+                if wins >= 10 {
+                    completed.push(ACHIEVEMENT_WIN_10);
+                }
+                if has_flamethrower(&nk) {
+                    completed.push(ACHIEVEMENT_FLAMETHROWER_WIN);
+                }
+
+                self.record_daily_win(&owner_id, token_id);
+            }
+            AchievementTrigger::Battle { won: false } => {}
+            AchievementTrigger::Mint | AchievementTrigger::Upgrade => {}
+        }
+
+        if nk.level >= 20 {
+            completed.push(ACHIEVEMENT_LEVEL_20);
+        }
+        if has_all_decals(&nk) {
+            completed.push(ACHIEVEMENT_ALL_DECALS);
+        }
+
+        for achievement_id in completed {
+            self.grant_achievement(&owner_id, token_id, achievement_id);
+        }
+    }
+
+    fn grant_achievement(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &TokenId,
+        achievement_id: &str,
+    ) {
+        let mut token_achievements = self.get_token_achievements(token_id.clone());
+        if token_achievements.iter().any(|id| id == achievement_id) {
+            return;
+        }
+        token_achievements.push(achievement_id.to_string());
+        self.achievements_by_token
+            .insert(token_id, &token_achievements);
+
+        let mut account_achievements = self
+            .achievements_by_account
+            .get(owner_id)
+            .unwrap_or(Vec::new());
+        if !account_achievements.iter().any(|id| id == achievement_id) {
+            account_achievements.push(achievement_id.to_string());
+            self.achievements_by_account
+                .insert(owner_id, &account_achievements);
+        }
+
+        let catalog = self.get_achievements_catalog();
+        let reward_repair_kits = catalog
+            .iter()
+            .find(|a| a.id == achievement_id)
+            .map(|a| a.reward_repair_kits)
+            .unwrap_or(0);
+        self.grant_repair_kits(token_id, reward_repair_kits);
+
+        EventLog::new(EventLogVariant::AchievementUnlocked(vec![AchievementLog {
+            owner_id: owner_id.to_string(),
+            token_id: token_id.to_string(),
+            achievement_id: achievement_id.to_string(),
+            reward_repair_kits: reward_repair_kits,
+        }]))
+        .emit();
+    }
+
+    fn record_daily_win(&mut self, owner_id: &AccountId, token_id: &TokenId) {
+        let mut quest = self.daily_quest_for(owner_id);
+
+        quest.wins = quest.wins + 1;
+
+        if !quest.completed && quest.wins >= DAILY_QUEST_WINS {
+            quest.completed = true;
+            self.grant_repair_kits(token_id, DAILY_QUEST_REPAIR_KITS);
+
+            EventLog::new(EventLogVariant::DailyQuestCompleted(vec![DailyQuestLog {
+                owner_id: owner_id.to_string(),
+                token_id: token_id.to_string(),
+                day: quest.day,
+                reward_repair_kits: DAILY_QUEST_REPAIR_KITS,
+            }]))
+            .emit();
+        }
+
+        self.daily_quests.insert(owner_id, &quest);
+    }
+
+    pub(crate) fn grant_repair_kits(&mut self, token_id: &TokenId, repair_kits: u32) {
+        if repair_kits == 0 {
+            return;
+        }
+        let mut nk = self.near_kart_get_config(token_id.clone());
+        nk.repair_kits = nk.repair_kits + repair_kits;
+        self.near_kart_set_config(token_id.clone(), &nk);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::get_logs;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_contract() -> (Contract, ValidAccountId) {
+        let (contract, _) =
+            setup_with_karts(&[("megakart", owner_id()), ("fluffykart", owner_id())]);
+        return (contract, owner_id());
+    }
+
+    #[test]
+    fn test_has_all_decals() {
+        let mut nk = NearKart::new();
+        nk.extra1 = String::from("7,3,1,2,6");
+        assert!(!has_all_decals(&nk));
+        nk.extra1 = String::from("7,3,1,2,6,5,4");
+        assert!(has_all_decals(&nk));
+    }
+
+    #[test]
+    fn test_no_achievements_on_mint() {
+        let (contract, br_acc) = setup_contract();
+
+        assert_eq!(
+            contract
+                .get_token_achievements("megakart".to_string())
+                .len(),
+            0
+        );
+        assert_eq!(contract.get_account_achievements(br_acc).len(), 0);
+    }
+
+    #[test]
+    fn test_battle_achievements() {
+        let (mut contract, br_acc) = setup_contract();
+        let token_id = "megakart".to_string();
+
+        let mut won = 0;
+        while won < 10 {
+            let result = contract.game_simple_battle(token_id.clone());
+            if result.winner == 0 {
+                won += 1;
+            }
+        }
+
+        assert_eq!(contract.get_kart_wins(token_id.clone()), 10);
+
+        let token_achievements = contract.get_token_achievements(token_id.clone());
+        assert!(token_achievements.contains(&ACHIEVEMENT_FIRST_WIN.to_string()));
+        assert!(token_achievements.contains(&ACHIEVEMENT_WIN_10.to_string()));
+        assert!(!token_achievements.contains(&ACHIEVEMENT_FLAMETHROWER_WIN.to_string()));
+
+        let account_achievements = contract.get_account_achievements(br_acc.clone());
+        assert!(account_achievements.contains(&ACHIEVEMENT_WIN_10.to_string()));
+
+        let quest = contract.get_daily_quest(br_acc);
+        assert_eq!(quest.wins, 10);
+        assert!(quest.completed);
+    }
+
+    #[test]
+    fn test_achievement_event() {
+        let (mut contract, _) = setup_contract();
+        let token_id = "megakart".to_string();
+
+        let mut result = contract.game_simple_battle(token_id.clone());
+        while result.winner != 0 {
+            result = contract.game_simple_battle(token_id.clone());
+        }
+
+        let logs = get_logs();
+        let event = logs
+            .iter()
+            .find(|l| l.contains("achievement_unlocked"))
+            .unwrap();
+        assert!(event.starts_with("EVENT_JSON:"));
+        assert!(event.contains("\"standard\":\"nearkarts\""));
+        assert!(event.contains("\"achievement_id\":\"first_win\""));
+    }
+}
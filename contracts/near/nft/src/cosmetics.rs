@@ -0,0 +1,265 @@
+/*
+Kart cosmetics.
+NOTES:
+  - The palette and finish catalog is `PALETTES` / `FINISHES` in nearkarts-types, so the web and
+    `validate_equip` check paint the same way the contract does.
+  - Unlocks belong to the kart and move with it on transfer. Unlocked palettes are kept in the
+    kart's `extra2`, owned finishes in `ex2` and the finish it is painted with in `ex1`.
+  - Palettes and finishes are bought with NEAR at the `palette` and `finish` prices. The kart
+    uses them from its next configure or upgrade. A new kart is minted in the standard palette
+    and finish, see `validate_mint_paint`.
+*/
+use crate::*;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PaletteView {
+    pub index: u8,
+    pub name: String,
+    pub colors: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FinishView {
+    pub index: u8,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CosmeticsCatalog {
+    pub palettes: Vec<PaletteView>,
+    pub finishes: Vec<FinishView>,
+    pub palette_price: U128,
+    pub finish_price: U128,
+}
+
+/// Palettes and finishes a kart can use, the standard ones included.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KartCosmetics {
+    pub palettes: Vec<u8>,
+    pub finishes: Vec<u8>,
+    pub finish: u8,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn buy_palette(&mut self, token_id: TokenId, palette: u8) {
+        self.assert_nft_owner(token_id.clone());
+        if palette >= NUM_PALETTES {
+            panic_error(KartError::PaletteIndexTooHigh);
+        }
+        if env::attached_deposit() < self.get_prices().palette.0 {
+            panic_error(KartError::CosmeticPaymentTooLow);
+        }
+
+        let mut nk = self.near_kart_get_config(token_id.clone());
+        if !nk.unlock_palette(palette) {
+            panic_error(KartError::PaletteAlreadyUnlocked);
+        }
+        self.near_kart_set_config(token_id.clone(), &nk);
+
+        self.emit_cosmetic_unlock(
+            token_id,
+            "palette",
+            palette,
+            PALETTES[palette as usize].name,
+        );
+    }
+
+    #[payable]
+    pub fn buy_finish(&mut self, token_id: TokenId, finish: u8) {
+        self.assert_nft_owner(token_id.clone());
+        if finish >= NUM_FINISHES {
+            panic_error(KartError::FinishIndexTooHigh);
+        }
+        if env::attached_deposit() < self.get_prices().finish.0 {
+            panic_error(KartError::CosmeticPaymentTooLow);
+        }
+
+        let mut nk = self.near_kart_get_config(token_id.clone());
+        if !nk.unlock_finish(finish) {
+            panic_error(KartError::FinishAlreadyOwned);
+        }
+        self.near_kart_set_config(token_id.clone(), &nk);
+
+        self.emit_cosmetic_unlock(token_id, "finish", finish, FINISHES[finish as usize]);
+    }
+
+    pub fn get_cosmetics_catalog(&self) -> CosmeticsCatalog {
+        let palettes = PALETTES
+            .iter()
+            .enumerate()
+            .map(|(i, palette)| PaletteView {
+                index: i as u8,
+                name: palette.name.to_string(),
+                colors: palette.colors.to_vec(),
+            })
+            .collect();
+        let finishes = FINISHES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| FinishView {
+                index: i as u8,
+                name: name.to_string(),
+            })
+            .collect();
+
+        let prices = self.get_prices();
+        return CosmeticsCatalog {
+            palettes: palettes,
+            finishes: finishes,
+            palette_price: prices.palette,
+            finish_price: prices.finish,
+        };
+    }
+
+    pub fn kart_cosmetics(&self, token_id: TokenId) -> KartCosmetics {
+        let nk = self.near_kart_get_config(token_id);
+        return KartCosmetics {
+            palettes: (0..NUM_PALETTES).filter(|i| nk.has_palette(*i)).collect(),
+            finishes: (0..NUM_FINISHES).filter(|i| nk.has_finish(*i)).collect(),
+            finish: nk.ex1,
+        };
+    }
+}
+
+impl Contract {
+    fn emit_cosmetic_unlock(&self, token_id: TokenId, kind: &str, index: u8, name: &str) {
+        EventLog::new(EventLogVariant::CosmeticUnlock(vec![CosmeticUnlockLog {
+            owner_id: env::predecessor_account_id(),
+            token_id: token_id,
+            kind: kind.to_string(),
+            index: index,
+            name: name.to_string(),
+        }]))
+        .emit();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_contract() -> (Contract, VMContextBuilder) {
+        let (contract, mut context) = setup_with_karts(&[("megakart", owner_id())]);
+        set_caller(&mut context, owner_id(), DEFAULT_FINISH_PRICE);
+        return (contract, context);
+    }
+
+    fn upgrade(contract: &mut Contract, nk: NearKart) {
+        let mut unlocked = contract.near_kart_get_config("megakart".to_string());
+        unlocked.locked = false;
+        contract.near_kart_set_config("megakart".to_string(), &unlocked);
+
+        contract.upgrade(
+            "megakart".to_string(),
+            nk,
+            TEST_CID.to_string(),
+            media_hash(),
+            TEST_SIG.to_string(),
+            TEST_PUB_KEY.to_string(),
+        );
+    }
+
+    #[test]
+    fn test_buy_palette_and_finish() {
+        let (mut contract, _) = setup_contract();
+
+        let cosmetics = contract.kart_cosmetics("megakart".to_string());
+        assert_eq!(cosmetics.palettes, vec![STANDARD_PALETTE]);
+        assert_eq!(cosmetics.finishes, vec![STANDARD_FINISH]);
+
+        contract.buy_palette("megakart".to_string(), 2);
+        contract.buy_finish("megakart".to_string(), 3);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"cosmetic_unlock""#)
+                && log.contains(r#""name":"animated""#)));
+
+        let cosmetics = contract.kart_cosmetics("megakart".to_string());
+        assert_eq!(cosmetics.palettes, vec![0, 2]);
+        assert_eq!(cosmetics.finishes, vec![0, 3]);
+        assert_eq!(cosmetics.finish, STANDARD_FINISH);
+    }
+
+    #[test]
+    fn test_upgrade_with_unlocked_paint() {
+        let (mut contract, _) = setup_contract();
+        contract.buy_palette("megakart".to_string(), 1);
+        contract.buy_finish("megakart".to_string(), 1);
+
+        let mut nk = contract.near_kart_get_config("megakart".to_string());
+        nk.color1 = PALETTES[1].colors[0];
+        nk.ex1 = 1;
+        upgrade(&mut contract, nk);
+
+        let nk = contract.near_kart_get_config("megakart".to_string());
+        assert_eq!(nk.color1, PALETTES[1].colors[0]);
+        assert_eq!(contract.kart_cosmetics("megakart".to_string()).finish, 1);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:222:The primary color is not in a palette the kart unlocked"
+    )]
+    fn test_upgrade_with_locked_color() {
+        let (mut contract, _) = setup_contract();
+
+        let mut nk = contract.near_kart_get_config("megakart".to_string());
+        nk.color1 = PALETTES[1].colors[0];
+        upgrade(&mut contract, nk);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:223:The secondary color is not in a palette the kart unlocked"
+    )]
+    fn test_mint_with_locked_color() {
+        let (mut contract, _) = setup_contract();
+
+        let mut nk = NearKart::new();
+        nk.color2 = PALETTES[3].colors[0];
+        contract.nft_mint(
+            "fluffykart".to_string(),
+            owner_id(),
+            "Fluffy Kart".to_string(),
+            nk,
+            TEST_CID.to_string(),
+            media_hash(),
+            TEST_SIG.to_string(),
+            TEST_PUB_KEY.to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:227:The kart already unlocked this palette")]
+    fn test_buy_standard_palette() {
+        let (mut contract, _) = setup_contract();
+        contract.buy_palette("megakart".to_string(), STANDARD_PALETTE);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:409:Attached deposit is below the price of the palette or finish"
+    )]
+    fn test_buy_finish_payment_too_low() {
+        let (mut contract, mut context) = setup_contract();
+
+        testing_env!(context.attached_deposit(DEFAULT_PALETTE_PRICE).build());
+        contract.buy_finish("megakart".to_string(), 1);
+    }
+
+    #[test]
+    fn test_catalog() {
+        let (contract, _) = setup_contract();
+
+        let catalog = contract.get_cosmetics_catalog();
+        assert_eq!(catalog.palettes.len(), NUM_PALETTES as usize);
+        assert_eq!(catalog.finishes[1].name, "metallic");
+        assert_eq!(catalog.finish_price.0, DEFAULT_FINISH_PRICE);
+    }
+}
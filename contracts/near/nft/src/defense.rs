@@ -0,0 +1,235 @@
+/*
+Defending karts.
+NOTES:
+  - A battle against another account's kart is a defense for the away kart. Battles against an
+    own kart, an NPC or in a guild team battle are not.
+  - A defending kart that wins gets `DEFENSE_REPAIR_KITS`, at most once per UTC day so two
+    accounts can't farm kits off each other. The season rating already moves for both karts.
+  - An attacker that loses a defense loses `DEFENSE_ENERGY_PENALTY` energy, so it waits longer
+    for its next battle since battles cost energy (see energy.rs), and wears a second time on top
+    of the wear of the battle.
+  - Each kart keeps its last `MAX_DEFENSE_LOG` defenses for `defense_log`, and every defense emits
+    a `defense` event with the defending owner, so players see what happened while offline.
+*/
+use crate::*;
+use near_sdk::json_types::U64;
+
+pub const DEFENSE_REPAIR_KITS: u32 = 1;
+pub const DEFENSE_ENERGY_PENALTY: u32 = 2;
+pub const MAX_DEFENSE_LOG: usize = 20;
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct DefenseRecord {
+    pub battle_id: u64,
+    pub attacker_token_id: TokenId,
+    /// The defending kart won.
+    pub defended: bool,
+    pub reward_repair_kits: u32,
+    pub attacker_energy_lost: u32,
+    pub timestamp: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Last defenses of a kart, newest first.
+    pub fn defense_log(&self, token_id: TokenId) -> Vec<DefenseRecord> {
+        let mut log = self.defense_logs.get(&token_id).unwrap_or(Vec::new());
+        log.reverse();
+        return log;
+    }
+}
+
+impl Contract {
+    /// Settle a battle for the away kart, if it defended against another account.
+    pub(crate) fn record_defense(&mut self, battle_id: u64, result: &SimpleBattle) {
+        let owner_id = match self.token_owner(result.away_token_id.clone()) {
+            Some(owner_id) => owner_id,
+            None => return, // NPC
+        };
+        if Some(&owner_id) == self.token_owner(result.home_token_id.clone()).as_ref() {
+            return;
+        }
+
+        let defended = result.winner == 1;
+        let mut reward_repair_kits = 0;
+        let mut attacker_energy_lost = 0;
+        if defended {
+            // Losing to a defender costs the attacker energy and extra wear
+            attacker_energy_lost = self.drain_energy(&result.home_token_id, DEFENSE_ENERGY_PENALTY);
+            self.wear_kart(result.home_token_id.clone());
+
+            let today = env::block_timestamp() / NANOS_PER_DAY;
+            if self.defense_reward_days.get(&result.away_token_id) != Some(today) {
+                self.defense_reward_days
+                    .insert(&result.away_token_id, &today);
+                reward_repair_kits = DEFENSE_REPAIR_KITS;
+                self.grant_repair_kits(&result.away_token_id, reward_repair_kits);
+            }
+        }
+
+        let mut log = self
+            .defense_logs
+            .get(&result.away_token_id)
+            .unwrap_or(Vec::new());
+        if log.len() >= MAX_DEFENSE_LOG {
+            log.remove(0);
+        }
+        log.push(DefenseRecord {
+            battle_id: battle_id,
+            attacker_token_id: result.home_token_id.clone(),
+            defended: defended,
+            reward_repair_kits: reward_repair_kits,
+            attacker_energy_lost: attacker_energy_lost,
+            timestamp: U64(env::block_timestamp()),
+        });
+        self.defense_logs.insert(&result.away_token_id, &log);
+
+        EventLog::new(EventLogVariant::Defense(vec![DefenseLog {
+            owner_id: owner_id,
+            token_id: result.away_token_id.clone(),
+            attacker_token_id: result.home_token_id.clone(),
+            battle_id: battle_id,
+            defended: defended,
+            reward_repair_kits: reward_repair_kits,
+            attacker_energy_lost: attacker_energy_lost,
+        }]))
+        .emit();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Attacking kart "0" owned by accounts(1) and defending kart "1" owned by accounts(2),
+    /// calling as accounts(1).
+    fn setup_contract() -> (Contract, VMContextBuilder) {
+        let (contract, mut context) = setup_with_karts(&[("0", accounts(1)), ("1", accounts(2))]);
+        set_caller(&mut context, accounts(1), 0);
+        return (contract, context);
+    }
+
+    fn battle(winner: u8) -> SimpleBattle {
+        SimpleBattle {
+            home_token_id: "0".to_string(),
+            away_token_id: "1".to_string(),
+            winner: winner,
+            battle: 1234,
+            prize: "0".to_string(),
+            extra: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_battle_is_logged_for_defender() {
+        let (mut contract, _) = setup_contract();
+
+        let result = contract.game_simple_battle("0".to_string());
+        assert_eq!(result.away_token_id, "1");
+
+        let log = contract.defense_log("1".to_string());
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].attacker_token_id, "0");
+        assert_eq!(log[0].defended, result.winner == 1);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"defense""#)
+                && log.contains(&format!(r#""owner_id":"{}""#, accounts(2)))));
+    }
+
+    #[test]
+    fn test_defender_reward_once_a_day() {
+        let (mut contract, mut context) = setup_contract();
+
+        contract.record_defense(0, &battle(1));
+        contract.record_defense(1, &battle(1));
+        assert_eq!(
+            contract.near_kart_get_config("1".to_string()).repair_kits,
+            1
+        );
+
+        testing_env!(context.block_timestamp(NANOS_PER_DAY).build());
+        contract.record_defense(2, &battle(1));
+        assert_eq!(
+            contract.near_kart_get_config("1".to_string()).repair_kits,
+            2
+        );
+
+        let log = contract.defense_log("1".to_string());
+        assert_eq!(log[0].battle_id, 2);
+        assert_eq!(log[0].reward_repair_kits, 1);
+        assert_eq!(log[1].reward_repair_kits, 0);
+    }
+
+    #[test]
+    fn test_losing_attacker_wears_again() {
+        let (mut contract, _) = setup_contract();
+
+        contract.record_defense(0, &battle(1));
+        let nk = contract.near_kart_get_config("0".to_string());
+        assert_eq!(nk.dur_skin, MAX_DURABILITY - SKIN_WEAR[0]);
+        assert_eq!(
+            contract.get_kart_energy("0".to_string()),
+            MAX_ENERGY - DEFENSE_ENERGY_PENALTY
+        );
+        assert_eq!(
+            contract.defense_log("1".to_string())[0].attacker_energy_lost,
+            DEFENSE_ENERGY_PENALTY
+        );
+
+        // A won attack costs nothing extra
+        contract.record_defense(1, &battle(0));
+        let nk = contract.near_kart_get_config("0".to_string());
+        assert_eq!(nk.dur_skin, MAX_DURABILITY - SKIN_WEAR[0]);
+        assert_eq!(
+            contract.get_kart_energy("0".to_string()),
+            MAX_ENERGY - DEFENSE_ENERGY_PENALTY
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:230:The kart has no energy left to battle")]
+    fn test_losing_attacker_waits_for_energy() {
+        let (mut contract, _) = setup_contract();
+        contract.drain_energy(&"0".to_string(), MAX_ENERGY - DEFENSE_ENERGY_PENALTY);
+
+        // The energy left for the next battle goes to the defense penalty
+        contract.record_defense(0, &battle(1));
+        contract.game_simple_battle("0".to_string());
+    }
+
+    #[test]
+    fn test_own_karts_do_not_defend() {
+        let (mut contract, mut context) = setup_contract();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(1), "1".to_string(), None, None);
+
+        contract.record_defense(0, &battle(1));
+        assert!(contract.defense_log("1".to_string()).is_empty());
+        assert_eq!(
+            contract.near_kart_get_config("1".to_string()).repair_kits,
+            0
+        );
+    }
+
+    #[test]
+    fn test_defense_log_is_capped() {
+        let (mut contract, _) = setup_contract();
+
+        for battle_id in 0..(MAX_DEFENSE_LOG as u64 + 5) {
+            contract.record_defense(battle_id, &battle(0));
+        }
+
+        let log = contract.defense_log("1".to_string());
+        assert_eq!(log.len(), MAX_DEFENSE_LOG);
+        assert_eq!(log[0].battle_id, MAX_DEFENSE_LOG as u64 + 4);
+    }
+}
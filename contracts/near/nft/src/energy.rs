@@ -0,0 +1,141 @@
+/*
+Kart energy.
+NOTES:
+  - Energy is read only for players, shown by `get_kart_energy` and in `KartView`. Every battle
+    costs the home kart `BATTLE_ENERGY_COST`, a kart without energy can't battle, which is what
+    makes the energy an attacker loses to a defender (`drain_energy`) a penalty. Energy comes
+    back one point per `ENERGY_REGEN_INTERVAL` up to `MAX_ENERGY`.
+  - A kart that never spent energy has no entry and is at `MAX_ENERGY`. Regeneration is worked
+    out when energy is read, `updated_at` keeps the time of the last point regained so partial
+    intervals are not lost.
+  - Energy is kept by the contract per token, not in the kart config, so it can't be set through
+    configure or upgrade.
+*/
+use crate::*;
+
+pub const MAX_ENERGY: u32 = 30;
+pub const BATTLE_ENERGY_COST: u32 = 1;
+pub const ENERGY_REGEN_INTERVAL: u64 = 1_200_000_000_000;
+
+#[derive(Clone, BorshSerialize, BorshDeserialize, Debug)]
+pub struct KartEnergy {
+    pub energy: u32,
+    pub updated_at: u64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Energy the kart has now, regeneration included.
+    pub fn get_kart_energy(&self, token_id: TokenId) -> u32 {
+        return self.current_energy(&token_id).energy;
+    }
+}
+
+impl Contract {
+    fn current_energy(&self, token_id: &TokenId) -> KartEnergy {
+        let now = env::block_timestamp();
+        let stored = match self.kart_energy.get(token_id) {
+            Some(stored) => stored,
+            None => {
+                return KartEnergy {
+                    energy: MAX_ENERGY,
+                    updated_at: now,
+                }
+            }
+        };
+
+        let regained = now.saturating_sub(stored.updated_at) / ENERGY_REGEN_INTERVAL;
+        let energy = cmp::min(stored.energy as u64 + regained, MAX_ENERGY as u64) as u32;
+        let updated_at = if energy == MAX_ENERGY {
+            now
+        } else {
+            stored.updated_at + regained * ENERGY_REGEN_INTERVAL
+        };
+
+        return KartEnergy {
+            energy: energy,
+            updated_at: updated_at,
+        };
+    }
+
+    /// Spend energy for a battle, panics when the kart has too little.
+    pub(crate) fn use_energy(&mut self, token_id: &TokenId, amount: u32) {
+        let mut energy = self.current_energy(token_id);
+        if energy.energy < amount {
+            panic_error(KartError::KartOutOfEnergy);
+        }
+
+        energy.energy = energy.energy - amount;
+        self.kart_energy.insert(token_id, &energy);
+    }
+
+    /// Take up to `amount` energy from the kart. Returns the energy taken.
+    pub(crate) fn drain_energy(&mut self, token_id: &TokenId, amount: u32) -> u32 {
+        let mut energy = self.current_energy(token_id);
+        let drained = cmp::min(energy.energy, amount);
+
+        energy.energy = energy.energy - drained;
+        self.kart_energy.insert(token_id, &energy);
+        return drained;
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_contract() -> (Contract, VMContextBuilder) {
+        return setup_with_karts(&[("megakart", owner_id())]);
+    }
+
+    #[test]
+    fn test_battle_uses_energy() {
+        let (mut contract, _) = setup_contract();
+        assert_eq!(contract.get_kart_energy("megakart".to_string()), MAX_ENERGY);
+
+        contract.game_simple_battle("megakart".to_string());
+        assert_eq!(
+            contract.get_kart_energy("megakart".to_string()),
+            MAX_ENERGY - BATTLE_ENERGY_COST
+        );
+    }
+
+    #[test]
+    fn test_energy_regenerates() {
+        let (mut contract, mut context) = setup_contract();
+        let token_id = "megakart".to_string();
+
+        testing_env!(context.block_timestamp(1_000).build());
+        assert_eq!(contract.drain_energy(&token_id, 5), 5);
+
+        // Half an interval later nothing is back yet, and the half isn't lost on the next drain
+        testing_env!(context
+            .block_timestamp(1_000 + ENERGY_REGEN_INTERVAL / 2)
+            .build());
+        assert_eq!(contract.drain_energy(&token_id, 1), 1);
+        assert_eq!(contract.get_kart_energy(token_id.clone()), MAX_ENERGY - 6);
+
+        testing_env!(context
+            .block_timestamp(1_000 + ENERGY_REGEN_INTERVAL * 2)
+            .build());
+        assert_eq!(contract.get_kart_energy(token_id.clone()), MAX_ENERGY - 4);
+
+        testing_env!(context
+            .block_timestamp(1_000 + ENERGY_REGEN_INTERVAL * 100)
+            .build());
+        assert_eq!(contract.get_kart_energy(token_id), MAX_ENERGY);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:230:The kart has no energy left to battle")]
+    fn test_battle_without_energy() {
+        let (mut contract, _) = setup_contract();
+        contract.drain_energy(&"megakart".to_string(), MAX_ENERGY);
+
+        contract.game_simple_battle("megakart".to_string());
+    }
+}
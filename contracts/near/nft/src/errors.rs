@@ -0,0 +1,51 @@
+/*
+Contract errors.
+NOTES:
+  - `KartError` lives in `nearkarts-types` with its stable numeric and string codes, so clients
+    and off-chain tools share them with the contract.
+  - Every failure of the contract's own methods goes through `panic_error`, which panics with
+    `KART_ERR:<code>:<message>`. Clients parse the code with `KartError::from_panic_message`
+    (or the regex `KART_ERR:(\d+):`) and look its `id` up in `get_error_catalog` to show
+    localized text, falling back to the message.
+  - Panics raised inside near-contract-standards (approvals, transfer receivers) and
+    `assert_one_yocto` keep their library messages.
+*/
+use crate::*;
+
+pub fn panic_error(error: KartError) -> ! {
+    env::panic(error.panic_message().as_bytes())
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Every error the contract panics with, ordered by code.
+    pub fn get_error_catalog(&self) -> Vec<ErrorInfo> {
+        return error_catalog();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+
+    #[test]
+    fn test_error_catalog() {
+        testing_env!(VMContextBuilder::new().build());
+        let contract = Contract::new_default_meta(accounts(0));
+
+        let catalog = contract.get_error_catalog();
+        assert_eq!(catalog.len(), KartError::ALL.len());
+        assert_eq!(catalog[0].code, 100);
+        assert_eq!(catalog[0].id, "error_token_not_found");
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:403:Mint and upgrade prices can't be zero")]
+    fn test_panic_error_format() {
+        testing_env!(VMContextBuilder::new().build());
+        panic_error(KartError::PriceZero);
+    }
+}
@@ -0,0 +1,18 @@
+/*
+NEP-297 events.
+NOTES:
+  - The event types live in `nearkarts-types` so off-chain tools parse exactly what the contract
+    logs, this module only adds the `emit` that writes them to the receipt logs.
+*/
+use crate::*;
+pub use nearkarts_types::events::*;
+
+pub trait EmitEvent {
+    fn emit(&self);
+}
+
+impl EmitEvent for EventLog {
+    fn emit(&self) {
+        log!("{}", &self.to_string());
+    }
+}
@@ -0,0 +1,793 @@
+/*
+Guilds and team battles.
+NOTES:
+  - Any account not in a guild can create one and becomes its leader. Officers invite accounts,
+    which join by accepting. The leader is always an officer, and an account is in one guild
+    at most.
+  - Members register up to `MAX_GUILD_ROSTER` of their own karts into the guild roster. A kart
+    leaves the roster when it changes owner, is burned or its owner leaves the guild.
+  - An officer challenges another guild to a best-of-N team battle, staking a wager from the
+    guild treasury. When an officer of the other guild accepts, staking the same wager, rounds
+    are fought kart by kart through the roster order with the battle roll of
+    `game_simple_battle`, until one guild has won the majority. The winner's treasury takes
+    both wagers and the guild ratings move by `GUILD_RATING_STEP`.
+  - Team rounds wear both karts but grant no loot, levels or season rating. Each round is kept
+    as a battle, so `battle_with_karts` shows it.
+  - Treasuries are funded with `deposit_guild_treasury` and wager winnings. Officers pay out
+    through proposals, which execute once a majority of the officers approved them. Each
+    proposal keeps the officers it was made with, so promoting or demoting officers while it
+    is open doesn't change who has to approve it.
+  - NEAR held for guilds is counted in `guild_funds` and is not part of the owner treasury.
+  - Guilds are kept sorted by rating, then wins in `guild_standings` as challenges resolve, so
+    `guild_leaderboard` pages through them without loading every guild.
+*/
+use crate::*;
+
+pub const MAX_GUILD_MEMBERS: usize = 30;
+pub const MAX_GUILD_ROSTER: usize = 5;
+pub const MAX_GUILD_BEST_OF: u8 = 9;
+pub const MAX_GUILD_NAME_LENGTH: usize = 32;
+const GUILD_RATING_STEP: u32 = 16;
+
+/// Orders guild standings best first: highest rating, then most wins, then oldest guild.
+pub type GuildStandingKey = (u32, u32, u64);
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct Guild {
+    pub id: u64,
+    pub name: String,
+    pub leader_id: AccountId,
+    pub officers: Vec<AccountId>,
+    pub members: Vec<AccountId>,
+    pub roster: Vec<TokenId>,
+    pub treasury: U128,
+    pub rating: u32,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+impl Guild {
+    fn is_officer(&self, account_id: &AccountId) -> bool {
+        self.officers.contains(account_id)
+    }
+}
+
+#[derive(
+    Clone, Copy, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GuildChallengeStatus {
+    Open,
+    Resolved,
+    Declined,
+}
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct GuildChallenge {
+    pub id: u64,
+    pub challenger_id: u64,
+    pub defender_id: u64,
+    pub best_of: u8,
+    pub wager: U128,
+    pub status: GuildChallengeStatus,
+    pub winner_id: Option<u64>,
+    /// Battle ids of the rounds, see `battle_with_karts`.
+    pub battle_ids: Vec<u64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct GuildProposal {
+    pub id: u64,
+    pub guild_id: u64,
+    pub proposer_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+    pub officers: Vec<AccountId>,
+    pub approvals: Vec<AccountId>,
+    pub executed: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GuildStanding {
+    pub rank: u32,
+    pub guild_id: u64,
+    pub name: String,
+    pub rating: u32,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn create_guild(&mut self, name: String) -> Guild {
+        let account_id = env::predecessor_account_id();
+        self.assert_not_in_guild(&account_id);
+
+        let name = name.trim().to_string();
+        if name.is_empty() || name.len() > MAX_GUILD_NAME_LENGTH {
+            panic_error(KartError::GuildNameInvalid);
+        }
+        let name_key = name.to_lowercase();
+        if self.guild_names.get(&name_key).is_some() {
+            panic_error(KartError::GuildNameTaken);
+        }
+
+        let guild = Guild {
+            id: self.guilds.len(),
+            name: name,
+            leader_id: account_id.clone(),
+            officers: vec![account_id.clone()],
+            members: vec![account_id.clone()],
+            roster: Vec::new(),
+            treasury: U128(0),
+            rating: INITIAL_RATING,
+            wins: 0,
+            losses: 0,
+        };
+        self.guilds.push(&guild);
+        self.guild_standings
+            .insert(&guild_standing_key(&guild), &guild.id);
+        self.guild_names.insert(&name_key, &guild.id);
+        self.guild_members.insert(&account_id, &guild.id);
+
+        return guild;
+    }
+
+    pub fn invite_to_guild(&mut self, guild_id: u64, account_id: ValidAccountId) {
+        let guild = self.guild(guild_id);
+        Contract::assert_guild_officer(&guild, &env::predecessor_account_id());
+
+        self.guild_invites
+            .insert(&guild_invite_key(guild_id, account_id.as_ref()));
+    }
+
+    pub fn accept_guild_invite(&mut self, guild_id: u64) {
+        let account_id = env::predecessor_account_id();
+        self.assert_not_in_guild(&account_id);
+
+        let key = guild_invite_key(guild_id, &account_id);
+        if !self.guild_invites.contains(&key) {
+            panic_error(KartError::GuildInviteNotFound);
+        }
+
+        let mut guild = self.guild(guild_id);
+        if guild.members.len() >= MAX_GUILD_MEMBERS {
+            panic_error(KartError::GuildFull);
+        }
+
+        guild.members.push(account_id.clone());
+        self.guilds.replace(guild_id, &guild);
+        self.guild_members.insert(&account_id, &guild_id);
+        self.guild_invites.remove(&key);
+    }
+
+    /// Leave the caller's guild, taking their karts off the roster.
+    pub fn leave_guild(&mut self) {
+        let account_id = env::predecessor_account_id();
+        let mut guild = self.account_guild(&account_id);
+        if guild.leader_id == account_id {
+            panic_error(KartError::GuildLeaderCannotLeave);
+        }
+
+        guild.members.retain(|id| id != &account_id);
+        guild.officers.retain(|id| id != &account_id);
+        let (kept, removed): (Vec<TokenId>, Vec<TokenId>) = guild
+            .roster
+            .into_iter()
+            .partition(|token_id| self.token_owner(token_id.clone()).as_ref() != Some(&account_id));
+        guild.roster = kept;
+        for token_id in removed.iter() {
+            self.guild_karts.remove(token_id);
+        }
+
+        self.guilds.replace(guild.id, &guild);
+        self.guild_members.remove(&account_id);
+    }
+
+    /// Make a member an officer, or take the role away. Leader only.
+    pub fn set_guild_officer(&mut self, guild_id: u64, account_id: ValidAccountId, officer: bool) {
+        let mut guild = self.guild(guild_id);
+        if env::predecessor_account_id() != guild.leader_id {
+            panic_error(KartError::NotGuildLeader);
+        }
+
+        let account_id: AccountId = account_id.into();
+        if !guild.members.contains(&account_id) {
+            panic_error(KartError::NotGuildMember);
+        }
+        if account_id == guild.leader_id {
+            return;
+        }
+
+        guild.officers.retain(|id| id != &account_id);
+        if officer {
+            guild.officers.push(account_id);
+        }
+        self.guilds.replace(guild_id, &guild);
+    }
+
+    /// Add one of the caller's karts to their guild's roster.
+    pub fn register_guild_kart(&mut self, token_id: TokenId) {
+        self.assert_nft_owner(token_id.clone());
+        let mut guild = self.account_guild(&env::predecessor_account_id());
+
+        if self.guild_karts.get(&token_id).is_some() {
+            panic_error(KartError::KartInGuildRoster);
+        }
+        if guild.roster.len() >= MAX_GUILD_ROSTER {
+            panic_error(KartError::GuildRosterFull);
+        }
+
+        guild.roster.push(token_id.clone());
+        self.guilds.replace(guild.id, &guild);
+        self.guild_karts.insert(&token_id, &guild.id);
+    }
+
+    /// Take a kart off its guild roster, by its owner or an officer of the guild.
+    pub fn unregister_guild_kart(&mut self, token_id: TokenId) {
+        let guild_id = self
+            .guild_karts
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::KartNotInGuildRoster));
+
+        let account_id = env::predecessor_account_id();
+        let guild = self.guild(guild_id);
+        if self.token_owner(token_id.clone()) != Some(account_id.clone())
+            && !guild.is_officer(&account_id)
+        {
+            panic_error(KartError::NotGuildOfficer);
+        }
+
+        self.remove_from_guild_roster(&token_id);
+    }
+
+    #[payable]
+    pub fn deposit_guild_treasury(&mut self, guild_id: u64) -> U128 {
+        let amount = env::attached_deposit();
+        if amount == 0 {
+            panic_error(KartError::GuildAmountZero);
+        }
+
+        let mut guild = self.guild(guild_id);
+        guild.treasury = U128(guild.treasury.0 + amount);
+        self.guilds.replace(guild_id, &guild);
+        self.guild_funds = self.guild_funds + amount;
+
+        return guild.treasury;
+    }
+
+    /// Challenge another guild to a best-of-`best_of` team battle.
+    ///
+    /// # Arguments
+    ///
+    /// * `guild_id` - The challenging guild, the caller must be one of its officers
+    /// * `opponent_id` - The challenged guild
+    /// * `best_of` - Odd number of rounds, up to `MAX_GUILD_BEST_OF`
+    /// * `wager` - yoctoNEAR staked from each treasury, the winner takes both
+    ///
+    pub fn challenge_guild(
+        &mut self,
+        guild_id: u64,
+        opponent_id: u64,
+        best_of: u8,
+        wager: U128,
+    ) -> GuildChallenge {
+        let account_id = self.session_or_predecessor();
+        let mut guild = self.guild(guild_id);
+        Contract::assert_guild_officer(&guild, &account_id);
+
+        if guild_id == opponent_id {
+            panic_error(KartError::GuildChallengeSelf);
+        }
+        self.guild(opponent_id);
+        if best_of == 0 || best_of % 2 == 0 || best_of > MAX_GUILD_BEST_OF {
+            panic_error(KartError::GuildBestOfInvalid);
+        }
+
+        Contract::stake_wager(&mut guild, wager.0);
+        self.guilds.replace(guild_id, &guild);
+
+        let challenge = GuildChallenge {
+            id: self.guild_challenges.len(),
+            challenger_id: guild_id,
+            defender_id: opponent_id,
+            best_of: best_of,
+            wager: wager,
+            status: GuildChallengeStatus::Open,
+            winner_id: None,
+            battle_ids: Vec::new(),
+        };
+        self.guild_challenges.push(&challenge);
+
+        return challenge;
+    }
+
+    /// Accept a challenge to the caller's guild and fight it out.
+    pub fn accept_guild_challenge(&mut self, challenge_id: u64) -> GuildChallenge {
+        let account_id = self.session_or_predecessor();
+        let mut challenge = self.open_guild_challenge(challenge_id);
+        let mut defender = self.guild(challenge.defender_id);
+        Contract::assert_guild_officer(&defender, &account_id);
+        let mut challenger = self.guild(challenge.challenger_id);
+
+        if challenger.roster.is_empty() || defender.roster.is_empty() {
+            panic_error(KartError::GuildRosterEmpty);
+        }
+        Contract::stake_wager(&mut defender, challenge.wager.0);
+
+        let needed = (challenge.best_of / 2 + 1) as u32;
+        let (mut challenger_wins, mut defender_wins) = (0, 0);
+        let mut rounds = Vec::new();
+        let mut round = 0;
+        while challenger_wins < needed && defender_wins < needed {
+            let home_token_id = challenger.roster[round % challenger.roster.len()].clone();
+            let away_token_id = defender.roster[round % defender.roster.len()].clone();
+            let (battle_id, result) = self.team_round(home_token_id, away_token_id);
+
+            if result.winner == 0 {
+                challenger_wins += 1;
+            } else {
+                defender_wins += 1;
+            }
+            challenge.battle_ids.push(battle_id);
+            rounds.push(result);
+            round += 1;
+        }
+
+        self.guild_standings
+            .remove(&guild_standing_key(&challenger));
+        self.guild_standings.remove(&guild_standing_key(&defender));
+        let (winner, loser) = if challenger_wins == needed {
+            (&mut challenger, &mut defender)
+        } else {
+            (&mut defender, &mut challenger)
+        };
+        winner.treasury = U128(winner.treasury.0 + challenge.wager.0 * 2);
+        winner.rating = winner.rating + GUILD_RATING_STEP;
+        winner.wins = winner.wins + 1;
+        loser.rating = loser.rating.saturating_sub(GUILD_RATING_STEP);
+        loser.losses = loser.losses + 1;
+
+        challenge.status = GuildChallengeStatus::Resolved;
+        challenge.winner_id = Some(winner.id);
+        self.guild_challenges.replace(challenge_id, &challenge);
+        self.guilds.replace(challenger.id, &challenger);
+        self.guilds.replace(defender.id, &defender);
+        self.guild_standings
+            .insert(&guild_standing_key(&challenger), &challenger.id);
+        self.guild_standings
+            .insert(&guild_standing_key(&defender), &defender.id);
+
+        EventLog::new(EventLogVariant::GuildBattle(vec![GuildBattleLog {
+            challenge_id: challenge_id,
+            challenger_guild_id: challenge.challenger_id,
+            defender_guild_id: challenge.defender_id,
+            winner_guild_id: challenge.winner_id.unwrap(),
+            wager: challenge.wager.0.to_string(),
+            rounds: rounds,
+        }]))
+        .emit();
+
+        return challenge;
+    }
+
+    /// Decline a challenge to the caller's guild, or withdraw one it made.
+    ///
+    /// The wager goes back to the challenger's treasury.
+    pub fn decline_guild_challenge(&mut self, challenge_id: u64) {
+        let account_id = self.session_or_predecessor();
+        let mut challenge = self.open_guild_challenge(challenge_id);
+        let mut challenger = self.guild(challenge.challenger_id);
+        let defender = self.guild(challenge.defender_id);
+        if !challenger.is_officer(&account_id) && !defender.is_officer(&account_id) {
+            panic_error(KartError::NotGuildOfficer);
+        }
+
+        challenger.treasury = U128(challenger.treasury.0 + challenge.wager.0);
+        self.guilds.replace(challenger.id, &challenger);
+
+        challenge.status = GuildChallengeStatus::Declined;
+        self.guild_challenges.replace(challenge_id, &challenge);
+    }
+
+    /// Propose paying `amount` from the guild treasury to `receiver_id`.
+    ///
+    /// Counts as the proposer's approval, so a guild with a single officer pays out at once.
+    pub fn propose_guild_payout(
+        &mut self,
+        guild_id: u64,
+        receiver_id: ValidAccountId,
+        amount: U128,
+    ) -> GuildProposal {
+        let guild = self.guild(guild_id);
+        Contract::assert_guild_officer(&guild, &env::predecessor_account_id());
+        if amount.0 == 0 {
+            panic_error(KartError::GuildAmountZero);
+        }
+
+        let proposal = GuildProposal {
+            id: self.guild_proposals.len(),
+            guild_id: guild_id,
+            proposer_id: env::predecessor_account_id(),
+            receiver_id: receiver_id.into(),
+            amount: amount,
+            officers: guild.officers.clone(),
+            approvals: vec![env::predecessor_account_id()],
+            executed: false,
+        };
+        self.guild_proposals.push(&proposal);
+
+        return self.execute_guild_proposal_if_approved(proposal);
+    }
+
+    pub fn approve_guild_payout(&mut self, proposal_id: u64) -> GuildProposal {
+        let mut proposal = self
+            .guild_proposals
+            .get(proposal_id)
+            .unwrap_or_else(|| panic_error(KartError::GuildProposalNotFound));
+        if proposal.executed {
+            panic_error(KartError::GuildProposalExecuted);
+        }
+
+        // Approved by the officers the proposal was made with
+        let account_id = env::predecessor_account_id();
+        if !proposal.officers.contains(&account_id) {
+            panic_error(KartError::NotGuildOfficer);
+        }
+        if !proposal.approvals.contains(&account_id) {
+            proposal.approvals.push(account_id);
+        }
+        self.guild_proposals.replace(proposal_id, &proposal);
+
+        return self.execute_guild_proposal_if_approved(proposal);
+    }
+
+    pub fn get_guild(&self, guild_id: u64) -> Option<Guild> {
+        return self.guilds.get(guild_id);
+    }
+
+    pub fn get_account_guild(&self, account_id: ValidAccountId) -> Option<Guild> {
+        let guild_id = self.guild_members.get(account_id.as_ref())?;
+        return self.guilds.get(guild_id);
+    }
+
+    pub fn get_guild_challenge(&self, challenge_id: u64) -> Option<GuildChallenge> {
+        return self.guild_challenges.get(challenge_id);
+    }
+
+    pub fn get_guild_proposal(&self, proposal_id: u64) -> Option<GuildProposal> {
+        return self.guild_proposals.get(proposal_id);
+    }
+
+    /// Guilds by rating, then wins, best first.
+    pub fn guild_leaderboard(&self, from: u64, limit: u64) -> Vec<GuildStanding> {
+        let standings = self
+            .guild_standings
+            .iter()
+            .enumerate()
+            .skip(from as usize)
+            .take(limit as usize)
+            .map(|(i, (_, guild_id))| {
+                let guild = self.guild(guild_id);
+                GuildStanding {
+                    rank: i as u32 + 1,
+                    guild_id: guild.id,
+                    name: guild.name,
+                    rating: guild.rating,
+                    wins: guild.wins,
+                    losses: guild.losses,
+                }
+            })
+            .collect();
+        return standings;
+    }
+}
+
+fn guild_standing_key(guild: &Guild) -> GuildStandingKey {
+    (u32::MAX - guild.rating, u32::MAX - guild.wins, guild.id)
+}
+
+fn guild_invite_key(guild_id: u64, account_id: &AccountId) -> String {
+    format!("{}:{}", guild_id, account_id)
+}
+
+impl Contract {
+    fn guild(&self, guild_id: u64) -> Guild {
+        return self
+            .guilds
+            .get(guild_id)
+            .unwrap_or_else(|| panic_error(KartError::GuildNotFound));
+    }
+
+    fn account_guild(&self, account_id: &AccountId) -> Guild {
+        let guild_id = self
+            .guild_members
+            .get(account_id)
+            .unwrap_or_else(|| panic_error(KartError::NotGuildMember));
+        return self.guild(guild_id);
+    }
+
+    fn open_guild_challenge(&self, challenge_id: u64) -> GuildChallenge {
+        let challenge = self
+            .guild_challenges
+            .get(challenge_id)
+            .unwrap_or_else(|| panic_error(KartError::GuildChallengeNotFound));
+        if challenge.status != GuildChallengeStatus::Open {
+            panic_error(KartError::GuildChallengeNotOpen);
+        }
+        return challenge;
+    }
+
+    fn assert_not_in_guild(&self, account_id: &AccountId) {
+        if self.guild_members.get(account_id).is_some() {
+            panic_error(KartError::AlreadyInGuild);
+        }
+    }
+
+    fn assert_guild_officer(guild: &Guild, account_id: &AccountId) {
+        if !guild.is_officer(account_id) {
+            panic_error(KartError::NotGuildOfficer);
+        }
+    }
+
+    fn stake_wager(guild: &mut Guild, wager: u128) {
+        if guild.treasury.0 < wager {
+            panic_error(KartError::GuildTreasuryTooLow);
+        }
+        guild.treasury = U128(guild.treasury.0 - wager);
+    }
+
+    /// Fight one round of a team battle, kept as a battle. Returns its battle id.
+    fn team_round(
+        &mut self,
+        home_token_id: TokenId,
+        away_token_id: TokenId,
+    ) -> (u64, SimpleBattle) {
+        let home_kart = self.near_kart_get_config(home_token_id.clone());
+        let (battle_rand, won_battle) = self.roll_battle(&home_kart);
+
+        self.wear_kart(home_token_id.clone());
+        self.wear_kart(away_token_id.clone());
+
+        let result = SimpleBattle {
+            home_token_id: home_token_id,
+            away_token_id: away_token_id,
+            winner: if won_battle { 0 } else { 1 },
+            battle: battle_rand,
+            prize: String::from("0"),
+            extra: String::from("guild"),
+        };
+        let battle_id = self.store_battle(&result);
+        return (battle_id, result);
+    }
+
+    fn execute_guild_proposal_if_approved(&mut self, proposal: GuildProposal) -> GuildProposal {
+        let mut proposal = proposal;
+        let mut guild = self.guild(proposal.guild_id);
+
+        if proposal.approvals.len() * 2 <= proposal.officers.len() {
+            return proposal;
+        }
+
+        if guild.treasury.0 < proposal.amount.0 {
+            panic_error(KartError::GuildTreasuryTooLow);
+        }
+        guild.treasury = U128(guild.treasury.0 - proposal.amount.0);
+        self.guilds.replace(guild.id, &guild);
+        self.guild_funds = self.guild_funds - proposal.amount.0;
+
+        proposal.executed = true;
+        self.guild_proposals.replace(proposal.id, &proposal);
+        Promise::new(proposal.receiver_id.clone()).transfer(proposal.amount.0);
+
+        return proposal;
+    }
+
+    /// Take a kart off the roster it is on, if any.
+    pub(crate) fn remove_from_guild_roster(&mut self, token_id: &TokenId) {
+        let guild_id = match self.guild_karts.remove(token_id) {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+
+        let mut guild = self.guild(guild_id);
+        guild.roster.retain(|id| id != token_id);
+        self.guilds.replace(guild_id, &guild);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    const TREASURY_DEPOSIT: u128 = 1e24 as u128;
+    const WAGER: u128 = 1e23 as u128;
+
+    /// Two guilds, led by accounts(1) and accounts(2), each with one kart on the roster and a
+    /// funded treasury.
+    fn setup_guilds() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) =
+            setup_with_karts(&[("kart0", accounts(1)), ("kart1", accounts(2))]);
+
+        for (i, leader) in [accounts(1), accounts(2)].iter().enumerate() {
+            set_caller(&mut context, leader.clone(), 0);
+            contract.create_guild(format!("Guild {}", i));
+            contract.register_guild_kart(format!("kart{}", i));
+
+            set_caller(&mut context, leader.clone(), TREASURY_DEPOSIT);
+            contract.deposit_guild_treasury(i as u64);
+        }
+
+        set_caller(&mut context, accounts(1), 0);
+        return (contract, context);
+    }
+
+    #[test]
+    fn test_create_and_join_guild() {
+        let (mut contract, mut context) = setup_guilds();
+
+        contract.invite_to_guild(0, accounts(3));
+        set_caller(&mut context, accounts(3), 0);
+        contract.accept_guild_invite(0);
+
+        let guild = contract.get_account_guild(accounts(3)).unwrap();
+        assert_eq!(guild.id, 0);
+        assert_eq!(guild.members.len(), 2);
+        assert_eq!(guild.roster, vec!["kart0".to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:907:The account was not invited")]
+    fn test_join_without_invite() {
+        let (mut contract, mut context) = setup_guilds();
+
+        set_caller(&mut context, accounts(3), 0);
+        contract.accept_guild_invite(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:902:Another guild has this name")]
+    fn test_guild_names_are_unique() {
+        let (mut contract, mut context) = setup_guilds();
+
+        set_caller(&mut context, accounts(3), 0);
+        contract.create_guild("guild 0".to_string());
+    }
+
+    #[test]
+    fn test_team_battle() {
+        let (mut contract, mut context) = setup_guilds();
+
+        let challenge = contract.challenge_guild(0, 1, 3, U128(WAGER));
+        assert_eq!(
+            contract.get_guild(0).unwrap().treasury.0,
+            TREASURY_DEPOSIT - WAGER
+        );
+
+        set_caller(&mut context, accounts(2), 0);
+        let challenge = contract.accept_guild_challenge(challenge.id);
+        assert_eq!(challenge.status, GuildChallengeStatus::Resolved);
+        assert!(challenge.battle_ids.len() >= 2 && challenge.battle_ids.len() <= 3);
+
+        let winner_id = challenge.winner_id.unwrap();
+        let winner = contract.get_guild(winner_id).unwrap();
+        let loser = contract.get_guild(1 - winner_id).unwrap();
+        assert_eq!(winner.treasury.0, TREASURY_DEPOSIT + WAGER);
+        assert_eq!(loser.treasury.0, TREASURY_DEPOSIT - WAGER);
+        assert_eq!(winner.wins, 1);
+        assert_eq!(loser.losses, 1);
+
+        let leaderboard = contract.guild_leaderboard(0, 10);
+        assert_eq!(leaderboard.len(), 2);
+        assert_eq!(leaderboard[0].guild_id, winner_id);
+        assert_eq!(leaderboard[0].rank, 1);
+        assert_eq!(leaderboard[0].rating, INITIAL_RATING + GUILD_RATING_STEP);
+
+        let page = contract.guild_leaderboard(1, 10);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].guild_id, 1 - winner_id);
+        assert_eq!(page[0].rank, 2);
+
+        let round = contract.battle_with_karts(challenge.battle_ids[0]);
+        assert_eq!(round.result.extra, "guild");
+    }
+
+    #[test]
+    fn test_decline_refunds_wager() {
+        let (mut contract, mut context) = setup_guilds();
+
+        let challenge = contract.challenge_guild(0, 1, 1, U128(WAGER));
+        set_caller(&mut context, accounts(2), 0);
+        contract.decline_guild_challenge(challenge.id);
+
+        assert_eq!(contract.get_guild(0).unwrap().treasury.0, TREASURY_DEPOSIT);
+        assert_eq!(
+            contract.get_guild_challenge(challenge.id).unwrap().status,
+            GuildChallengeStatus::Declined
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:916:Team battles are best of an odd number of rounds, up to 9"
+    )]
+    fn test_best_of_must_be_odd() {
+        let (mut contract, _) = setup_guilds();
+        contract.challenge_guild(0, 1, 2, U128(WAGER));
+    }
+
+    #[test]
+    fn test_payout_needs_officer_majority() {
+        let (mut contract, mut context) = setup_guilds();
+
+        contract.invite_to_guild(0, accounts(3));
+        set_caller(&mut context, accounts(3), 0);
+        contract.accept_guild_invite(0);
+        set_caller(&mut context, accounts(1), 0);
+        contract.set_guild_officer(0, accounts(3), true);
+
+        // One of two officers is not a majority
+        let proposal = contract.propose_guild_payout(0, accounts(4), U128(WAGER));
+        assert!(!proposal.executed);
+
+        set_caller(&mut context, accounts(3), 0);
+        let proposal = contract.approve_guild_payout(proposal.id);
+        assert!(proposal.executed);
+        assert_eq!(
+            contract.get_guild(0).unwrap().treasury.0,
+            TREASURY_DEPOSIT - WAGER
+        );
+    }
+
+    #[test]
+    fn test_demoted_officers_still_approve() {
+        let (mut contract, mut context) = setup_guilds();
+
+        for officer in [accounts(3), accounts(4)].iter() {
+            set_caller(&mut context, accounts(1), 0);
+            contract.invite_to_guild(0, officer.clone());
+            set_caller(&mut context, officer.clone(), 0);
+            contract.accept_guild_invite(0);
+            set_caller(&mut context, accounts(1), 0);
+            contract.set_guild_officer(0, officer.clone(), true);
+        }
+
+        let proposal = contract.propose_guild_payout(0, accounts(1), U128(WAGER));
+        assert_eq!(proposal.officers.len(), 3);
+
+        // Demoting the other officers doesn't leave the leader a majority on their own
+        contract.set_guild_officer(0, accounts(3), false);
+        contract.set_guild_officer(0, accounts(4), false);
+        let proposal = contract.approve_guild_payout(proposal.id);
+        assert!(!proposal.executed);
+
+        set_caller(&mut context, accounts(3), 0);
+        let proposal = contract.approve_guild_payout(proposal.id);
+        assert!(proposal.executed);
+    }
+
+    #[test]
+    fn test_transfer_leaves_roster() {
+        let (mut contract, mut context) = setup_guilds();
+
+        set_caller(&mut context, accounts(1), 1);
+        contract.nft_transfer(accounts(3), "kart0".to_string(), None, None);
+        assert!(contract.get_guild(0).unwrap().roster.is_empty());
+    }
+
+    #[test]
+    fn test_guild_funds_are_not_owner_treasury() {
+        let (contract, mut context) = setup_guilds();
+
+        testing_env!(context.account_balance(3 * TREASURY_DEPOSIT).build());
+        let locked = env::storage_usage() as u128 * env::storage_byte_cost();
+        assert_eq!(
+            contract.get_treasury_balance().0,
+            3 * TREASURY_DEPOSIT - locked - 2 * TREASURY_DEPOSIT
+        );
+    }
+}
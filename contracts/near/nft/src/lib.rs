@@ -18,204 +18,132 @@ NOTES:
 use ed25519_dalek::{PublicKey, Signature, Verifier};
 use hex;
 use near_contract_standards::non_fungible_token::core::StorageKey as NFTStorageKey;
+use near_contract_standards::non_fungible_token::core::{
+    NonFungibleTokenCore, NonFungibleTokenResolver,
+};
 use near_contract_standards::non_fungible_token::metadata::{
     NFTContractMetadata, NonFungibleTokenMetadataProvider, TokenMetadata, NFT_METADATA_SPEC,
 };
 use near_contract_standards::non_fungible_token::NonFungibleToken;
 use near_contract_standards::non_fungible_token::{Token, TokenId};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap, UnorderedSet, Vector};
-use near_sdk::json_types::ValidAccountId;
+use near_sdk::collections::{
+    LazyOption, LookupMap, LookupSet, TreeMap, UnorderedMap, UnorderedSet, Vector,
+};
+use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128};
 use near_sdk::{
-    env, log, near_bindgen, AccountId, BorshStorageKey, PanicOnDefault, Promise, PromiseOrValue,
+    assert_one_yocto, env, log, near_bindgen, AccountId, BorshStorageKey, PanicOnDefault, Promise,
+    PromiseOrValue,
 };
-use rmp_serde;
 use serde::{Deserialize, Serialize};
-use std::fmt;
-
-/// This is the name of the NFT standard we're using
-pub const NFT_STANDARD_NAME: &str = "nep171";
-
-/// Enum that represents the data type of the EventLog.
-/// The enum can either be an NftMint or an NftTransfer.
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "event", content = "data")]
-#[serde(rename_all = "snake_case")]
-#[serde(crate = "near_sdk::serde")]
-#[non_exhaustive]
-pub enum EventLogVariant {
-    NftMint(Vec<NftMintLog>),
-    NftTransfer(Vec<NftTransferLog>),
-}
-
-/// Interface to capture data about an event
-///
-/// Arguments:
-/// * `standard`: name of standard e.g. nep171
-/// * `version`: e.g. 1.0.0
-/// * `event`: associate event data
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(crate = "near_sdk::serde")]
-pub struct EventLog {
-    pub standard: String,
-    pub version: String,
-
-    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
-    #[serde(flatten)]
-    pub event: EventLogVariant,
-}
-
-impl fmt::Display for EventLog {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_fmt(format_args!(
-            "EVENT_JSON:{}",
-            &serde_json::to_string(self).map_err(|_| fmt::Error)?
-        ))
-    }
-}
-
-/// An event log to capture token minting
-///
-/// Arguments
-/// * `owner_id`: "account.near"
-/// * `token_ids`: ["1", "abc"]
-/// * `memo`: optional message
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(crate = "near_sdk::serde")]
-pub struct NftMintLog {
-    pub owner_id: String,
-    pub token_ids: Vec<String>,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub memo: Option<String>,
-}
+use std::cmp;
+use std::collections::HashMap;
+
+pub use crate::achievements::*;
+pub use crate::cosmetics::*;
+pub use crate::defense::*;
+pub use crate::energy::*;
+pub use crate::errors::*;
+pub use crate::events::*;
+pub use crate::guilds::*;
+pub use crate::loot::*;
+pub use crate::migrate::*;
+pub use crate::names::*;
+pub use crate::npcs::*;
+pub use crate::operators::*;
+pub use crate::pricing::*;
+pub use crate::raids::*;
+pub use crate::relay::*;
+pub use crate::seasons::*;
+pub use crate::sessions::*;
+pub use crate::views::*;
+pub use nearkarts_types::{
+    battle::*, cosmetics::*, equip::*, error::*, kart::*, media::*, name::*, replay::*,
+};
 
-/// An event log to capture token transfer
-///
-/// Arguments
-/// * `authorized_id`: approved account to transfer
-/// * `old_owner_id`: "owner.near"
-/// * `new_owner_id`: "receiver.near"
-/// * `token_ids`: ["1", "12345abc"]
-/// * `memo`: optional message
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(crate = "near_sdk::serde")]
-pub struct NftTransferLog {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub authorized_id: Option<String>,
-
-    pub old_owner_id: String,
-    pub new_owner_id: String,
-    pub token_ids: Vec<String>,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub memo: Option<String>,
-}
+mod achievements;
+mod cosmetics;
+mod defense;
+mod energy;
+mod errors;
+mod events;
+mod guilds;
+mod loot;
+mod migrate;
+mod names;
+mod npcs;
+mod operators;
+mod pricing;
+mod raids;
+mod relay;
+mod seasons;
+mod sessions;
+mod views;
 
 near_sdk::setup_alloc!();
 
-const NUM_DECALS: u32 = 7;
-const NUM_WEAPONS: u8 = 6;
-const NUM_WEAPONS_MELEE: u8 = 5;
-const NUM_SHIELDS: u8 = 2;
-const NUM_SKINS: u8 = 4;
-const NUM_TRANSPORTS: u8 = 3;
+const MAX_WORN_PENALTY: u8 = 2;
+const REPAIR_PRICE_PER_POINT: u128 = 1e20 as u128;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     tokens: NonFungibleToken,
+    token_ids: Vector<TokenId>,
+    token_index: LookupMap<TokenId, u64>,
+    indexing_tokens: bool,
+    last_indexed_token_id: Option<TokenId>,
     metadata: LazyOption<NFTContractMetadata>,
     signer_pub_keys: UnorderedSet<String>,
     prev_block_index: near_sdk::BlockHeight,
     random_buffer: Vector<u8>,
     random_index: u8,
     last_battle: LookupMap<AccountId, SimpleBattle>,
-}
-
-// Kart configuration is serialized and extra field of the NFT metadata
-//
-// It is serialized in Rust MessagePack format.
-#[derive(Default, Clone, Serialize, Deserialize)]
-pub struct NearKart {
-    version: u8,
-    level: u32,
-    left: u8,
-    right: u8,
-    top: u8,
-    front: u8,
-    skin: u8,
-    transport: u8,
-    color1: u32,
-    color2: u32,
-    ex1: u8,
-    ex2: u32,
-    locked: bool,
-    decal1: String,
-    decal2: String,
-    decal3: String,
-    extra1: String,
-    extra2: String,
-    extra3: String,
-}
-
-#[derive(Default, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
-pub struct SimpleBattle {
-    home_token_id: String,
-    away_token_id: String,
-    winner: u8,
-    battle: u32,
-    prize: String,
-    extra: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct BattleLog {
-    pub event: String,
-    pub data: SimpleBattle,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct KartMeta {
-    token_id: String,
-    name: String,
-    media: String,
-    reference: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct KartMetaLog {
-    pub event: String,
-    pub data: KartMeta,
-}
-
-impl NearKart {
-    pub fn new() -> Self {
-        let mut kart = Self::default();
-        kart.level = 1;
-        return kart;
-    }
-
-    pub fn from_data(data: &String) -> Self {
-        let mut s = Self::default();
-        s.deserialize(data);
-        s
-    }
-
-    pub fn serialize(&self) -> String {
-        let sj_message_pack = rmp_serde::encode::to_vec(self).unwrap();
-        let sj_hex = hex::encode(&sj_message_pack);
-        return sj_hex;
-    }
-
-    pub fn deserialize(&mut self, data: &String) -> &Self {
-        if String::len(data) > 16 {
-            let sj_vec = hex::decode(data).unwrap_or(Vec::new());
-            let sj_old: NearKart = rmp_serde::decode::from_slice(&sj_vec).unwrap();
-            self.clone_from(&sj_old);
-        }
-        self
-    }
+    battles: Vector<SimpleBattle>,
+    battle_count: u64,
+    last_battle_ids: LookupMap<AccountId, u64>,
+    seasons: Vector<Season>,
+    season_stats: LookupMap<u32, UnorderedMap<TokenId, SeasonStats>>,
+    season_standings: LookupMap<u32, TreeMap<StandingKey, SeasonStats>>,
+    season_results: LookupMap<u32, Vector<SeasonResult>>,
+    season_ranks: LookupMap<String, u32>,
+    season_rewards: LookupMap<u32, Vec<SeasonRewardTier>>,
+    season_claims: LookupSet<String>,
+    kart_wins: LookupMap<TokenId, u32>,
+    achievements_by_token: LookupMap<TokenId, Vec<String>>,
+    achievements_by_account: LookupMap<AccountId, Vec<String>>,
+    daily_quests: LookupMap<AccountId, DailyQuest>,
+    loot_table: LazyOption<LootTable>,
+    pity_counters: LookupMap<TokenId, u32>,
+    prices: LazyOption<Prices>,
+    operators_by_id: LookupMap<TokenId, Vec<AccountId>>,
+    relayers: UnorderedSet<AccountId>,
+    relay_keys: LookupMap<AccountId, Vec<String>>,
+    relay_nonces: LookupMap<AccountId, u64>,
+    relay_usage: LookupMap<AccountId, RelayUsage>,
+    session_keys: LookupMap<Vec<u8>, SessionKey>,
+    guilds: Vector<Guild>,
+    guild_names: LookupMap<String, u64>,
+    guild_members: LookupMap<AccountId, u64>,
+    guild_invites: LookupSet<String>,
+    guild_karts: LookupMap<TokenId, u64>,
+    guild_challenges: Vector<GuildChallenge>,
+    guild_proposals: Vector<GuildProposal>,
+    guild_standings: TreeMap<GuildStandingKey, u64>,
+    guild_funds: u128,
+    session_funds: u128,
+    raids: Vector<Raid>,
+    raid_attacks: LookupMap<String, u64>,
+    raid_damage: LookupMap<String, u64>,
+    raid_claims: LookupSet<String>,
+    raid_funds: u128,
+    npcs: Vector<Npc>,
+    defense_logs: LookupMap<TokenId, Vec<DefenseRecord>>,
+    defense_reward_days: LookupMap<TokenId, u64>,
+    kart_names: LookupMap<String, TokenId>,
+    kart_names_to_backfill: u64,
+    kart_energy: LookupMap<TokenId, KartEnergy>,
+    blocked_names: UnorderedSet<String>,
 }
 
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
@@ -230,6 +158,52 @@ enum StorageKey {
     SignerKey,
     RandomBufferKey,
     LastBattleKey,
+    // Keys above are in use by the first deployed version, add new ones below
+    TokenIds,
+    TokenIndex,
+    Battles,
+    LastBattleIds,
+    Seasons,
+    SeasonStatsKey,
+    SeasonStats { season_id: u32 },
+    SeasonResultsKey,
+    SeasonResults { season_id: u32 },
+    SeasonRanks,
+    SeasonRewards,
+    SeasonClaims,
+    KartWins,
+    AchievementsByToken,
+    AchievementsByAccount,
+    DailyQuests,
+    LootTable,
+    PityCounters,
+    Prices,
+    OperatorsById,
+    Relayers,
+    RelayKeys,
+    RelayNonces,
+    RelayUsage,
+    SessionKeys,
+    Guilds,
+    GuildNames,
+    GuildMembers,
+    GuildInvites,
+    GuildKarts,
+    GuildChallenges,
+    GuildProposals,
+    Raids,
+    RaidAttacks,
+    RaidDamage,
+    RaidClaims,
+    Npcs,
+    DefenseLogs,
+    DefenseRewardDays,
+    KartNames,
+    BlockedNames,
+    KartEnergy,
+    SeasonStandingsKey,
+    SeasonStandings { season_id: u32 },
+    GuildStandings,
 }
 
 #[near_bindgen]
@@ -245,7 +219,7 @@ impl Contract {
                 name: "NEAR Karts".to_string(),
                 symbol: "NEARKARTS".to_string(),
                 icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
-                base_uri: None,
+                base_uri: Some(IPFS_GATEWAY.to_string()),
                 reference: None,
                 reference_hash: None,
             },
@@ -256,7 +230,7 @@ impl Contract {
     pub fn new(owner_id: ValidAccountId, metadata: NFTContractMetadata) -> Self {
         assert!(!env::state_exists(), "Already initialized");
         metadata.assert_valid();
-        Self {
+        Contract::from_base(BaseContract {
             tokens: NonFungibleToken::new(
                 StorageKey::NonFungibleToken,
                 owner_id,
@@ -270,6 +244,69 @@ impl Contract {
             random_buffer: Vector::new(StorageKey::RandomBufferKey),
             random_index: 0,
             last_battle: LookupMap::<AccountId, SimpleBattle>::new(StorageKey::LastBattleKey),
+        })
+    }
+
+    /// The collections of the first deployed version, from `new` or `migrate`, with everything
+    /// added since started empty. Every field is started here so `migrate` keeps working.
+    fn from_base(base: BaseContract) -> Self {
+        Self {
+            tokens: base.tokens,
+            token_ids: Vector::new(StorageKey::TokenIds),
+            token_index: LookupMap::new(StorageKey::TokenIndex),
+            indexing_tokens: false,
+            last_indexed_token_id: None,
+            metadata: base.metadata,
+            signer_pub_keys: base.signer_pub_keys,
+            prev_block_index: base.prev_block_index,
+            random_buffer: base.random_buffer,
+            random_index: base.random_index,
+            last_battle: base.last_battle,
+            battles: Vector::new(StorageKey::Battles),
+            battle_count: 0,
+            last_battle_ids: LookupMap::new(StorageKey::LastBattleIds),
+            seasons: Vector::new(StorageKey::Seasons),
+            season_stats: LookupMap::new(StorageKey::SeasonStatsKey),
+            season_standings: LookupMap::new(StorageKey::SeasonStandingsKey),
+            season_results: LookupMap::new(StorageKey::SeasonResultsKey),
+            season_ranks: LookupMap::new(StorageKey::SeasonRanks),
+            season_rewards: LookupMap::new(StorageKey::SeasonRewards),
+            season_claims: LookupSet::new(StorageKey::SeasonClaims),
+            kart_wins: LookupMap::new(StorageKey::KartWins),
+            achievements_by_token: LookupMap::new(StorageKey::AchievementsByToken),
+            achievements_by_account: LookupMap::new(StorageKey::AchievementsByAccount),
+            daily_quests: LookupMap::new(StorageKey::DailyQuests),
+            loot_table: LazyOption::new(StorageKey::LootTable, Some(&LootTable::default_table())),
+            pity_counters: LookupMap::new(StorageKey::PityCounters),
+            prices: LazyOption::new(StorageKey::Prices, Some(&Prices::default_prices())),
+            operators_by_id: LookupMap::new(StorageKey::OperatorsById),
+            relayers: UnorderedSet::new(StorageKey::Relayers),
+            relay_keys: LookupMap::new(StorageKey::RelayKeys),
+            relay_nonces: LookupMap::new(StorageKey::RelayNonces),
+            relay_usage: LookupMap::new(StorageKey::RelayUsage),
+            session_keys: LookupMap::new(StorageKey::SessionKeys),
+            guilds: Vector::new(StorageKey::Guilds),
+            guild_names: LookupMap::new(StorageKey::GuildNames),
+            guild_members: LookupMap::new(StorageKey::GuildMembers),
+            guild_invites: LookupSet::new(StorageKey::GuildInvites),
+            guild_karts: LookupMap::new(StorageKey::GuildKarts),
+            guild_challenges: Vector::new(StorageKey::GuildChallenges),
+            guild_proposals: Vector::new(StorageKey::GuildProposals),
+            guild_standings: TreeMap::new(StorageKey::GuildStandings),
+            guild_funds: 0,
+            session_funds: 0,
+            raids: Vector::new(StorageKey::Raids),
+            raid_attacks: LookupMap::new(StorageKey::RaidAttacks),
+            raid_damage: LookupMap::new(StorageKey::RaidDamage),
+            raid_claims: LookupSet::new(StorageKey::RaidClaims),
+            raid_funds: 0,
+            npcs: Vector::new(StorageKey::Npcs),
+            defense_logs: LookupMap::new(StorageKey::DefenseLogs),
+            defense_reward_days: LookupMap::new(StorageKey::DefenseRewardDays),
+            kart_names: LookupMap::new(StorageKey::KartNames),
+            kart_names_to_backfill: 0,
+            kart_energy: LookupMap::new(StorageKey::KartEnergy),
+            blocked_names: UnorderedSet::new(StorageKey::BlockedNames),
         }
     }
 
@@ -293,64 +330,48 @@ impl Contract {
         token_id: TokenId,
         near_kart_new: NearKart,
         cid: String,
+        media_hash: Base64VecU8,
         sig: String,
         pub_key: String,
     ) {
-        self.assert_nft_owner(token_id.clone());
-
-        if env::attached_deposit() < 1e23 as u128 {
-            env::panic(b"error_upgrade_payment_too_low");
-        }
+        self.assert_nft_owner_or_operator(token_id.clone(), &env::predecessor_account_id());
 
-        let is_signer = self._is_signer(pub_key.clone());
-        if !is_signer {
-            env::panic(b"error_pubkey_is_not_signer");
+        if env::attached_deposit() < self.get_prices().upgrade.0 {
+            panic_error(KartError::UpgradePaymentTooLow);
         }
 
-        let verified = Contract::verify_sig(cid.clone(), sig.clone(), pub_key.clone());
-        if !verified {
-            env::panic(b"error_signature_verification_failed");
-        }
+        self.assert_signed_media(&cid, &media_hash, sig, pub_key);
 
         let lookup_map = self.tokens.token_metadata_by_id.as_mut().unwrap();
-        let mut metadata = lookup_map.get(&token_id.to_string()).unwrap();
+        let mut metadata = lookup_map
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
         let extra = metadata.extra.unwrap_or(String::from(""));
-        let mut nk = NearKart::from_data(&extra);
+        let mut nk = NearKart::from_data(&extra).unwrap_or_else(|e| panic_error(e));
 
         if nk.locked {
-            env::panic(b"error_cannot_upgrade_while_kart_is_locked");
+            panic_error(KartError::KartLocked);
         }
 
         Contract::assert_valid_equip(near_kart_new.clone(), nk.clone());
 
-        nk.level = nk.level + 1;
-        nk.locked = true;
-        nk.color1 = near_kart_new.color1;
-        nk.decal1 = near_kart_new.decal1;
-        nk.front = near_kart_new.front;
-        nk.left = near_kart_new.left;
-        nk.right = near_kart_new.right;
-        nk.skin = near_kart_new.skin;
-        nk.transport = near_kart_new.transport;
+        nk.apply_upgrade(&near_kart_new);
 
         let extra = nk.serialize();
         metadata.extra = Some(extra);
         lookup_map.insert(&token_id, &metadata);
 
-        self.update_media(token_id.clone(), cid.clone(), sig, pub_key);
+        self.update_media(token_id.clone(), cid.clone(), media_hash);
+        self.check_achievements(&token_id, AchievementTrigger::Upgrade);
 
-        let kart_meta = KartMeta {
+        EventLog::new(EventLogVariant::Upgrade(vec![KartMeta {
             token_id: token_id.clone(),
-            name: metadata.title.unwrap(),
+            name: metadata.title.unwrap_or_default(),
             media: cid.clone(),
             reference: String::from(""),
-        };
-
-        let kml: KartMetaLog = KartMetaLog {
-            event: "configure_on_upgrade".to_string(),
-            data: kart_meta,
-        };
-        log!("EVENT_JSON:{}", serde_json::to_string(&kml).unwrap());
+        }]))
+        .emit();
+        self.emit_level_up(&token_id, nk.level);
     }
 
     fn internal_mint(
@@ -360,16 +381,20 @@ impl Contract {
         token_metadata: Option<TokenMetadata>,
     ) -> Token {
         if self.tokens.token_metadata_by_id.is_some() && token_metadata.is_none() {
-            env::panic(b"Must provide metadata");
+            panic_error(KartError::MetadataRequired);
         }
         if self.tokens.owner_by_id.get(&token_id).is_some() {
-            env::panic(b"token_id must be unique");
+            panic_error(KartError::TokenIdNotUnique);
+        }
+        if token_id.starts_with(NPC_TOKEN_PREFIX) {
+            panic_error(KartError::TokenIdReserved);
         }
 
         let owner_id: AccountId = token_owner_id;
 
         // Core behavior: every token must have an owner
         self.tokens.owner_by_id.insert(&token_id, &owner_id);
+        self.index_token(&token_id);
 
         // Metadata extension: Save metadata, keep variable around to return later.
         // Note that check above already panicked if metadata extension in use but no metadata
@@ -413,22 +438,16 @@ impl Contract {
         name: String,
         mut near_kart_new: NearKart,
         cid: String,
+        media_hash: Base64VecU8,
         sig: String,
         pub_key: String,
     ) -> Token {
-        if env::attached_deposit() < 1e23 as u128 {
-            env::panic(b"error_mint_payment_too_low");
+        if env::attached_deposit() < self.get_prices().mint.0 {
+            panic_error(KartError::MintPaymentTooLow);
         }
 
-        let is_signer = self._is_signer(pub_key.clone());
-        if !is_signer {
-            env::panic(b"error_pubkey_is_not_signer");
-        }
-
-        let verified = Contract::verify_sig(cid.clone(), sig.clone(), pub_key.clone());
-        if !verified {
-            env::panic(b"error_signature_verification_failed");
-        }
+        self.assert_signed_media(&cid, &media_hash, sig, pub_key);
+        let name = self.register_kart_name(&token_id, &name);
 
         let tm = TokenMetadata {
             title: Some(name.clone()),
@@ -447,56 +466,41 @@ impl Contract {
 
         let token = self.internal_mint(token_id.clone(), receiver_id.to_string(), Some(tm));
 
-        // Initialize any fields the user is not allowed to set on mint
-        near_kart_new.version = 1;
-        near_kart_new.level = 1;
-        near_kart_new.ex1 = 0;
-        near_kart_new.ex2 = 0;
-        near_kart_new.locked = true;
-        near_kart_new.decal2 = String::new();
-        near_kart_new.decal3 = String::new();
-        near_kart_new.extra1 = String::from("7"); // Everyone gets the NEAR decal
-        near_kart_new.extra2 = String::new();
-        near_kart_new.extra3 = String::new();
+        near_kart_new.reset_for_mint();
+        validate_mint_paint(&near_kart_new).unwrap_or_else(|e| panic_error(e));
 
         self.configure(token_id.clone(), near_kart_new);
-        self.update_media(token_id.clone(), cid.clone(), sig, pub_key);
-
-        let nft_mint_log: EventLog = EventLog {
-            standard: NFT_STANDARD_NAME.to_string(),
-            version: NFT_METADATA_SPEC.to_string(),
-
-            event: EventLogVariant::NftMint(vec![NftMintLog {
-                owner_id: token.owner_id.to_string(),
-                token_ids: vec![token_id.to_string()],
-                memo: None,
-            }]),
-        };
+        self.update_media(token_id.clone(), cid.clone(), media_hash);
+        self.check_achievements(&token_id, AchievementTrigger::Mint);
 
-        log!("{}", &nft_mint_log.to_string());
+        EventLog::new(EventLogVariant::NftMint(vec![NftMintLog {
+            owner_id: token.owner_id.to_string(),
+            token_ids: vec![token_id.to_string()],
+            memo: None,
+        }]))
+        .emit();
 
-        let kart_meta = KartMeta {
+        EventLog::new(EventLogVariant::Configure(vec![KartMeta {
             token_id: token_id.clone(),
             name: name.clone(),
             media: cid.clone(),
             reference: String::from(""),
-        };
-
-        let kml: KartMetaLog = KartMetaLog {
-            event: "configure_on_mint".to_string(),
-            data: kart_meta,
-        };
-        log!("EVENT_JSON:{}", serde_json::to_string(&kml).unwrap());
+        }]))
+        .emit();
 
         return token;
     }
 
     fn configure(&mut self, token_id: TokenId, near_kart_new: NearKart) {
-        self.assert_nft_owner(token_id.clone());
+        self.assert_nft_owner_or_operator(token_id.clone(), &env::predecessor_account_id());
         let lookup_map = self.tokens.token_metadata_by_id.as_mut().unwrap();
-        let mut metadata = lookup_map.get(&token_id.to_string()).unwrap();
+        let mut metadata = lookup_map
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
 
-        Contract::assert_valid_equip(near_kart_new.clone(), near_kart_new.clone());
+        let extra = metadata.extra.clone().unwrap_or_default();
+        let nk_prev = NearKart::from_data(&extra).unwrap_or_else(|e| panic_error(e));
+        Contract::assert_valid_equip(near_kart_new.clone(), nk_prev);
 
         let extra = near_kart_new.serialize();
         metadata.extra = Some(extra);
@@ -504,69 +508,8 @@ impl Contract {
     }
 
     fn assert_valid_equip(nk: NearKart, nk_prev: NearKart) {
-        let max_index = Contract::get_max_weapon_index_for_level(nk.level);
-        let shield_start_index = 200;
-
-        let mut weapon_or_shield_index_left = nk.left;
-        let mut is_shield_left = false;
-        if nk.left >= shield_start_index {
-            weapon_or_shield_index_left -= shield_start_index;
-            is_shield_left = true;
-        }
-
-        let mut weapon_or_shield_index_right = nk.right;
-        let mut is_shield_right = false;
-        if nk.right >= shield_start_index {
-            is_shield_right = true;
-            weapon_or_shield_index_right -= shield_start_index;
-        }
-
-        if nk.front > NUM_WEAPONS_MELEE - 1 {
-            env::panic(b"error_front_weapon_index_too_high");
-        } else if nk.transport > NUM_TRANSPORTS - 1 {
-            env::panic(b"error_transport_index_too_high");
-        } else if nk.skin > NUM_SKINS - 1 {
-            env::panic(b"error_skin_index_too_high");
-        } else {
-            if is_shield_left {
-                if weapon_or_shield_index_left > NUM_SHIELDS - 1 {
-                    env::panic(b"error_shield_left_index_too_high");
-                }
-            } else {
-                if weapon_or_shield_index_left > NUM_WEAPONS - 1 {
-                    env::panic(b"error_weapon_left_index_too_high");
-                }
-            }
-            if is_shield_right {
-                if weapon_or_shield_index_right > NUM_SHIELDS - 1 {
-                    env::panic(b"error_shield_right_index_too_high");
-                }
-            } else {
-                if weapon_or_shield_index_right > NUM_WEAPONS - 1 {
-                    env::panic(b"error_weapon_right_index_too_high");
-                }
-            }
-        }
-
-        if nk.front > max_index {
-            env::panic(b"error_level_not_high_enough_to_equip_front_weapon");
-        } else if weapon_or_shield_index_left > max_index {
-            env::panic(b"error_level_not_high_enough_to_equip_left_weapon");
-        } else if weapon_or_shield_index_right > max_index {
-            env::panic(b"error_level_not_high_enough_to_equip_right_weapon");
-        } else if nk.transport > max_index {
-            env::panic(b"error_level_not_high_enough_to_use_transport");
-        } else if nk.skin > max_index {
-            env::panic(b"error_level_not_high_enough_to_use_skin");
-        }
-
-        if nk.decal1 != "" && nk.decal1 != "0" && nk.decal1 != "7" {
-            let unlocked_decals: Vec<String> =
-                nk_prev.extra1.split(",").map(|s| s.to_string()).collect();
-            if !unlocked_decals.contains(&nk.decal1) {
-                println!("{:?} {}", unlocked_decals, nk.decal1);
-                env::panic(b"error_decal_front_is_not_unlocked");
-            }
+        if let Err(error) = validate_equip(&nk, &nk_prev) {
+            panic_error(error);
         }
     }
 
@@ -591,38 +534,109 @@ impl Contract {
 
     pub fn nft_get_token_metadata(&self, token_id: TokenId) -> TokenMetadata {
         let lookup_map = self.tokens.token_metadata_by_id.as_ref().unwrap();
-        let metadata = lookup_map.get(&token_id.to_string()).unwrap();
+        let metadata = lookup_map
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
         return metadata;
     }
 
     pub fn nft_get_metadata_title(&self, token_id: TokenId) -> String {
         let lookup_map = self.tokens.token_metadata_by_id.as_ref().unwrap();
-        let metadata = lookup_map.get(&token_id.to_string()).unwrap();
+        let metadata = lookup_map
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
         return metadata.title.unwrap_or("".to_string());
     }
 
     pub fn nft_get_metadata_extra(&self, token_id: TokenId) -> String {
         let lookup_map = self.tokens.token_metadata_by_id.as_ref().unwrap();
-        let metadata = lookup_map.get(&token_id.to_string()).unwrap();
+        let metadata = lookup_map
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
         return metadata.extra.unwrap_or("".to_string());
     }
 
     pub fn near_kart_get_config(&self, token_id: TokenId) -> NearKart {
         let extra = self.nft_get_metadata_extra(token_id);
-        let sj = NearKart::from_data(&extra);
+        let sj = NearKart::from_data(&extra).unwrap_or_else(|e| panic_error(e));
         return sj;
     }
 
+    /// Amount of yoctoNEAR `repair_kart` requires to fully repair a kart.
+    pub fn get_repair_cost(&self, token_id: TokenId) -> U128 {
+        let nk = self.near_kart_get_config(token_id);
+        let cost = nk.repair_points_needed() as u128 * self.get_prices().repair_per_point.0;
+        return U128(cost);
+    }
+
+    /// Restore all parts of a kart to full durability, paid in NEAR.
+    ///
+    /// Any deposit above the repair cost is refunded.
+    #[payable]
+    pub fn repair_kart(&mut self, token_id: TokenId) {
+        self.assert_nft_owner(token_id.clone());
+        let mut nk = self.near_kart_get_config(token_id.clone());
+
+        let points = nk.repair_points_needed();
+        if points == 0 {
+            panic_error(KartError::KartDoesNotNeedRepair);
+        }
+
+        let cost = points as u128 * self.get_prices().repair_per_point.0;
+        let deposit = env::attached_deposit();
+        if deposit < cost {
+            panic_error(KartError::RepairPaymentTooLow);
+        }
+
+        nk.repair();
+        self.near_kart_set_config(token_id, &nk);
+
+        let refund = deposit - cost;
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
+
+    /// Restore all parts of a kart to full durability using a repair kit won in battle.
+    pub fn repair_kart_with_kit(&mut self, token_id: TokenId) {
+        self.assert_nft_owner(token_id.clone());
+        let mut nk = self.near_kart_get_config(token_id.clone());
+
+        if nk.repair_kits == 0 {
+            panic_error(KartError::NoRepairKits);
+        }
+        if nk.repair_points_needed() == 0 {
+            panic_error(KartError::KartDoesNotNeedRepair);
+        }
+
+        nk.repair_kits = nk.repair_kits - 1;
+        nk.repair();
+        self.near_kart_set_config(token_id, &nk);
+    }
+
+    fn near_kart_set_config(&mut self, token_id: TokenId, nk: &NearKart) {
+        let lookup_map = self.tokens.token_metadata_by_id.as_mut().unwrap();
+        let mut metadata = lookup_map
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
+
+        let extra = nk.serialize();
+        metadata.extra = Some(extra);
+        lookup_map.insert(&token_id, &metadata);
+    }
+
     fn assert_nft_owner(&self, token_id: TokenId) {
         //get the token object from the token ID
-        let account_id = self.tokens.owner_by_id.get(&token_id).expect("No token");
+        let account_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
 
         //make sure that the person calling the function is the owner of the token
-        assert_eq!(
-            &env::predecessor_account_id(),
-            &account_id,
-            "Caller must be the token owner."
-        );
+        if env::predecessor_account_id() != account_id {
+            panic_error(KartError::NotTokenOwner);
+        }
     }
 
     fn token_owner(&self, token_id: TokenId) -> Option<String> {
@@ -638,37 +652,94 @@ impl Contract {
             env::current_account_id(),
             env::predecessor_account_id()
         );
-        assert_eq!(valid, true, "Caller must be relative of contract owner");
+        if !valid {
+            panic_error(KartError::NotContractOwner);
+        }
+    }
+
+    fn assert_valid_media(cid: &String, media_hash: &Base64VecU8) {
+        if let Err(error) = validate_media(cid, &media_hash.0) {
+            panic_error(error);
+        }
+    }
+
+    /// Check the media and that a registered signer signed it.
+    fn assert_signed_media(
+        &self,
+        cid: &String,
+        media_hash: &Base64VecU8,
+        sig: String,
+        pub_key: String,
+    ) {
+        let is_signer = self._is_signer(pub_key.clone());
+        if !is_signer {
+            panic_error(KartError::PubKeyIsNotSigner);
+        }
+
+        Contract::assert_valid_media(cid, media_hash);
+        let message = media_message(cid, &media_hash.0);
+        let verified = Contract::verify_sig(message, sig, pub_key);
+        if !verified {
+            panic_error(KartError::SignatureVerificationFailed);
+        }
     }
 
-    fn update_media(&mut self, token_id: TokenId, cid: String, sig: String, pub_key: String) {
+    fn update_media(&mut self, token_id: TokenId, cid: String, media_hash: Base64VecU8) {
         let lookup_map = self.tokens.token_metadata_by_id.as_mut().unwrap();
-        let mut metadata = lookup_map.get(&token_id.to_string()).unwrap();
+        let mut metadata = lookup_map
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
 
         metadata.media = Some(cid.clone());
+        metadata.media_hash = Some(media_hash);
         lookup_map.insert(&token_id, &metadata);
     }
 
+    /// Add a minted token to the end of `token_ids`.
+    fn index_token(&mut self, token_id: &TokenId) {
+        self.token_index.insert(token_id, &self.token_ids.len());
+        self.token_ids.push(token_id);
+    }
+
+    /// Remove a burned token from `token_ids`, the last token takes its place.
+    fn unindex_token(&mut self, token_id: &TokenId) {
+        let index = match self.token_index.remove(token_id) {
+            Some(index) => index,
+            None => return,
+        };
+
+        self.token_ids.swap_remove(index);
+        if let Some(moved_id) = self.token_ids.get(index) {
+            self.token_index.insert(&moved_id, &index);
+        }
+    }
+
     pub fn get_num_karts(&self) -> u32 {
-        let tokens_owner = self.tokens.owner_by_id.to_vec();
-        return tokens_owner.len() as u32;
+        return self.token_ids.len() as u32;
     }
 
+    /// Token at `index` of the kart list, karts are listed in mint order until one is burned.
     pub fn get_token_id_by_index(&self, index: u32) -> TokenId {
-        let tokens_owner = self.tokens.owner_by_id.to_vec();
-        let token_info = tokens_owner[index as usize].clone();
-        return token_info.0;
+        match self.token_ids.get(index as u64) {
+            Some(token_id) => return token_id,
+            None => panic_error(KartError::TokenIndexOutOfRange),
+        }
     }
 
+    /// A random other kart, or an NPC when there is none. Without NPCs a lone kart gets itself.
     pub fn get_random_opponent(&mut self, token_id: TokenId) -> TokenId {
         let mut opponent_id = token_id.clone(); // In case there is only one token return it
-        let tokens_owner = self.tokens.owner_by_id.to_vec();
-        let num_tokens = tokens_owner.len() as u32;
+        let num_tokens = self.token_ids.len();
+
+        if num_tokens <= 1 {
+            if let Some(npc_token_id) = self.random_npc_opponent() {
+                return npc_token_id;
+            }
+        }
 
         if num_tokens > 1 {
-            let mut rand_index = self.get_random_u32() % num_tokens;
-            let mut token_info = tokens_owner[rand_index as usize].clone();
-            opponent_id = token_info.0;
+            let mut rand_index = self.get_random_u32() as u64 % num_tokens;
+            opponent_id = self.token_ids.get(rand_index).unwrap();
 
             if opponent_id == token_id {
                 rand_index = rand_index + 1;
@@ -677,43 +748,21 @@ impl Contract {
                     rand_index = 0;
                 }
 
-                token_info = tokens_owner[rand_index as usize].clone();
-                opponent_id = token_info.0;
+                opponent_id = self.token_ids.get(rand_index).unwrap();
             }
         }
 
         return opponent_id;
     }
 
-    /// Set the extra1 field of the NearKart data structure.
-    ///
-    /// This is used to store a comma separated list of unlocked decals.
-    ///
-    /// # Arguments
-    ///
-    /// * `token_id` - The id of the token update
-    /// * `extra1` - CSV string of unlocked decal ids
-    ///
-    fn near_kart_set_extra1(&mut self, token_id: TokenId, extra1: &String) {
-        self.assert_nft_owner(token_id.clone());
-        let lookup_map = self.tokens.token_metadata_by_id.as_mut().unwrap();
-        let mut metadata = lookup_map.get(&token_id.to_string()).unwrap();
-        let extra = metadata.extra.unwrap_or(String::from(""));
-        let mut nk = NearKart::from_data(&extra);
-
-        nk.extra1 = extra1.clone();
-
-        let extra = nk.serialize();
-        metadata.extra = Some(extra);
-        lookup_map.insert(&token_id, &metadata);
-    }
-
+    /// Only called for the winner of a battle, after the battle checked the caller.
     fn level_up(&mut self, token_id: TokenId) {
-        self.assert_nft_owner(token_id.clone());
         let lookup_map = self.tokens.token_metadata_by_id.as_mut().unwrap();
-        let mut metadata = lookup_map.get(&token_id.to_string()).unwrap();
+        let mut metadata = lookup_map
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
         let extra = metadata.extra.unwrap_or(String::from(""));
-        let mut nk = NearKart::from_data(&extra);
+        let mut nk = NearKart::from_data(&extra).unwrap_or_else(|e| panic_error(e));
 
         nk.level = nk.level + 1;
 
@@ -724,95 +773,129 @@ impl Contract {
         let extra = nk.serialize();
         metadata.extra = Some(extra);
         lookup_map.insert(&token_id, &metadata);
+
+        self.emit_level_up(&token_id, nk.level);
+    }
+
+    fn emit_level_up(&self, token_id: &TokenId, level: u32) {
+        EventLog::new(EventLogVariant::LevelUp(vec![LevelUpLog {
+            owner_id: self.tokens.owner_by_id.get(token_id).unwrap(),
+            token_id: token_id.to_string(),
+            level: level,
+        }]))
+        .emit();
     }
 
     pub fn game_simple_battle(&mut self, token_id: TokenId) -> SimpleBattle {
-        self.assert_nft_owner(token_id.clone());
+        let account_id = self.session_or_predecessor();
+        return self.internal_battle(token_id, &account_id);
+    }
 
-        let mut prize = 0;
+    /// Battle with a kart `account_id` owns or operates.
+    fn internal_battle(&mut self, token_id: TokenId, account_id: &AccountId) -> SimpleBattle {
+        self.assert_nft_owner_or_operator(token_id.clone(), account_id);
+        self.use_energy(&token_id, BATTLE_ENERGY_COST);
+
+        let mut prize = String::from("0");
         let opponent_token_id = self.get_random_opponent(token_id.clone());
 
-        let battle_rand = self.get_random_u32();
+        let home_kart = self.near_kart_get_config(token_id.clone());
+        let (battle_rand, won_battle) = self.roll_battle(&home_kart);
         let mut winner = 0;
-        let winner_rand = (battle_rand % 4) as u8;
-        let won_battle = winner_rand != 0;
+        let mut extra = String::new();
 
         if !won_battle {
             winner = 1;
         }
 
         if won_battle {
-            let won_prize_rand = self.get_random_u32();
-            let won_prize_selector = won_prize_rand % 4;
-            let won_prize = won_prize_selector != 0;
-
-            if won_prize {
-                let prize_rand = self.get_random_u32();
-                prize = prize_rand % NUM_DECALS + 1;
-
-                let near_kart = self.near_kart_get_config(token_id.clone());
-                let unlocks_str = near_kart.extra1;
-                let mut unlocks: Vec<String> =
-                    unlocks_str.split(",").map(|s| s.to_string()).collect();
-                let mut has_already_unlocked = false;
-
-                for unlock in unlocks.iter() {
-                    if unlock == &prize.to_string() {
-                        has_already_unlocked = true;
-                        break;
-                    }
-                }
+            let loot_drop = self.roll_loot(&token_id);
+            if loot_drop.decal != "0" || loot_drop.repair_kits > 0 {
+                EventLog::new(EventLogVariant::Prize(vec![PrizeLog {
+                    owner_id: self.token_owner(token_id.clone()).unwrap(),
+                    token_id: token_id.clone(),
+                    decal: loot_drop.decal.clone(),
+                    repair_kits: loot_drop.repair_kits,
+                }]))
+                .emit();
+            }
 
-                if !has_already_unlocked {
-                    if unlocks.len() == 1 && unlocks[0] == "" {
-                        unlocks[0] = prize.to_string();
-                    } else {
-                        unlocks.push(prize.to_string());
-                    }
-                    let new_unlocks_str = unlocks.join(",");
-                    self.near_kart_set_extra1(token_id.clone(), &new_unlocks_str);
-                } else {
-                    prize = 0;
-                }
+            prize = loot_drop.decal;
+            if loot_drop.repair_kits > 0 {
+                extra = String::from("repair_kit");
             }
 
             self.level_up(token_id.clone());
         }
 
+        self.record_season_battle(&token_id, &opponent_token_id, won_battle);
+
+        self.wear_kart(token_id.clone());
+        if opponent_token_id != token_id && npc_id_from_token_id(&opponent_token_id).is_none() {
+            self.wear_kart(opponent_token_id.clone());
+        }
+
+        self.check_achievements(&token_id, AchievementTrigger::Battle { won: won_battle });
+
         let result = SimpleBattle {
             home_token_id: token_id,
             away_token_id: opponent_token_id,
             winner: winner,
             battle: battle_rand,
-            prize: prize.to_string(),
-            extra: "".to_string(),
+            prize: prize,
+            extra: extra,
         };
 
-        self.last_battle
-            .insert(&env::predecessor_account_id(), &result.clone());
+        let battle_id = self.record_battle(&result);
+        self.record_defense(battle_id, &result);
 
-        let b: BattleLog = BattleLog {
-            event: "game_simple_battle".to_string(),
-            data: result.clone(),
-        };
-        log!("EVENT_JSON:{}", serde_json::to_string(&b).unwrap());
+        EventLog::new(EventLogVariant::Battle(vec![result.clone()])).emit();
 
         return result;
     }
 
+    /// Roll the battle number and whether the home kart wins it.
+    fn roll_battle(&mut self, home_kart: &NearKart) -> (u32, bool) {
+        // Worn out parts lower the odds of winning
+        let worn_penalty = cmp::min(home_kart.worn_part_count(), MAX_WORN_PENALTY);
+
+        let battle_rand = self.get_random_u32();
+        let winner_rand = (battle_rand % 4) as u8;
+        return (battle_rand, winner_rand > worn_penalty);
+    }
+
+    fn wear_kart(&mut self, token_id: TokenId) {
+        let mut nk = self.near_kart_get_config(token_id.clone());
+        nk.apply_wear();
+        self.near_kart_set_config(token_id, &nk);
+    }
+
     pub fn get_last_battle(&self, account_id: ValidAccountId) -> SimpleBattle {
         let result = self
             .last_battle
             .get(&account_id.to_string())
-            .expect("error_no_last_battle");
+            .unwrap_or_else(|| panic_error(KartError::NoLastBattle));
         return result;
     }
 
+    /// Rounds of the account's last battle, see `replay::battle_rounds`. Replayed with the
+    /// karts as they are now, so a later upgrade changes the weapons used.
+    pub fn get_last_battle_replay(&self, account_id: ValidAccountId) -> Vec<BattleRound> {
+        let battle = self.get_last_battle(account_id);
+        let home = self.near_kart_get_config(battle.home_token_id.clone());
+        let away = self.battle_kart(battle.away_token_id.clone());
+        return battle_rounds(&battle, &home, &away);
+    }
+
     fn verify_sig(message: String, sig: String, pub_key: String) -> bool {
-        let sig_bytes = hex::decode(sig).unwrap();
-        let s = Signature::from_bytes(&sig_bytes).unwrap();
-        let pub_key_bytes = hex::decode(pub_key).unwrap();
-        let pub_key_obj = PublicKey::from_bytes(&pub_key_bytes).unwrap();
+        let s = hex::decode(sig)
+            .ok()
+            .and_then(|sig_bytes| Signature::from_bytes(&sig_bytes).ok())
+            .unwrap_or_else(|| panic_error(KartError::InvalidSignature));
+        let pub_key_obj = hex::decode(pub_key)
+            .ok()
+            .and_then(|pub_key_bytes| PublicKey::from_bytes(&pub_key_bytes).ok())
+            .unwrap_or_else(|| panic_error(KartError::InvalidPublicKey));
 
         let ok = pub_key_obj.verify(message.as_bytes(), &s).is_ok();
 
@@ -863,13 +946,70 @@ impl Contract {
         return num;
     }
 
-    fn get_max_weapon_index_for_level(level: u32) -> u8 {
-        // let weapon_index = ((level as u8 / 5) + 1) * 5;
-        let mut weapon_index = level + 2;
-        if weapon_index > 255 {
-            weapon_index = 255;
+    fn is_valid_decal(decal: &String) -> bool {
+        match decal.parse::<u32>() {
+            Ok(index) => index >= 1 && index <= NUM_DECALS,
+            Err(_) => false,
         }
-        return weapon_index as u8;
+    }
+
+    /// Burn a kart, removing it from its owner and all enumeration indexes.
+    #[payable]
+    pub fn nft_burn(&mut self, token_id: TokenId, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_nft_owner(token_id.clone());
+        self.release_kart_name(&token_id);
+
+        let owner_id = self.tokens.owner_by_id.remove(&token_id).unwrap();
+        self.unindex_token(&token_id);
+
+        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+            token_metadata_by_id.remove(&token_id);
+        }
+
+        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+            let mut token_ids = tokens_per_owner.get(&owner_id).unwrap();
+            token_ids.remove(&token_id);
+            if token_ids.is_empty() {
+                tokens_per_owner.remove(&owner_id);
+            } else {
+                tokens_per_owner.insert(&owner_id, &token_ids);
+            }
+        }
+
+        if let Some(approvals_by_id) = &mut self.tokens.approvals_by_id {
+            approvals_by_id.remove(&token_id);
+        }
+        self.clear_operators(&token_id);
+        self.remove_from_guild_roster(&token_id);
+
+        EventLog::new(EventLogVariant::NftBurn(vec![NftBurnLog {
+            owner_id: owner_id,
+            authorized_id: None,
+            token_ids: vec![token_id],
+            memo: memo,
+        }]))
+        .emit();
+    }
+
+    fn emit_transfer(
+        old_owner_id: &AccountId,
+        new_owner_id: &AccountId,
+        token_id: &TokenId,
+        sender_id: Option<AccountId>,
+        memo: Option<String>,
+    ) {
+        // Only record the authorized account when it is not the owner itself
+        let authorized_id = sender_id.filter(|sender_id| sender_id != old_owner_id);
+
+        EventLog::new(EventLogVariant::NftTransfer(vec![NftTransferLog {
+            authorized_id: authorized_id,
+            old_owner_id: old_owner_id.to_string(),
+            new_owner_id: new_owner_id.to_string(),
+            token_ids: vec![token_id.to_string()],
+            memo: memo,
+        }]))
+        .emit();
     }
 
     fn is_sub_account(main_account: String, sub_account: String) -> bool {
@@ -886,7 +1026,107 @@ impl Contract {
     }
 }
 
-near_contract_standards::impl_non_fungible_token_core!(Contract, tokens);
+// Core transfers are implemented by hand instead of with impl_non_fungible_token_core! so that
+// every transfer emits an nft_transfer event
+#[near_bindgen]
+impl NonFungibleTokenCore for Contract {
+    #[payable]
+    fn nft_transfer(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        let old_owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
+        self.tokens.nft_transfer(
+            receiver_id.clone(),
+            token_id.clone(),
+            approval_id,
+            memo.clone(),
+        );
+        self.clear_operators(&token_id);
+        self.remove_from_guild_roster(&token_id);
+        Contract::emit_transfer(
+            &old_owner_id,
+            &receiver_id.to_string(),
+            &token_id,
+            Some(env::predecessor_account_id()),
+            memo,
+        );
+    }
+
+    #[payable]
+    fn nft_transfer_call(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        let old_owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
+        let result = self.tokens.nft_transfer_call(
+            receiver_id.clone(),
+            token_id.clone(),
+            approval_id,
+            memo.clone(),
+            msg,
+        );
+        // Operators and the guild roster are left to nft_resolve_transfer, the receiver may
+        // still return the kart
+        Contract::emit_transfer(
+            &old_owner_id,
+            &receiver_id.to_string(),
+            &token_id,
+            Some(env::predecessor_account_id()),
+            memo,
+        );
+        return result;
+    }
+
+    fn nft_token(self, token_id: TokenId) -> Option<Token> {
+        self.tokens.nft_token(token_id)
+    }
+}
+
+#[near_bindgen]
+impl NonFungibleTokenResolver for Contract {
+    #[private]
+    fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool {
+        let transferred = self.tokens.nft_resolve_transfer(
+            previous_owner_id.clone(),
+            receiver_id.clone(),
+            token_id.clone(),
+            approved_account_ids,
+        );
+
+        if transferred {
+            self.clear_operators(&token_id);
+            self.remove_from_guild_roster(&token_id);
+        } else {
+            // The receiver did not keep the token, log it going back to the previous owner
+            Contract::emit_transfer(&receiver_id, &previous_owner_id, &token_id, None, None);
+        }
+
+        return transferred;
+    }
+}
+
 near_contract_standards::impl_non_fungible_token_approval!(Contract, tokens);
 near_contract_standards::impl_non_fungible_token_enumeration!(Contract, tokens);
 
@@ -897,6 +1137,73 @@ impl NonFungibleTokenMetadataProvider for Contract {
     }
 }
 
+/// Contract and karts the module tests start from.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) mod test_utils {
+    use core::convert::TryFrom;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+
+    pub const MINT_STORAGE_COST: u128 = 1e23 as u128;
+    pub const TEST_CID: &str = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+    // Made with the ed25519 secret key [7; 32] over media_message(TEST_CID, sha256)
+    pub const TEST_SIG: &str = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+    pub const TEST_PUB_KEY: &str =
+        "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+
+    pub fn contract_id() -> ValidAccountId {
+        ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap()
+    }
+
+    pub fn owner_id() -> ValidAccountId {
+        ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap()
+    }
+
+    pub fn media_hash() -> Base64VecU8 {
+        Base64VecU8::from(parse_cid(TEST_CID).unwrap().digest.to_vec())
+    }
+
+    pub fn set_caller(context: &mut VMContextBuilder, account_id: ValidAccountId, deposit: u128) {
+        testing_env!(context
+            .predecessor_account_id(account_id)
+            .attached_deposit(deposit)
+            .build());
+    }
+
+    /// Contract owned by `owner_id()` with the test signer key and a kart titled
+    /// "Kart <token_id>" for every `(token_id, owner)`. The context is left calling as the contract owner
+    /// with nothing attached.
+    pub fn setup_with_karts(karts: &[(&str, ValidAccountId)]) -> (Contract, VMContextBuilder) {
+        let mut context = VMContextBuilder::new();
+        context
+            .current_account_id(contract_id())
+            .signer_account_id(owner_id());
+        set_caller(&mut context, owner_id(), 0);
+
+        let mut contract = Contract::new_default_meta(owner_id());
+        contract.add_signer_key(TEST_PUB_KEY.to_string());
+        for (token_id, kart_owner) in karts.iter() {
+            // nft_mint configures the kart as the caller, so the owner mints their own
+            set_caller(&mut context, kart_owner.clone(), MINT_STORAGE_COST);
+            contract.nft_mint(
+                token_id.to_string(),
+                kart_owner.clone(),
+                format!("Kart {}", token_id),
+                NearKart::new(),
+                TEST_CID.to_string(),
+                media_hash(),
+                TEST_SIG.to_string(),
+                TEST_PUB_KEY.to_string(),
+            );
+        }
+
+        set_caller(&mut context, owner_id(), 0);
+        return (contract, context);
+    }
+}
+
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use core::convert::TryFrom;
@@ -907,8 +1214,10 @@ mod tests {
     use super::*;
 
     const MINT_STORAGE_COST: u128 = 1e23 as u128;
-    const DEFAULT_EXTRA: &str = "dc0013010100000000000000000000c3a0a0a0a137a0a0";
+    const DEFAULT_EXTRA: &str = "dc0019010100000000000000000000c3a0a0a0a137a0a0646464646400";
     const DEFAULT_TITLE: &str = "MegaKart";
+    // Test media signatures are made with the ed25519 secret key [7; 32], over
+    // media_message(cid, sha256) for the test cid
 
     fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
         let mut builder = VMContextBuilder::new();
@@ -924,7 +1233,7 @@ mod tests {
         predecessor_account_id: ValidAccountId,
     ) -> VMContextBuilder {
         let pub_key = Vec::from(
-            hex::decode("c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462")
+            hex::decode("ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c")
                 .unwrap(),
         );
         let mut builder = VMContextBuilder::new();
@@ -1001,8 +1310,9 @@ mod tests {
         starting_near_kart.level = 1;
         starting_near_kart.decal1 = "7".to_string();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1010,6 +1320,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1045,8 +1356,9 @@ mod tests {
         let token_id = "0".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1054,6 +1366,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1070,10 +1383,17 @@ mod tests {
 
         let nft_count = contract.nft_count(br_acc.clone());
         assert_eq!(nft_count, 1);
-    }
 
-    #[test]
-    #[should_panic(expected = "error_cannot_upgrade_while_kart_is_locked")]
+        let md = contract.nft_get_token_metadata(token_id.clone());
+        assert_eq!(md.media_hash.unwrap().0, media_hash.0);
+        assert_eq!(
+            contract.nft_metadata().base_uri,
+            Some(IPFS_GATEWAY.to_string())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:216:The kart is locked until it levels up")]
     fn test_upgrade_locked_panic() {
         let br_nk_acc =
             ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
@@ -1087,8 +1407,9 @@ mod tests {
         let token_id = "0".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1096,6 +1417,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1105,6 +1427,7 @@ mod tests {
             token_id.clone(),
             nk1.clone(),
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1124,8 +1447,9 @@ mod tests {
         let token_id = "0".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1133,6 +1457,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1155,6 +1480,7 @@ mod tests {
             token_id.clone(),
             nk1.clone(),
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1162,6 +1488,305 @@ mod tests {
         assert_eq!(nk2.left, 4)
     }
 
+    fn mint_default_kart(contract: &mut Contract, owner: ValidAccountId, token_id: &TokenId) {
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            token_id.clone(),
+            owner,
+            format!("{} {}", DEFAULT_TITLE, token_id),
+            NearKart::new(),
+            cid.to_string(),
+            media_hash.clone(),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+    }
+
+    fn setup_decal_contract() -> (Contract, TokenId) {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let token_id = "0".to_string();
+        mint_default_kart(&mut contract, br_acc, &token_id);
+
+        let mut nk = contract.near_kart_get_config(token_id.clone());
+        nk.extra1 = String::from("7,3,5");
+        contract.near_kart_set_config(token_id.clone(), &nk);
+
+        (contract, token_id)
+    }
+
+    #[test]
+    fn test_mint_keeps_decal_slots() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let token_id = "0".to_string();
+        let mut starting_near_kart = NearKart::new();
+        starting_near_kart.decal1 = "7".to_string();
+        starting_near_kart.decal2 = "7".to_string();
+        starting_near_kart.decal3 = "0".to_string();
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            token_id.clone(),
+            br_acc,
+            String::from(DEFAULT_TITLE),
+            starting_near_kart,
+            cid.to_string(),
+            media_hash.clone(),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+
+        let nk = contract.near_kart_get_config(token_id.clone());
+        assert_eq!(nk.decal1, "7");
+        assert_eq!(nk.decal2, "7");
+        assert_eq!(nk.decal3, "0");
+    }
+
+    #[test]
+    fn test_configure_unlocked_decals() {
+        let (mut contract, token_id) = setup_decal_contract();
+
+        let mut nk = contract.near_kart_get_config(token_id.clone());
+        nk.decal1 = "3".to_string();
+        nk.decal2 = "5".to_string();
+        nk.decal3 = "7".to_string();
+        contract.configure(token_id.clone(), nk);
+
+        let nk = contract.near_kart_get_config(token_id.clone());
+        assert_eq!(nk.decal1, "3");
+        assert_eq!(nk.decal2, "5");
+        assert_eq!(nk.decal3, "7");
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:213:The front decal is not unlocked")]
+    fn test_decal_front_not_unlocked() {
+        let (mut contract, token_id) = setup_decal_contract();
+
+        let mut nk = contract.near_kart_get_config(token_id.clone());
+        nk.decal1 = "4".to_string();
+        contract.configure(token_id.clone(), nk);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:214:The left decal is not unlocked")]
+    fn test_decal_left_not_unlocked() {
+        let (mut contract, token_id) = setup_decal_contract();
+
+        let mut nk = contract.near_kart_get_config(token_id.clone());
+        nk.decal2 = "4".to_string();
+        contract.configure(token_id.clone(), nk);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:215:The right decal is not unlocked")]
+    fn test_decal_right_not_unlocked() {
+        let (mut contract, token_id) = setup_decal_contract();
+
+        let mut nk = contract.near_kart_get_config(token_id.clone());
+        nk.decal3 = "4".to_string();
+        contract.configure(token_id.clone(), nk);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:214:The left decal is not unlocked")]
+    fn test_mint_decal_left_not_unlocked() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let mut starting_near_kart = NearKart::new();
+        starting_near_kart.decal2 = "3".to_string();
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            "0".to_string(),
+            br_acc,
+            String::from(DEFAULT_TITLE),
+            starting_near_kart,
+            cid.to_string(),
+            media_hash.clone(),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:304:Media hash does not match the CID")]
+    fn test_mint_media_hash_mismatch() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            "0".to_string(),
+            br_acc,
+            String::from(DEFAULT_TITLE),
+            NearKart::new(),
+            cid.to_string(),
+            Base64VecU8::from(vec![0u8; 32]),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:300:Media is not a valid IPFS CID")]
+    fn test_mint_invalid_cid() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            "0".to_string(),
+            br_acc,
+            String::from(DEFAULT_TITLE),
+            NearKart::new(),
+            "kart.png".to_string(),
+            Base64VecU8::from(vec![0u8; 32]),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:307:Signature is not a hex ed25519 signature")]
+    fn test_mint_malformed_signature() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            "0".to_string(),
+            br_acc,
+            String::from(DEFAULT_TITLE),
+            NearKart::new(),
+            cid.to_string(),
+            media_hash,
+            "not hex".to_string(),
+            t_pub_key_1.to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:103:Caller must be the token owner")]
+    fn test_battle_not_token_owner() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        let mut context = get_context_br(br_nk_acc.clone(), br_acc.clone());
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let token_id = "0".to_string();
+        mint_default_kart(&mut contract, br_acc.clone(), &token_id);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.game_simple_battle(token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:100:No kart has this token id")]
+    fn test_token_metadata_not_found() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(1).into());
+        contract.nft_get_token_metadata("nokart".to_string());
+    }
+
+    #[test]
+    fn test_upgrade_decals_and_color2() {
+        let (mut contract, token_id) = setup_decal_contract();
+
+        let mut nk = contract.near_kart_get_config(token_id.clone());
+        nk.locked = false;
+        contract.near_kart_set_config(token_id.clone(), &nk);
+
+        nk.decal1 = "5".to_string();
+        nk.decal2 = "3".to_string();
+        nk.decal3 = "5".to_string();
+        nk.color2 = 0xff0000;
+
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.upgrade(
+            token_id.clone(),
+            nk,
+            cid.to_string(),
+            media_hash.clone(),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+
+        let nk = contract.near_kart_get_config(token_id.clone());
+        assert_eq!(nk.decal1, "5");
+        assert_eq!(nk.decal2, "3");
+        assert_eq!(nk.decal3, "5");
+        assert_eq!(nk.color2, 0xff0000);
+    }
+
     #[test]
     fn test_mint_verified_image() {
         let br_nk_acc =
@@ -1177,8 +1802,10 @@ mod tests {
         let starting_near_kart = NearKart::new();
 
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         println!("MINT1");
         let token = contract.nft_mint(
@@ -1187,6 +1814,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1213,8 +1841,9 @@ mod tests {
         let token_id = "0".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1222,6 +1851,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1229,20 +1859,18 @@ mod tests {
         assert_eq!(token.token_id, token_id);
 
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
 
         contract.add_signer_key(t_pub_key_1.to_string());
 
-        contract.update_media(
-            token_id.clone(),
-            cid.to_string(),
-            t_sig_1.to_string(),
-            t_pub_key_1.to_string(),
-        );
+        contract.update_media(token_id.clone(), cid.to_string(), media_hash.clone());
 
         let md = contract.nft_get_token_metadata(token_id.clone());
         assert_eq!(cid.to_string(), md.media.unwrap_or("".to_string()));
+        assert_eq!(md.media_hash.unwrap().0, media_hash.0);
     }
 
     #[test]
@@ -1259,8 +1887,9 @@ mod tests {
         let token_id = "megakart".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1268,6 +1897,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1279,9 +1909,10 @@ mod tests {
         let token_away = contract.nft_mint(
             token_id_away.clone(),
             br_acc.clone(),
-            String::from(DEFAULT_TITLE),
+            String::from("FluffyKart"),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1292,6 +1923,8 @@ mod tests {
         assert_eq!(num_karts, 2);
 
         let sel_id = contract.get_token_id_by_index(0);
+        assert_eq!(sel_id, token_id.clone());
+        let sel_id = contract.get_token_id_by_index(1);
         assert_eq!(sel_id, token_id_away.clone());
 
         let opponent_id = contract.get_random_opponent("megakart".to_string());
@@ -1315,8 +1948,9 @@ mod tests {
         let token_id = "megakart".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1324,6 +1958,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1335,9 +1970,10 @@ mod tests {
         let token_away = contract.nft_mint(
             token_id_away.clone(),
             br_acc.clone(),
-            String::from(DEFAULT_TITLE),
+            String::from("FluffyKart"),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1361,6 +1997,15 @@ mod tests {
         assert_eq!(last_battle.home_token_id, token_id.clone());
         assert_eq!(last_battle.battle, battle_result_2.battle);
 
+        let replay = contract.get_last_battle_replay(br_acc.clone());
+        let winner_score: u32 = replay
+            .iter()
+            .filter(|round| round.aggressor == last_battle.winner)
+            .map(|round| round.score)
+            .sum();
+        assert_eq!(replay.last().unwrap().aggressor, last_battle.winner);
+        assert!(winner_score >= WINNER_SCORE);
+
         let battle_result_3 = contract.game_simple_battle(token_id.clone());
         assert_ne!(battle_result_3.battle, battle_2);
         let battle_result_4 = contract.game_simple_battle(token_id.clone());
@@ -1385,6 +2030,179 @@ mod tests {
         assert_eq!(nk1.decal1, "3");
     }
 
+    #[test]
+    fn test_battle_wear() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let token_id = "megakart".to_string();
+        let starting_near_kart = NearKart::new();
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            token_id.clone(),
+            br_acc.clone(),
+            String::from(DEFAULT_TITLE),
+            starting_near_kart,
+            cid.to_string(),
+            media_hash.clone(),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+
+        let token_id_away = "fluffykart".to_string();
+        let starting_near_kart = NearKart::new();
+        contract.nft_mint(
+            token_id_away.clone(),
+            br_acc.clone(),
+            String::from("FluffyKart"),
+            starting_near_kart,
+            cid.to_string(),
+            media_hash.clone(),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+
+        let nk = contract.near_kart_get_config(token_id.clone());
+        assert_eq!(nk.dur_skin, MAX_DURABILITY);
+        assert_eq!(nk.dur_transport, MAX_DURABILITY);
+        assert_eq!(nk.worn_part_count(), 0);
+
+        contract.game_simple_battle(token_id.clone());
+
+        let nk = contract.near_kart_get_config(token_id.clone());
+        assert_eq!(nk.dur_skin, MAX_DURABILITY - SKIN_WEAR[0]);
+        assert_eq!(nk.dur_transport, MAX_DURABILITY - TRANSPORT_WEAR[0]);
+        // Empty weapon slots do not wear
+        assert_eq!(nk.dur_front, MAX_DURABILITY);
+
+        let nk_away = contract.near_kart_get_config(token_id_away.clone());
+        assert_eq!(nk_away.dur_skin, MAX_DURABILITY - SKIN_WEAR[0]);
+
+        let cost = contract.get_repair_cost(token_id.clone());
+        assert_eq!(
+            cost.0,
+            (SKIN_WEAR[0] + TRANSPORT_WEAR[0]) as u128 * REPAIR_PRICE_PER_POINT
+        );
+    }
+
+    #[test]
+    fn test_worn_part_count() {
+        let mut nk = NearKart::new();
+        nk.left = 1;
+        nk.right = 200;
+
+        let mut i = 0;
+        while i < 50 {
+            nk.apply_wear();
+            i += 1;
+        }
+
+        assert_eq!(nk.dur_left, 0);
+        assert_eq!(nk.dur_right, 0);
+        assert_eq!(nk.dur_front, MAX_DURABILITY);
+        assert_eq!(nk.dur_skin, 0);
+        assert_eq!(nk.dur_transport, 0);
+        assert_eq!(nk.worn_part_count(), 4);
+
+        nk.repair();
+        assert_eq!(nk.worn_part_count(), 0);
+        assert_eq!(nk.repair_points_needed(), 0);
+    }
+
+    #[test]
+    fn test_repair_kart() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let token_id = "0".to_string();
+        let starting_near_kart = NearKart::new();
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            token_id.clone(),
+            br_acc.clone(),
+            String::from(DEFAULT_TITLE),
+            starting_near_kart,
+            cid.to_string(),
+            media_hash.clone(),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+
+        contract.game_simple_battle(token_id.clone());
+        contract.game_simple_battle(token_id.clone());
+
+        let nk = contract.near_kart_get_config(token_id.clone());
+        assert_gt!(nk.repair_points_needed(), 0);
+
+        contract.repair_kart(token_id.clone());
+
+        let nk = contract.near_kart_get_config(token_id.clone());
+        assert_eq!(nk.repair_points_needed(), 0);
+        assert_eq!(nk.dur_skin, MAX_DURABILITY);
+        assert_eq!(nk.dur_transport, MAX_DURABILITY);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:402:Attached deposit is below the repair cost")]
+    fn test_repair_kart_payment_too_low() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        let mut context = get_context_br(br_nk_acc.clone(), br_acc.clone());
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let token_id = "0".to_string();
+        let starting_near_kart = NearKart::new();
+        let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+        contract.add_signer_key(t_pub_key_1.to_string());
+        contract.nft_mint(
+            token_id.clone(),
+            br_acc.clone(),
+            String::from(DEFAULT_TITLE),
+            starting_near_kart,
+            cid.to_string(),
+            media_hash.clone(),
+            t_sig_1.to_string(),
+            t_pub_key_1.to_string(),
+        );
+
+        contract.game_simple_battle(token_id.clone());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(br_acc.clone())
+            .build());
+        contract.repair_kart(token_id.clone());
+    }
+
     #[test]
     fn test_random() {
         let br_nk_acc =
@@ -1420,8 +2238,9 @@ mod tests {
         let token_id = "0".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1429,6 +2248,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1477,8 +2297,9 @@ mod tests {
         let token_id = "0".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1486,6 +2307,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1525,8 +2347,9 @@ mod tests {
         let token_id = "0".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1534,6 +2357,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1580,8 +2404,9 @@ mod tests {
         let token_id = "0".to_string();
         let starting_near_kart = NearKart::new();
         let cid = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
-        let t_sig_1 = "43e2e88d7286e4aa26450f5167fb8c8718817832313938c532351d261e711d13926eb1ad847d3e7a81461bd7b0ee7da702fbcd45e1bad025c7b1378e66f6030d";
-        let t_pub_key_1 = "c58b29b2a183a22fca6e6503e30d61a0ac3e36dbcfb946eb59fbb9d76876a462";
+        let media_hash = Base64VecU8::from(parse_cid(cid).unwrap().digest.to_vec());
+        let t_sig_1 = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+        let t_pub_key_1 = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
         contract.add_signer_key(t_pub_key_1.to_string());
         let token = contract.nft_mint(
             token_id.clone(),
@@ -1589,6 +2414,7 @@ mod tests {
             String::from(DEFAULT_TITLE),
             starting_near_kart,
             cid.to_string(),
+            media_hash.clone(),
             t_sig_1.to_string(),
             t_pub_key_1.to_string(),
         );
@@ -1616,4 +2442,108 @@ mod tests {
             .build());
         assert!(!contract.nft_is_approved(token_id.clone(), accounts(1), Some(1)));
     }
+
+    #[test]
+    fn test_transfer_emits_event() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        let mut context = get_context_br(br_nk_acc.clone(), br_acc.clone());
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let token_id = "0".to_string();
+        mint_default_kart(&mut contract, br_acc.clone(), &token_id);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(1), token_id.clone(), None, Some("gg".to_string()));
+
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            logs[0],
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"muhindogalien.testnet","new_owner_id":"bob","token_ids":["0"],"memo":"gg"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_burn() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        let mut context = get_context_br(br_nk_acc.clone(), br_acc.clone());
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        let token_id = "0".to_string();
+        mint_default_kart(&mut contract, br_acc.clone(), &token_id);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.nft_burn(token_id.clone(), None);
+
+        let logs = get_logs();
+        assert_eq!(
+            logs[0],
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"muhindogalien.testnet","token_ids":["0"]}]}"#
+        );
+        assert_eq!(contract.nft_supply_for_owner(br_acc), U128(0));
+        assert!(contract.nft_token(token_id).is_none());
+    }
+
+    #[test]
+    fn test_burn_updates_kart_list() {
+        let br_nk_acc =
+            ValidAccountId::try_from("near_karts.muhindogalien.testnet".to_string()).unwrap();
+        let br_acc = ValidAccountId::try_from("muhindogalien.testnet".to_string()).unwrap();
+        let mut context = get_context_br(br_nk_acc.clone(), br_acc.clone());
+        configure_env_for_storage_br(
+            br_acc.clone(),
+            get_context_br(br_nk_acc.clone(), br_acc.clone()),
+        );
+        let mut contract = Contract::new_default_meta(br_acc.clone());
+
+        for token_id in ["0", "1", "2"].iter() {
+            mint_default_kart(&mut contract, br_acc.clone(), &token_id.to_string());
+        }
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.nft_burn("0".to_string(), None);
+
+        // The last kart takes the burned kart's place
+        assert_eq!(contract.get_num_karts(), 2);
+        assert_eq!(contract.get_token_id_by_index(0), "2".to_string());
+        assert_eq!(contract.get_token_id_by_index(1), "1".to_string());
+
+        contract.nft_burn("1".to_string(), None);
+        assert_eq!(contract.get_num_karts(), 1);
+        assert_eq!(contract.get_token_id_by_index(0), "2".to_string());
+        assert_eq!(
+            contract.get_random_opponent("2".to_string()),
+            "2".to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:105:No kart at this index")]
+    fn test_token_id_by_index_out_of_range() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(1).into());
+        contract.get_token_id_by_index(0);
+    }
 }
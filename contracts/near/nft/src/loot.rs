@@ -0,0 +1,441 @@
+/*
+Battle prize loot table.
+NOTES:
+  - A won battle rolls `prize_rand % prize_denominator`, rolls of
+    `prize_denominator - prize_numerator` and above win a prize.
+  - The prize is picked from the entries by weight. A decal the kart has already unlocked is
+    rerolled among the locked entries, converted into repair kits or drops nothing, by the
+    table's `duplicate_policy`.
+  - Every win without a drop of `pity_rarity` or better counts as dry. Once a kart has
+    `pity_threshold` dry wins in a row its next win is guaranteed such a drop. A threshold of 0
+    disables the pity timer.
+  - The default table picks the same decals as the prize roll it replaced: a prize on a 3 in 4
+    chance (`prize_rand % 4 != 0`), then decal `prize_rand % NUM_DECALS + 1`, all 7 decals with
+    equal odds. Where that roll dropped nothing for a decal the kart already has, the default
+    table gives a repair kit instead. The NEAR decal "7" every kart starts with is one of the 7,
+    so at least 1 in 7 prizes is a repair kit, which is how battles win kits.
+*/
+use crate::*;
+
+const ODDS_BASIS_POINTS: u128 = 10_000;
+
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    Reroll,
+    RepairKit,
+    Nothing,
+}
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct LootEntry {
+    pub decal: String,
+    pub rarity: Rarity,
+    pub weight: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct LootTable {
+    pub prize_numerator: u32,
+    pub prize_denominator: u32,
+    pub entries: Vec<LootEntry>,
+    pub duplicate_policy: DuplicatePolicy,
+    pub duplicate_repair_kits: u32,
+    pub pity_threshold: u32,
+    pub pity_rarity: Rarity,
+}
+
+/// Published odds of a single entry, in basis points of a won battle.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LootOdds {
+    pub decal: String,
+    pub rarity: Rarity,
+    pub weight: u32,
+    pub odds_bps: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LootTableView {
+    pub prize_numerator: u32,
+    pub prize_denominator: u32,
+    pub duplicate_policy: DuplicatePolicy,
+    pub duplicate_repair_kits: u32,
+    pub pity_threshold: u32,
+    pub pity_rarity: Rarity,
+    pub entries: Vec<LootOdds>,
+}
+
+/// Outcome of a prize roll. `decal` is "0" when nothing was unlocked.
+pub struct LootDrop {
+    pub decal: String,
+    pub repair_kits: u32,
+}
+
+impl LootTable {
+    pub fn default_table() -> Self {
+        let rarities = [
+            Rarity::Common,
+            Rarity::Common,
+            Rarity::Rare,
+            Rarity::Rare,
+            Rarity::Epic,
+            Rarity::Rare,
+            Rarity::Common,
+        ];
+
+        let entries = rarities
+            .iter()
+            .enumerate()
+            .map(|(i, rarity)| LootEntry {
+                decal: (i + 1).to_string(),
+                rarity: *rarity,
+                weight: 1,
+            })
+            .collect();
+
+        Self {
+            prize_numerator: 3,
+            prize_denominator: 4,
+            entries: entries,
+            duplicate_policy: DuplicatePolicy::RepairKit,
+            duplicate_repair_kits: 1,
+            pity_threshold: 0,
+            pity_rarity: Rarity::Rare,
+        }
+    }
+
+    /// Whether a won battle's `prize_rand` wins a prize.
+    fn wins_prize(&self, prize_rand: u32) -> bool {
+        return prize_rand % self.prize_denominator
+            >= self.prize_denominator - self.prize_numerator;
+    }
+
+    fn assert_valid(&self) {
+        if self.prize_denominator == 0 || self.prize_numerator > self.prize_denominator {
+            panic_error(KartError::LootPrizeChanceInvalid);
+        }
+        if self.entries.len() == 0 {
+            panic_error(KartError::LootTableEmpty);
+        }
+        for entry in self.entries.iter() {
+            if entry.weight == 0 {
+                panic_error(KartError::LootEntryWeightZero);
+            }
+            if !Contract::is_valid_decal(&entry.decal) {
+                panic_error(KartError::LootEntryDecalInvalid);
+            }
+        }
+        if self.pity_threshold > 0 && !self.entries.iter().any(|e| e.rarity >= self.pity_rarity) {
+            panic_error(KartError::LootPityRarityHasNoEntries);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_loot_table(&mut self, loot_table: LootTable) {
+        Contract::assert_contract_owner();
+        loot_table.assert_valid();
+        self.loot_table.set(&loot_table);
+    }
+
+    /// The loot table with the odds of each entry dropping from a won battle.
+    pub fn loot_table(&self) -> LootTableView {
+        let table = self.loot_table.get().unwrap();
+        let total_weight: u128 = table.entries.iter().map(|e| e.weight as u128).sum();
+
+        let entries = table
+            .entries
+            .iter()
+            .map(|entry| LootOdds {
+                decal: entry.decal.clone(),
+                rarity: entry.rarity,
+                weight: entry.weight,
+                odds_bps: (ODDS_BASIS_POINTS * table.prize_numerator as u128 * entry.weight as u128
+                    / (table.prize_denominator as u128 * total_weight))
+                    as u32,
+            })
+            .collect();
+
+        LootTableView {
+            prize_numerator: table.prize_numerator,
+            prize_denominator: table.prize_denominator,
+            duplicate_policy: table.duplicate_policy,
+            duplicate_repair_kits: table.duplicate_repair_kits,
+            pity_threshold: table.pity_threshold,
+            pity_rarity: table.pity_rarity,
+            entries: entries,
+        }
+    }
+
+    pub fn get_pity_counter(&self, token_id: TokenId) -> u32 {
+        return self.pity_counters.get(&token_id).unwrap_or(0);
+    }
+}
+
+impl Contract {
+    /// Roll the prize for a won battle and apply it to the kart.
+    pub(crate) fn roll_loot(&mut self, token_id: &TokenId) -> LootDrop {
+        let table = self.loot_table.get().unwrap();
+        let mut nk = self.near_kart_get_config(token_id.clone());
+        let pity = self.get_pity_counter(token_id.clone());
+        let is_unlocked = |nk: &NearKart, entry: &LootEntry| {
+            nk.extra1.split(",").any(|decal| decal == entry.decal)
+        };
+
+        let mut picked: Option<LootEntry> = None;
+
+        if table.pity_threshold > 0 && pity >= table.pity_threshold {
+            let rare: Vec<LootEntry> = table
+                .entries
+                .iter()
+                .filter(|e| e.rarity >= table.pity_rarity)
+                .cloned()
+                .collect();
+            let locked: Vec<LootEntry> = rare
+                .iter()
+                .filter(|e| !is_unlocked(&nk, *e))
+                .cloned()
+                .collect();
+            let pool = if locked.len() > 0 { locked } else { rare };
+            picked = Some(self.pick_weighted(&pool));
+        } else {
+            let prize_rand = self.get_random_u32();
+            if table.wins_prize(prize_rand) {
+                let mut entry = self.pick_weighted(&table.entries);
+
+                if is_unlocked(&nk, &entry) && table.duplicate_policy == DuplicatePolicy::Reroll {
+                    let locked: Vec<LootEntry> = table
+                        .entries
+                        .iter()
+                        .filter(|e| !is_unlocked(&nk, *e))
+                        .cloned()
+                        .collect();
+                    if locked.len() > 0 {
+                        entry = self.pick_weighted(&locked);
+                    }
+                }
+                picked = Some(entry);
+            }
+        }
+
+        let mut loot_drop = LootDrop {
+            decal: String::from("0"),
+            repair_kits: 0,
+        };
+        let mut is_rare_drop = false;
+
+        if let Some(entry) = picked {
+            is_rare_drop = entry.rarity >= table.pity_rarity;
+
+            if nk.unlock_decal(&entry.decal) {
+                loot_drop.decal = entry.decal;
+            } else if table.duplicate_policy != DuplicatePolicy::Nothing {
+                // Duplicates that could not be rerolled are converted into repair kits
+                loot_drop.repair_kits = table.duplicate_repair_kits;
+                nk.repair_kits = nk.repair_kits + loot_drop.repair_kits;
+            }
+            self.near_kart_set_config(token_id.clone(), &nk);
+        }
+
+        if is_rare_drop {
+            self.pity_counters.insert(token_id, &0);
+        } else {
+            self.pity_counters.insert(token_id, &(pity + 1));
+        }
+
+        return loot_drop;
+    }
+
+    fn pick_weighted(&mut self, entries: &Vec<LootEntry>) -> LootEntry {
+        let index = weighted_index(entries, self.get_random_u32());
+        return entries[index].clone();
+    }
+}
+
+/// Index of the entry `rand` picks, by weight.
+fn weighted_index(entries: &Vec<LootEntry>, rand: u32) -> usize {
+    let total_weight: u64 = entries.iter().map(|e| e.weight as u64).sum();
+    let mut roll = rand as u64 % total_weight;
+
+    for (i, entry) in entries.iter().enumerate() {
+        if roll < entry.weight as u64 {
+            return i;
+        }
+        roll -= entry.weight as u64;
+    }
+
+    return entries.len() - 1;
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_contract() -> Contract {
+        let (contract, _) =
+            setup_with_karts(&[("megakart", owner_id()), ("fluffykart", owner_id())]);
+        return contract;
+    }
+
+    fn single_entry_table(decal: &str, rarity: Rarity) -> LootTable {
+        let mut table = LootTable::default_table();
+        table.prize_numerator = 1;
+        table.prize_denominator = 1;
+        table.entries = vec![LootEntry {
+            decal: decal.to_string(),
+            rarity: rarity,
+            weight: 1,
+        }];
+        table
+    }
+
+    #[test]
+    fn test_default_loot_table_odds() {
+        let contract = setup_contract();
+
+        let view = contract.loot_table();
+        assert_eq!(view.entries.len(), NUM_DECALS as usize);
+        assert_eq!(view.pity_threshold, 0);
+        assert_eq!(view.duplicate_policy, DuplicatePolicy::RepairKit);
+        assert_eq!(view.duplicate_repair_kits, 1);
+        // 3 in 4 wins drop a prize, split evenly between 7 decals
+        assert!(view.entries.iter().all(|e| e.odds_bps == 1071));
+    }
+
+    #[test]
+    fn test_default_loot_table_matches_original_roll() {
+        let table = LootTable::default_table();
+
+        let rands = (0..1000).chain((u32::MAX - 1000)..=u32::MAX);
+        for rand in rands {
+            assert_eq!(table.wins_prize(rand), rand % 4 != 0);
+            let decal = &table.entries[weighted_index(&table.entries, rand)].decal;
+            assert_eq!(decal, &(rand % NUM_DECALS + 1).to_string());
+        }
+    }
+
+    #[test]
+    fn test_loot_table_odds_large_weights() {
+        let mut contract = setup_contract();
+        let mut table = single_entry_table("1", Rarity::Common);
+        table.prize_numerator = u32::MAX;
+        table.prize_denominator = u32::MAX;
+        table.entries[0].weight = u32::MAX;
+        contract.set_loot_table(table);
+
+        assert_eq!(contract.loot_table().entries[0].odds_bps, 10_000);
+    }
+
+    #[test]
+    fn test_duplicate_drops_nothing() {
+        let mut contract = setup_contract();
+        let mut table = single_entry_table("7", Rarity::Common);
+        table.duplicate_policy = DuplicatePolicy::Nothing;
+        contract.set_loot_table(table);
+
+        let token_id = "megakart".to_string();
+        let loot_drop = contract.roll_loot(&token_id);
+
+        assert_eq!(loot_drop.decal, "0");
+        assert_eq!(loot_drop.repair_kits, 0);
+        let nk = contract.near_kart_get_config(token_id);
+        assert_eq!(nk.repair_kits, 0);
+        assert_eq!(nk.extra1, "7");
+    }
+
+    #[test]
+    fn test_duplicate_converted_to_repair_kit() {
+        let mut contract = setup_contract();
+        // The default table with every prize rolling the NEAR decal
+        let mut table = LootTable::default_table();
+        table.prize_numerator = 1;
+        table.prize_denominator = 1;
+        table.entries.retain(|entry| entry.decal == "7");
+        contract.set_loot_table(table);
+
+        let token_id = "megakart".to_string();
+        let loot_drop = contract.roll_loot(&token_id);
+
+        assert_eq!(loot_drop.decal, "0");
+        assert_eq!(loot_drop.repair_kits, 1);
+        let nk = contract.near_kart_get_config(token_id);
+        assert_eq!(nk.repair_kits, 1);
+        assert_eq!(nk.extra1, "7");
+    }
+
+    #[test]
+    fn test_duplicate_rerolled() {
+        let mut contract = setup_contract();
+        let mut table = single_entry_table("7", Rarity::Common);
+        table.duplicate_policy = DuplicatePolicy::Reroll;
+        table.entries.push(LootEntry {
+            decal: String::from("2"),
+            rarity: Rarity::Common,
+            weight: 1,
+        });
+        table.entries[0].weight = 1000;
+        contract.set_loot_table(table);
+
+        let token_id = "megakart".to_string();
+        let loot_drop = contract.roll_loot(&token_id);
+
+        assert_eq!(loot_drop.decal, "2");
+        let nk = contract.near_kart_get_config(token_id);
+        assert_eq!(nk.extra1, "7,2");
+    }
+
+    #[test]
+    fn test_pity_timer() {
+        let mut contract = setup_contract();
+        let mut table = single_entry_table("1", Rarity::Common);
+        table.prize_numerator = 0;
+        table.pity_threshold = 3;
+        table.pity_rarity = Rarity::Epic;
+        table.entries.push(LootEntry {
+            decal: String::from("5"),
+            rarity: Rarity::Epic,
+            weight: 1,
+        });
+        contract.set_loot_table(table);
+
+        let token_id = "megakart".to_string();
+        for _ in 0..3 {
+            let loot_drop = contract.roll_loot(&token_id);
+            assert_eq!(loot_drop.decal, "0");
+        }
+        assert_eq!(contract.get_pity_counter(token_id.clone()), 3);
+
+        let loot_drop = contract.roll_loot(&token_id);
+        assert_eq!(loot_drop.decal, "5");
+        assert_eq!(contract.get_pity_counter(token_id), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:702:Loot entry decal is not a decal")]
+    fn test_set_loot_table_invalid_decal() {
+        let mut contract = setup_contract();
+        contract.set_loot_table(single_entry_table("8", Rarity::Common));
+    }
+}
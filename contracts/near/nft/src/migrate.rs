@@ -0,0 +1,220 @@
+/*
+Upgrading the deployed contract.
+NOTES:
+  - `migrate` reads the state of the first deployed version, `BaseContract`, and starts every
+    collection added since then empty, the loot table and prices at their defaults. Deploy the
+    new code and call `migrate` in the same transaction.
+  - The first version had no metadata `base_uri`, `migrate` sets it to `IPFS_GATEWAY` so the CID
+    media of existing karts resolves like that of new ones.
+  - Karts minted before the upgrade are not in the kart list yet. The contract owner adds them
+    with `backfill_token_index`, a page at a time in token id order, then registers their names
+    with `backfill_kart_names`. Minting and renaming are paused until both are done, battles
+    only meet karts indexed so far.
+  - `StorageKey` variants of the first version keep their position, new keys go after them so
+    a new collection never shares a prefix with an old one.
+*/
+use crate::*;
+
+/// State layout of the first deployed version.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct BaseContract {
+    pub(crate) tokens: NonFungibleToken,
+    pub(crate) metadata: LazyOption<NFTContractMetadata>,
+    pub(crate) signer_pub_keys: UnorderedSet<String>,
+    pub(crate) prev_block_index: near_sdk::BlockHeight,
+    pub(crate) random_buffer: Vector<u8>,
+    pub(crate) random_index: u8,
+    pub(crate) last_battle: LookupMap<AccountId, SimpleBattle>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Upgrade the state of the first deployed version.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        Contract::assert_contract_owner();
+
+        let base: BaseContract =
+            env::state_read().unwrap_or_else(|| panic_error(KartError::ContractStateNotFound));
+        let mut contract = Contract::from_base(base);
+        if let Some(mut metadata) = contract.metadata.get() {
+            metadata.base_uri = Some(IPFS_GATEWAY.to_string());
+            contract.metadata.set(&metadata);
+        }
+        contract.indexing_tokens = contract.tokens.owner_by_id.len() > 0;
+        return contract;
+    }
+
+    /// Add the next `limit` karts minted before the upgrade to the kart list. Returns whether
+    /// karts are left to index.
+    pub fn backfill_token_index(&mut self, limit: u64) -> bool {
+        Contract::assert_contract_owner();
+
+        if !self.indexing_tokens {
+            return false;
+        }
+
+        // Carry on after the last kart indexed
+        let mut next = match &self.last_indexed_token_id {
+            Some(token_id) => self.tokens.owner_by_id.higher(token_id),
+            None => self.tokens.owner_by_id.min(),
+        };
+
+        let mut indexed = 0;
+        while indexed < limit {
+            let token_id = match next {
+                Some(token_id) => token_id,
+                None => break,
+            };
+            if self.token_index.get(&token_id).is_none() {
+                self.index_token(&token_id);
+            }
+
+            next = self.tokens.owner_by_id.higher(&token_id);
+            self.last_indexed_token_id = Some(token_id);
+            indexed += 1;
+        }
+
+        if next.is_none() {
+            // The kart list is complete, names are registered from its end
+            self.indexing_tokens = false;
+            self.last_indexed_token_id = None;
+            self.kart_names_to_backfill = self.token_ids.len();
+        }
+        return self.indexing_tokens;
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::VMContextBuilder;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Write the state of the first version, with karts "0" and "1" sharing a title.
+    fn setup_base() -> ValidAccountId {
+        let br_acc = owner_id();
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(contract_id());
+        set_caller(&mut context, br_acc.clone(), MINT_STORAGE_COST);
+
+        let mut base = BaseContract {
+            tokens: NonFungibleToken::new(
+                StorageKey::NonFungibleToken,
+                br_acc.clone(),
+                Some(StorageKey::TokenMetadata),
+                Some(StorageKey::Enumeration),
+                Some(StorageKey::Approval),
+            ),
+            metadata: LazyOption::new(
+                StorageKey::Metadata,
+                Some(&NFTContractMetadata {
+                    spec: NFT_METADATA_SPEC.to_string(),
+                    name: "NEAR Karts".to_string(),
+                    symbol: "NEARKARTS".to_string(),
+                    icon: None,
+                    base_uri: None,
+                    reference: None,
+                    reference_hash: None,
+                }),
+            ),
+            signer_pub_keys: UnorderedSet::new(StorageKey::SignerKey),
+            prev_block_index: 7,
+            random_buffer: Vector::new(StorageKey::RandomBufferKey),
+            random_index: 3,
+            last_battle: LookupMap::new(StorageKey::LastBattleKey),
+        };
+        for (token_id, title) in [("0", "Old Kart"), ("1", "old kart")].iter() {
+            base.tokens.mint(
+                token_id.to_string(),
+                br_acc.clone(),
+                Some(TokenMetadata {
+                    title: Some(title.to_string()),
+                    description: None,
+                    media: None,
+                    media_hash: None,
+                    copies: None,
+                    issued_at: None,
+                    expires_at: None,
+                    starts_at: None,
+                    updated_at: None,
+                    extra: Some(NearKart::new().serialize()),
+                    reference: None,
+                    reference_hash: None,
+                }),
+            );
+        }
+        base.signer_pub_keys.insert(&"signer".to_string());
+        base.last_battle.insert(
+            br_acc.as_ref(),
+            &SimpleBattle {
+                home_token_id: "0".to_string(),
+                away_token_id: "1".to_string(),
+                winner: 0,
+                battle: 42,
+                prize: "0".to_string(),
+                extra: "".to_string(),
+            },
+        );
+        env::state_write(&base);
+
+        return br_acc;
+    }
+
+    #[test]
+    fn test_migrate() {
+        let br_acc = setup_base();
+
+        let mut contract = Contract::migrate();
+        assert!(contract.signer_pub_keys.contains(&"signer".to_string()));
+        assert_eq!(contract.random_index, 3);
+        assert_eq!(contract.get_last_battle(br_acc).battle, 42);
+        assert_eq!(contract.get_prices().mint.0, DEFAULT_MINT_PRICE);
+        assert_eq!(contract.get_num_karts(), 0);
+        let metadata = contract.nft_metadata();
+        assert_eq!(metadata.name, "NEAR Karts");
+        assert_eq!(metadata.base_uri, Some(IPFS_GATEWAY.to_string()));
+
+        assert!(contract.backfill_token_index(1));
+        assert!(!contract.backfill_token_index(1));
+        assert_eq!(contract.get_num_karts(), 2);
+        assert_eq!(contract.get_token_id_by_index(1), "1");
+
+        // The first kart registered from the end of the list keeps the shared name
+        assert_eq!(contract.backfill_kart_names(10), 0);
+        let kart = contract.kart_by_name("OLD KART".to_string()).unwrap();
+        assert_eq!(kart.token_id, "1");
+    }
+
+    #[test]
+    fn test_play_after_migrate() {
+        let br_acc = setup_base();
+
+        let mut contract = Contract::migrate();
+        assert!(!contract.backfill_token_index(10));
+        assert_eq!(contract.backfill_kart_names(10), 0);
+
+        // Karts minted before durability existed battle, wear and get repaired
+        let battle = contract.game_simple_battle("0".to_string());
+        assert_eq!(battle.away_token_id, "1");
+        assert_eq!(contract.get_last_battle(br_acc).battle, battle.battle);
+        let nk = contract.near_kart_get_config("0".to_string());
+        assert!(nk.repair_points_needed() > 0);
+
+        contract.repair_kart("0".to_string());
+        let nk = contract.near_kart_get_config("0".to_string());
+        assert_eq!(nk.repair_points_needed(), 0);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:114:Karts minted before the upgrade are being indexed, try again later"
+    )]
+    fn test_names_wait_for_token_index() {
+        setup_base();
+
+        let mut contract = Contract::migrate();
+        contract.backfill_kart_names(10);
+    }
+}
@@ -0,0 +1,318 @@
+/*
+Kart names.
+NOTES:
+  - The name passed to `nft_mint` is normalized with `normalize_kart_name` and stored as the
+    token title. Names are unique ignoring case, a burned kart frees its name.
+  - Karts minted before the registry are registered under their title by the contract owner with
+    `backfill_kart_names`, once `backfill_token_index` listed them. It goes a page at a time
+    from the end of the kart list so a burn moving the last kart can't skip one. Minting and
+    renaming are paused until the backfill is done. When
+    two old karts share a title the first registered keeps the name, the other keeps its title
+    but holds no name until renamed.
+  - The contract owner keeps a blocklist of words. A name is blocked when its letters and digits
+    contain a blocked word, see `compact_kart_name`.
+  - `rename_kart` costs the `rename` price and frees the previous name.
+*/
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Give a kart a new name, freeing its old one.
+    #[payable]
+    pub fn rename_kart(&mut self, token_id: TokenId, new_name: String) -> String {
+        self.assert_nft_owner(token_id.clone());
+        if env::attached_deposit() < self.get_prices().rename.0 {
+            panic_error(KartError::RenamePaymentTooLow);
+        }
+
+        let old_name = self.nft_get_metadata_title(token_id.clone());
+        self.release_kart_name(&token_id);
+        let new_name = self.register_kart_name(&token_id, &new_name);
+
+        let lookup_map = self.tokens.token_metadata_by_id.as_mut().unwrap();
+        let mut metadata = lookup_map.get(&token_id).unwrap();
+        metadata.title = Some(new_name.clone());
+        lookup_map.insert(&token_id, &metadata);
+
+        EventLog::new(EventLogVariant::KartRename(vec![KartRenameLog {
+            owner_id: env::predecessor_account_id(),
+            token_id: token_id,
+            old_name: old_name,
+            new_name: new_name.clone(),
+        }]))
+        .emit();
+
+        return new_name;
+    }
+
+    /// The kart with this name, in any case.
+    pub fn kart_by_name(&self, name: String) -> Option<KartView> {
+        let key = kart_name_key(&name).ok()?;
+        let token_id = self.kart_names.get(&key)?;
+        return self.kart_view(token_id);
+    }
+
+    /// Register the titles of up to `limit` karts minted before the name registry. Returns how
+    /// many karts are left to backfill.
+    pub fn backfill_kart_names(&mut self, limit: u64) -> u64 {
+        Contract::assert_contract_owner();
+        if self.indexing_tokens {
+            panic_error(KartError::TokensIndexing);
+        }
+
+        let end = cmp::min(self.kart_names_to_backfill, self.token_ids.len());
+        let start = end.saturating_sub(limit);
+        for index in start..end {
+            let token_id = self.token_ids.get(index).unwrap();
+            let title = self.nft_get_metadata_title(token_id.clone());
+            if let Ok(key) = kart_name_key(&title) {
+                if self.kart_names.get(&key).is_none() {
+                    self.kart_names.insert(&key, &token_id);
+                }
+            }
+        }
+
+        self.kart_names_to_backfill = start;
+        return start;
+    }
+
+    /// Block names containing any of these words.
+    pub fn add_blocked_names(&mut self, words: Vec<String>) {
+        Contract::assert_contract_owner();
+
+        for word in words.iter() {
+            let word = compact_kart_name(word);
+            if !word.is_empty() {
+                self.blocked_names.insert(&word);
+            }
+        }
+    }
+
+    pub fn remove_blocked_names(&mut self, words: Vec<String>) {
+        Contract::assert_contract_owner();
+
+        for word in words.iter() {
+            self.blocked_names.remove(&compact_kart_name(word));
+        }
+    }
+
+    pub fn get_blocked_names(&self) -> Vec<String> {
+        return self.blocked_names.to_vec();
+    }
+}
+
+impl Contract {
+    /// Validate and claim a name for a kart. Returns the normalized name.
+    pub(crate) fn register_kart_name(&mut self, token_id: &TokenId, name: &str) -> String {
+        if self.indexing_tokens || self.kart_names_to_backfill > 0 {
+            panic_error(KartError::KartNamesBackfilling);
+        }
+        let name = normalize_kart_name(name).unwrap_or_else(|e| panic_error(e));
+
+        let compact = compact_kart_name(&name);
+        if self
+            .blocked_names
+            .iter()
+            .any(|word| compact.contains(&word))
+        {
+            panic_error(KartError::KartNameBlocked);
+        }
+
+        let key = name.to_ascii_lowercase();
+        if self.kart_names.get(&key).is_some() {
+            panic_error(KartError::KartNameTaken);
+        }
+        self.kart_names.insert(&key, token_id);
+
+        return name;
+    }
+
+    /// Free the kart's name, if it holds one.
+    pub(crate) fn release_kart_name(&mut self, token_id: &TokenId) {
+        let title = self.nft_get_metadata_title(token_id.clone());
+        let key = match kart_name_key(&title) {
+            Ok(key) => key,
+            Err(_) => return,
+        };
+
+        if self.kart_names.get(&key).as_ref() == Some(token_id) {
+            self.kart_names.remove(&key);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_contract() -> (Contract, VMContextBuilder) {
+        let (contract, mut context) = setup_with_karts(&[]);
+        set_caller(&mut context, owner_id(), MINT_STORAGE_COST);
+        return (contract, context);
+    }
+
+    fn mint(contract: &mut Contract, token_id: &str, name: &str) {
+        contract.nft_mint(
+            token_id.to_string(),
+            owner_id(),
+            name.to_string(),
+            NearKart::new(),
+            TEST_CID.to_string(),
+            media_hash(),
+            TEST_SIG.to_string(),
+            TEST_PUB_KEY.to_string(),
+        );
+    }
+
+    #[test]
+    fn test_mint_registers_name() {
+        let (mut contract, _) = setup_contract();
+        mint(&mut contract, "megakart", "  Mega   Kart ");
+
+        assert_eq!(
+            contract.nft_get_metadata_title("megakart".to_string()),
+            "Mega Kart"
+        );
+        let kart = contract.kart_by_name("MEGA KART".to_string()).unwrap();
+        assert_eq!(kart.token_id, "megakart");
+        assert!(contract.kart_by_name("Fluffy".to_string()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:220:Another kart has this name")]
+    fn test_names_are_unique_ignoring_case() {
+        let (mut contract, _) = setup_contract();
+        mint(&mut contract, "megakart", "Mega Kart");
+        mint(&mut contract, "fluffykart", "mega kart");
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:221:The kart name contains a blocked word")]
+    fn test_blocked_names() {
+        let (mut contract, _) = setup_contract();
+        contract.add_blocked_names(vec!["Bad Word".to_string()]);
+        assert_eq!(contract.get_blocked_names(), vec!["badword".to_string()]);
+
+        mint(&mut contract, "megakart", "My bad-word kart");
+    }
+
+    #[test]
+    fn test_rename_kart() {
+        let (mut contract, _) = setup_contract();
+        mint(&mut contract, "megakart", "Mega Kart");
+
+        let name = contract.rename_kart("megakart".to_string(), "Turbo Kart".to_string());
+        assert_eq!(name, "Turbo Kart");
+        assert_eq!(
+            contract.nft_get_metadata_title("megakart".to_string()),
+            "Turbo Kart"
+        );
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"kart_rename""#)));
+
+        // The old name is free again
+        assert!(contract.kart_by_name("Mega Kart".to_string()).is_none());
+        mint(&mut contract, "fluffykart", "Mega Kart");
+
+        // Changing only the case keeps the name
+        contract.rename_kart("megakart".to_string(), "TURBO KART".to_string());
+        assert_eq!(
+            contract
+                .kart_by_name("turbo kart".to_string())
+                .unwrap()
+                .token_id,
+            "megakart"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:408:Attached deposit is below the rename price")]
+    fn test_rename_needs_payment() {
+        let (mut contract, mut context) = setup_contract();
+        mint(&mut contract, "megakart", "Mega Kart");
+
+        testing_env!(context.attached_deposit(0).build());
+        contract.rename_kart("megakart".to_string(), "Turbo Kart".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:103:Caller must be the token owner")]
+    fn test_rename_other_kart() {
+        let (mut contract, mut context) = setup_contract();
+        mint(&mut contract, "megakart", "Mega Kart");
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.rename_kart("megakart".to_string(), "Turbo Kart".to_string());
+    }
+
+    /// Forget the names of the minted karts, as if they were minted before the registry.
+    fn make_legacy(contract: &mut Contract, names: &[&str]) {
+        for name in names.iter() {
+            contract.kart_names.remove(&kart_name_key(name).unwrap());
+        }
+        contract.kart_names_to_backfill = contract.token_ids.len();
+    }
+
+    #[test]
+    fn test_backfill_kart_names() {
+        let (mut contract, _) = setup_contract();
+        mint(&mut contract, "megakart", "Mega Kart");
+        mint(&mut contract, "fluffykart", "Fluffy Kart");
+        make_legacy(&mut contract, &["Mega Kart", "Fluffy Kart"]);
+
+        assert_eq!(contract.backfill_kart_names(1), 1);
+        assert_eq!(
+            contract
+                .kart_by_name("fluffy kart".to_string())
+                .unwrap()
+                .token_id,
+            "fluffykart"
+        );
+        assert!(contract.kart_by_name("Mega Kart".to_string()).is_none());
+
+        assert_eq!(contract.backfill_kart_names(10), 0);
+        assert_eq!(
+            contract
+                .kart_by_name("MEGA KART".to_string())
+                .unwrap()
+                .token_id,
+            "megakart"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:220:Another kart has this name")]
+    fn test_backfilled_name_is_taken() {
+        let (mut contract, _) = setup_contract();
+        mint(&mut contract, "megakart", "Mega Kart");
+        make_legacy(&mut contract, &["Mega Kart"]);
+        contract.backfill_kart_names(10);
+
+        mint(&mut contract, "fluffykart", "mega kart");
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:229:Kart names are being migrated, try again later")]
+    fn test_mint_waits_for_backfill() {
+        let (mut contract, _) = setup_contract();
+        mint(&mut contract, "megakart", "Mega Kart");
+        make_legacy(&mut contract, &["Mega Kart"]);
+
+        mint(&mut contract, "fluffykart", "Mega Kart");
+    }
+
+    #[test]
+    fn test_burn_frees_name() {
+        let (mut contract, mut context) = setup_contract();
+        mint(&mut contract, "megakart", "Mega Kart");
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_burn("megakart".to_string(), None);
+        assert!(contract.kart_by_name("Mega Kart".to_string()).is_none());
+    }
+}
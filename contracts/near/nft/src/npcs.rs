@@ -0,0 +1,204 @@
+/*
+NPC opponents.
+NOTES:
+  - The contract owner seeds a pool of NPC karts, each with a kart config, a name and media. NPCs
+    are not NFTs and have no owner.
+  - `get_random_opponent` falls back to a random active NPC when there is no other kart to fight,
+    so a lone player never battles their own kart. The NPC's token id is `npc_token_id(id)`,
+    which marks it in the battle event and history.
+  - NPCs don't wear, level up or take part in seasons. A battle against one only counts for the
+    home kart, and not for its season rating.
+  - NPCs are never removed, so old battles can still show them, they are deactivated instead.
+*/
+use crate::*;
+
+pub const MAX_NPC_NAME_LENGTH: usize = 32;
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct Npc {
+    pub id: u64,
+    pub name: String,
+    /// IPFS CID of the NPC image.
+    pub media: String,
+    pub near_kart: NearKart,
+    pub active: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Add an NPC to the opponent pool. Its parts must be valid for its level.
+    pub fn add_npc(&mut self, name: String, media: String, near_kart: NearKart) -> Npc {
+        Contract::assert_contract_owner();
+
+        if name.is_empty() || name.len() > MAX_NPC_NAME_LENGTH {
+            panic_error(KartError::NpcNameInvalid);
+        }
+        validate_equip(&near_kart, &near_kart).unwrap_or_else(|e| panic_error(e));
+
+        let mut near_kart = near_kart;
+        near_kart.repair();
+
+        let npc = Npc {
+            id: self.npcs.len(),
+            name: name,
+            media: media,
+            near_kart: near_kart,
+            active: true,
+        };
+        self.npcs.push(&npc);
+
+        return npc;
+    }
+
+    /// Take an NPC out of matchmaking, or put it back.
+    pub fn set_npc_active(&mut self, npc_id: u64, active: bool) {
+        Contract::assert_contract_owner();
+
+        let mut npc = self
+            .npcs
+            .get(npc_id)
+            .unwrap_or_else(|| panic_error(KartError::NpcNotFound));
+        npc.active = active;
+        self.npcs.replace(npc_id, &npc);
+    }
+
+    pub fn get_npc(&self, npc_id: u64) -> Option<Npc> {
+        return self.npcs.get(npc_id);
+    }
+
+    pub fn get_npcs(&self) -> Vec<Npc> {
+        return self.npcs.to_vec();
+    }
+}
+
+impl Contract {
+    /// Token id of a random active NPC, if there is one.
+    pub(crate) fn random_npc_opponent(&mut self) -> Option<TokenId> {
+        let active: Vec<u64> = self
+            .npcs
+            .iter()
+            .filter(|npc| npc.active)
+            .map(|npc| npc.id)
+            .collect();
+        if active.is_empty() {
+            return None;
+        }
+
+        let rand_index = self.get_random_u32() as usize % active.len();
+        return Some(npc_token_id(active[rand_index]));
+    }
+
+    fn npc_for_token_id(&self, token_id: &TokenId) -> Option<Npc> {
+        let npc_id = npc_id_from_token_id(token_id)?;
+        return self.npcs.get(npc_id);
+    }
+
+    /// Kart config of a battle participant, a kart or an NPC.
+    pub(crate) fn battle_kart(&self, token_id: TokenId) -> NearKart {
+        match self.npc_for_token_id(&token_id) {
+            Some(npc) => npc.near_kart,
+            None => self.near_kart_get_config(token_id),
+        }
+    }
+
+    /// Metadata of a battle participant, NPCs get the title and media the web shows for karts.
+    pub(crate) fn battle_kart_metadata(&self, token_id: TokenId) -> TokenMetadata {
+        let npc = match self.npc_for_token_id(&token_id) {
+            Some(npc) => npc,
+            None => return self.nft_get_token_metadata(token_id),
+        };
+
+        return TokenMetadata {
+            title: Some(npc.name),
+            description: Some(String::from("NEAR Karts NPC")),
+            media: Some(npc.media),
+            media_hash: None,
+            copies: None,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: Some(npc.near_kart.serialize()),
+            reference: None,
+            reference_hash: None,
+        };
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Contract with a single kart "0" owned by the contract owner, calling as the owner.
+    fn setup_contract() -> (Contract, ValidAccountId, VMContextBuilder) {
+        let (contract, context) = setup_with_karts(&[("0", owner_id())]);
+        return (contract, owner_id(), context);
+    }
+
+    fn npc_kart() -> NearKart {
+        let mut nk = NearKart::new();
+        nk.level = 4;
+        nk.left = 2;
+        nk.front = 1;
+        return nk;
+    }
+
+    #[test]
+    fn test_single_kart_battles_npc() {
+        let (mut contract, br_acc, _) = setup_contract();
+        contract.add_npc("Rusty".to_string(), TEST_CID.to_string(), npc_kart());
+
+        let battle = contract.game_simple_battle("0".to_string());
+        assert_eq!(battle.away_token_id, "npc:0");
+        assert!(battle.is_npc_battle());
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""away_token_id":"npc:0""#)));
+
+        // History shows the NPC like a kart
+        let last = contract.last_battle_with_karts(br_acc);
+        assert_eq!(last.karts[1].level, 4);
+        assert_eq!(last.metadata[1].title, Some("Rusty".to_string()));
+        assert_eq!(last.metadata[1].media, Some(TEST_CID.to_string()));
+
+        // NPCs never wear
+        assert_eq!(
+            contract.get_npc(0).unwrap().near_kart.dur_left,
+            MAX_DURABILITY
+        );
+    }
+
+    #[test]
+    fn test_inactive_npcs_are_not_matched() {
+        let (mut contract, _, _) = setup_contract();
+        contract.add_npc("Rusty".to_string(), TEST_CID.to_string(), npc_kart());
+        contract.set_npc_active(0, false);
+
+        // Without an opponent the kart still fights itself
+        let battle = contract.game_simple_battle("0".to_string());
+        assert_eq!(battle.away_token_id, "0");
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:201:Unknown front weapon")]
+    fn test_npc_parts_are_validated() {
+        let (mut contract, _, _) = setup_contract();
+
+        let mut nk = npc_kart();
+        nk.front = NUM_WEAPONS_MELEE;
+        contract.add_npc("Rusty".to_string(), TEST_CID.to_string(), nk);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:104:Caller must be relative of contract owner")]
+    fn test_only_owner_adds_npcs() {
+        let (mut contract, _, mut context) = setup_contract();
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.add_npc("Rusty".to_string(), TEST_CID.to_string(), npc_kart());
+    }
+}
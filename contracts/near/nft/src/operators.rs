@@ -0,0 +1,194 @@
+/*
+Kart operators.
+NOTES:
+  - An operator is an account the owner lets battle, configure and upgrade a kart, for example
+    a game bot or a guild contract. Unlike a NEP-178 approval it can't transfer or burn the kart.
+  - Operators are kept per token and cleared when the kart changes owner, so a new owner never
+    inherits the previous owner's operators. With `nft_transfer_call` that is in
+    `nft_resolve_transfer`, once the receiver kept the kart, a kart it returns keeps them.
+  - Approving and revoking require exactly one yoctoNEAR, like `nft_burn`, so only a full access
+    key can grant access to a kart.
+*/
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Let `operator_id` battle, configure and upgrade the kart.
+    #[payable]
+    pub fn kart_approve_operator(&mut self, token_id: TokenId, operator_id: ValidAccountId) {
+        assert_one_yocto();
+        self.assert_nft_owner(token_id.clone());
+
+        let owner_id = env::predecessor_account_id();
+        let operator_id: AccountId = operator_id.into();
+        if operator_id == owner_id {
+            panic_error(KartError::OperatorIsOwner);
+        }
+
+        let mut operators = self.kart_operators(token_id.clone());
+        if !operators.contains(&operator_id) {
+            operators.push(operator_id.clone());
+            self.operators_by_id.insert(&token_id, &operators);
+        }
+
+        EventLog::new(EventLogVariant::OperatorApprove(vec![OperatorLog {
+            owner_id: owner_id,
+            token_id: token_id,
+            operator_id: operator_id,
+        }]))
+        .emit();
+    }
+
+    #[payable]
+    pub fn kart_revoke_operator(&mut self, token_id: TokenId, operator_id: ValidAccountId) {
+        assert_one_yocto();
+        self.assert_nft_owner(token_id.clone());
+
+        let operator_id: AccountId = operator_id.into();
+        let mut operators = self.kart_operators(token_id.clone());
+        if !operators.contains(&operator_id) {
+            return;
+        }
+
+        operators.retain(|id| id != &operator_id);
+        if operators.is_empty() {
+            self.operators_by_id.remove(&token_id);
+        } else {
+            self.operators_by_id.insert(&token_id, &operators);
+        }
+
+        EventLog::new(EventLogVariant::OperatorRevoke(vec![OperatorLog {
+            owner_id: env::predecessor_account_id(),
+            token_id: token_id,
+            operator_id: operator_id,
+        }]))
+        .emit();
+    }
+
+    pub fn kart_operators(&self, token_id: TokenId) -> Vec<AccountId> {
+        return self.operators_by_id.get(&token_id).unwrap_or(Vec::new());
+    }
+
+    pub fn kart_is_operator(&self, token_id: TokenId, account_id: ValidAccountId) -> bool {
+        return self
+            .kart_operators(token_id)
+            .iter()
+            .any(|id| id == account_id.as_ref());
+    }
+}
+
+impl Contract {
+    /// Panic unless `account_id` owns the kart or is one of its operators.
+    pub(crate) fn assert_nft_owner_or_operator(&self, token_id: TokenId, account_id: &AccountId) {
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| panic_error(KartError::TokenNotFound));
+
+        if account_id == &owner_id {
+            return;
+        }
+        if !self.kart_operators(token_id).contains(account_id) {
+            panic_error(KartError::NotTokenOwnerOrOperator);
+        }
+    }
+
+    /// Drop every operator of a kart that changed owner or was burned.
+    pub(crate) fn clear_operators(&mut self, token_id: &TokenId) {
+        self.operators_by_id.remove(token_id);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_contract() -> (Contract, ValidAccountId, VMContextBuilder) {
+        let (contract, mut context) = setup_with_karts(&[("0", owner_id())]);
+        testing_env!(context.attached_deposit(1).build());
+        return (contract, owner_id(), context);
+    }
+
+    #[test]
+    fn test_approve_and_revoke_operator() {
+        let (mut contract, _, _) = setup_contract();
+
+        contract.kart_approve_operator("0".to_string(), accounts(1));
+        assert!(contract.kart_is_operator("0".to_string(), accounts(1)));
+        assert!(!contract.kart_is_operator("0".to_string(), accounts(2)));
+        let logs = get_logs();
+        assert!(logs[0].contains(r#""event":"operator_approve""#));
+
+        // Approving twice keeps a single entry
+        contract.kart_approve_operator("0".to_string(), accounts(1));
+        assert_eq!(contract.kart_operators("0".to_string()).len(), 1);
+
+        contract.kart_revoke_operator("0".to_string(), accounts(1));
+        assert!(!contract.kart_is_operator("0".to_string(), accounts(1)));
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"operator_revoke""#)));
+    }
+
+    #[test]
+    fn test_operator_can_battle() {
+        let (mut contract, br_acc, mut context) = setup_contract();
+        contract.kart_approve_operator("0".to_string(), accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build());
+        let battle = contract.game_simple_battle("0".to_string());
+        assert_eq!(battle.home_token_id, "0");
+
+        // The battle is recorded as the owner's, not the operator's
+        let last_battle = contract.get_last_battle(br_acc);
+        assert_eq!(last_battle.battle, battle.battle);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:106:Caller must be the token owner or an approved operator"
+    )]
+    fn test_battle_without_operator_approval() {
+        let (mut contract, _, mut context) = setup_contract();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build());
+        contract.game_simple_battle("0".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:103:Caller must be the token owner")]
+    fn test_operator_cannot_approve_operators() {
+        let (mut contract, _, mut context) = setup_contract();
+        contract.kart_approve_operator("0".to_string(), accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.kart_approve_operator("0".to_string(), accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:107:The owner can't be an operator of own kart")]
+    fn test_owner_cannot_be_operator() {
+        let (mut contract, br_acc, _) = setup_contract();
+        contract.kart_approve_operator("0".to_string(), br_acc);
+    }
+
+    #[test]
+    fn test_transfer_clears_operators() {
+        let (mut contract, _, _) = setup_contract();
+        contract.kart_approve_operator("0".to_string(), accounts(1));
+
+        contract.nft_transfer(accounts(2), "0".to_string(), None, None);
+        assert!(contract.kart_operators("0".to_string()).is_empty());
+    }
+}
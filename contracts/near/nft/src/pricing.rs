@@ -0,0 +1,186 @@
+/*
+Prices and treasury.
+NOTES:
+  - Mint, upgrade, repair, rename, palette and finish prices are set by the contract owner, the
+    defaults are the prices the contract launched with.
+  - Payments stay on the contract account. The owner can withdraw anything above the balance
+    locked for storage, withdrawals require exactly one yoctoNEAR so they need a full access key.
+*/
+use crate::*;
+
+pub const DEFAULT_MINT_PRICE: u128 = 1e23 as u128;
+pub const DEFAULT_UPGRADE_PRICE: u128 = 1e23 as u128;
+pub const DEFAULT_RENAME_PRICE: u128 = 5e22 as u128;
+pub const DEFAULT_PALETTE_PRICE: u128 = 5e22 as u128;
+pub const DEFAULT_FINISH_PRICE: u128 = 2e23 as u128;
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct Prices {
+    pub mint: U128,
+    pub upgrade: U128,
+    pub repair_per_point: U128,
+    pub rename: U128,
+    pub palette: U128,
+    pub finish: U128,
+}
+
+impl Prices {
+    pub fn default_prices() -> Self {
+        Prices {
+            mint: U128(DEFAULT_MINT_PRICE),
+            upgrade: U128(DEFAULT_UPGRADE_PRICE),
+            repair_per_point: U128(REPAIR_PRICE_PER_POINT),
+            rename: U128(DEFAULT_RENAME_PRICE),
+            palette: U128(DEFAULT_PALETTE_PRICE),
+            finish: U128(DEFAULT_FINISH_PRICE),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_prices(&mut self, prices: Prices) {
+        Contract::assert_contract_owner();
+
+        if prices.mint.0 == 0 || prices.upgrade.0 == 0 {
+            panic_error(KartError::PriceZero);
+        }
+
+        self.prices.set(&prices);
+    }
+
+    pub fn get_prices(&self) -> Prices {
+        return self.prices.get().unwrap();
+    }
+
+    /// Balance the owner can withdraw, everything above the storage staking requirement and the
+    /// NEAR held in guild treasuries, raid reward pools and session key allowances.
+    pub fn get_treasury_balance(&self) -> U128 {
+        let storage = env::storage_usage() as u128 * env::storage_byte_cost();
+        let locked = storage + self.guild_funds + self.raid_funds + self.session_funds;
+        return U128(env::account_balance().saturating_sub(locked));
+    }
+
+    #[payable]
+    pub fn withdraw_treasury(&mut self, receiver_id: ValidAccountId, amount: U128) -> Promise {
+        assert_one_yocto();
+        Contract::assert_contract_owner();
+
+        if amount.0 == 0 {
+            panic_error(KartError::WithdrawAmountZero);
+        }
+        if amount.0 > self.get_treasury_balance().0 {
+            panic_error(KartError::TreasuryBalanceTooLow);
+        }
+
+        Promise::new(receiver_id.to_string()).transfer(amount.0)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use core::convert::TryFrom;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+
+    const CONTRACT_ACC: &str = "near_karts.muhindogalien.testnet";
+    const OWNER_ACC: &str = "muhindogalien.testnet";
+
+    fn context(predecessor: &str, deposit: u128, balance: u128) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(ValidAccountId::try_from(CONTRACT_ACC.to_string()).unwrap())
+            .predecessor_account_id(ValidAccountId::try_from(predecessor.to_string()).unwrap())
+            .attached_deposit(deposit)
+            .account_balance(balance);
+        builder
+    }
+
+    fn setup() -> Contract {
+        testing_env!(context(OWNER_ACC, 0, 10u128.pow(25)).build());
+        Contract::new_default_meta(ValidAccountId::try_from(OWNER_ACC.to_string()).unwrap())
+    }
+
+    fn prices(mint: u128) -> Prices {
+        Prices {
+            mint: U128(mint),
+            upgrade: U128(2e23 as u128),
+            repair_per_point: U128(1e19 as u128),
+            rename: U128(1e23 as u128),
+            palette: U128(1e23 as u128),
+            finish: U128(3e23 as u128),
+        }
+    }
+
+    #[test]
+    fn test_default_prices() {
+        let contract = setup();
+        let prices = contract.get_prices();
+
+        assert_eq!(prices.mint.0, DEFAULT_MINT_PRICE);
+        assert_eq!(prices.upgrade.0, DEFAULT_UPGRADE_PRICE);
+        assert_eq!(prices.repair_per_point.0, REPAIR_PRICE_PER_POINT);
+    }
+
+    #[test]
+    fn test_set_prices() {
+        let mut contract = setup();
+        contract.set_prices(prices(5e23 as u128));
+
+        assert_eq!(contract.get_prices().mint.0, 5e23 as u128);
+        assert_eq!(contract.get_prices().repair_per_point.0, 1e19 as u128);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:403:Mint and upgrade prices can't be zero")]
+    fn test_set_prices_zero() {
+        let mut contract = setup();
+        contract.set_prices(prices(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:104:Caller must be relative of contract owner")]
+    fn test_set_prices_not_owner() {
+        let mut contract = setup();
+        testing_env!(context("alice.testnet", 0, 10u128.pow(25)).build());
+        contract.set_prices(prices(5e23 as u128));
+    }
+
+    #[test]
+    fn test_withdraw_treasury() {
+        let mut contract = setup();
+        testing_env!(context(OWNER_ACC, 1, 10u128.pow(25)).build());
+
+        let available = contract.get_treasury_balance().0;
+        assert!(available > 0);
+        contract.withdraw_treasury(
+            ValidAccountId::try_from(OWNER_ACC.to_string()).unwrap(),
+            U128(available),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:405:Withdraw amount is above the treasury balance")]
+    fn test_withdraw_treasury_too_much() {
+        let mut contract = setup();
+        testing_env!(context(OWNER_ACC, 1, 10u128.pow(25)).build());
+
+        let available = contract.get_treasury_balance().0;
+        contract.withdraw_treasury(
+            ValidAccountId::try_from(OWNER_ACC.to_string()).unwrap(),
+            U128(available + 1),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_withdraw_treasury_requires_one_yocto() {
+        let mut contract = setup();
+        contract.withdraw_treasury(
+            ValidAccountId::try_from(OWNER_ACC.to_string()).unwrap(),
+            U128(1),
+        );
+    }
+}
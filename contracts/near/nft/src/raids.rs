@@ -0,0 +1,467 @@
+/*
+Boss raids.
+NOTES:
+  - The contract owner creates a raid with a boss, its HP pool and armor, and a time window. The
+    NEAR attached to `create_raid` is the reward pool, it is counted in `raid_funds` and is not
+    part of the owner treasury.
+  - Any kart can attack an active raid with `raid_attack`, once per `RAID_ATTACK_COOLDOWN`. Each
+    weapon of the kart hits once and a kart without weapons bumps, with the scores of a battle
+    replay plus the kart level, less the boss armor. Attacks wear the kart like a battle.
+  - Damage is credited to the kart owner. A raid ends when the boss is defeated or its window
+    closes, after which every account claims the part of the pool matching its share of the
+    damage dealt. A boss that was not defeated still pays out.
+  - A raid that ended without any damage has nobody to pay, the contract owner moves its pool
+    back to the owner treasury with `reclaim_raid_pool`.
+*/
+use crate::*;
+use near_sdk::json_types::U64;
+
+pub const RAID_ATTACK_COOLDOWN: u64 = 900_000_000_000;
+pub const MAX_RAID_NAME_LENGTH: usize = 32;
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct Raid {
+    pub id: u64,
+    pub boss_name: String,
+    /// IPFS CID of the boss image.
+    pub boss_media: String,
+    pub max_hp: U64,
+    pub hp: U64,
+    /// Subtracted from every hit, a hit always deals at least 1.
+    pub armor: u32,
+    pub starts_at: U64,
+    pub ends_at: U64,
+    pub reward_pool: U128,
+    pub total_damage: U64,
+}
+
+impl Raid {
+    fn has_ended(&self) -> bool {
+        return self.hp.0 == 0 || env::block_timestamp() >= self.ends_at.0;
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RaidAttack {
+    pub raid_id: u64,
+    pub token_id: TokenId,
+    pub damage: U64,
+    pub boss_hp: U64,
+}
+
+/// Damage of one attack on a boss with `armor`, rolled with the replay rng.
+pub fn raid_damage(nk: &NearKart, armor: u32, seed: u32) -> u64 {
+    let mut rng = ReplayRng::new(seed);
+    let (weapons, _) = kart_arms(nk);
+    let hits = cmp::max(weapons.len(), 1);
+
+    let mut damage = 0;
+    for _ in 0..hits {
+        let score = rng.range(BOTTOM_SCORE, TOP_SCORE) + nk.level;
+        damage += cmp::max(score.saturating_sub(armor), 1) as u64;
+    }
+    return damage;
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Create a raid, the attached deposit is its reward pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `boss_name` - Shown to players
+    /// * `boss_media` - IPFS CID of the boss image
+    /// * `max_hp` - HP pool all attacks share
+    /// * `armor` - Subtracted from every hit
+    /// * `starts_at` - Block timestamp the raid opens at
+    /// * `ends_at` - Block timestamp the raid closes at
+    ///
+    #[payable]
+    pub fn create_raid(
+        &mut self,
+        boss_name: String,
+        boss_media: String,
+        max_hp: U64,
+        armor: u32,
+        starts_at: U64,
+        ends_at: U64,
+    ) -> Raid {
+        Contract::assert_contract_owner();
+
+        if boss_name.is_empty() || boss_name.len() > MAX_RAID_NAME_LENGTH {
+            panic_error(KartError::RaidNameInvalid);
+        }
+        if max_hp.0 == 0 {
+            panic_error(KartError::RaidHpZero);
+        }
+        if ends_at.0 <= starts_at.0 {
+            panic_error(KartError::RaidEndBeforeStart);
+        }
+
+        let raid = Raid {
+            id: self.raids.len(),
+            boss_name: boss_name,
+            boss_media: boss_media,
+            max_hp: max_hp,
+            hp: max_hp,
+            armor: armor,
+            starts_at: starts_at,
+            ends_at: ends_at,
+            reward_pool: U128(env::attached_deposit()),
+            total_damage: U64(0),
+        };
+        self.raids.push(&raid);
+        self.raid_funds = self.raid_funds + raid.reward_pool.0;
+
+        return raid;
+    }
+
+    /// Attack the raid boss with a kart the caller owns or operates.
+    pub fn raid_attack(&mut self, raid_id: u64, token_id: TokenId) -> RaidAttack {
+        self.assert_nft_owner_or_operator(token_id.clone(), &env::predecessor_account_id());
+
+        let mut raid = self.raid(raid_id);
+        let now = env::block_timestamp();
+        if now < raid.starts_at.0 {
+            panic_error(KartError::RaidNotStarted);
+        }
+        if raid.has_ended() {
+            panic_error(KartError::RaidEnded);
+        }
+
+        let key = raid_key(raid_id, &token_id);
+        if let Some(last_attack) = self.raid_attacks.get(&key) {
+            if now < last_attack + RAID_ATTACK_COOLDOWN {
+                panic_error(KartError::RaidAttackCooldown);
+            }
+        }
+        self.raid_attacks.insert(&key, &now);
+
+        let nk = self.near_kart_get_config(token_id.clone());
+        let seed = self.get_random_u32();
+        let damage = cmp::min(raid_damage(&nk, raid.armor, seed), raid.hp.0);
+        self.wear_kart(token_id.clone());
+
+        raid.hp = U64(raid.hp.0 - damage);
+        raid.total_damage = U64(raid.total_damage.0 + damage);
+        self.raids.replace(raid_id, &raid);
+
+        let owner_id = self.token_owner(token_id.clone()).unwrap();
+        let damage_key = raid_key(raid_id, &owner_id);
+        let dealt = self.raid_damage.get(&damage_key).unwrap_or(0);
+        self.raid_damage.insert(&damage_key, &(dealt + damage));
+
+        EventLog::new(EventLogVariant::RaidAttack(vec![RaidAttackLog {
+            raid_id: raid_id,
+            owner_id: owner_id,
+            token_id: token_id.clone(),
+            damage: damage,
+            boss_hp: raid.hp.0,
+        }]))
+        .emit();
+
+        return RaidAttack {
+            raid_id: raid_id,
+            token_id: token_id,
+            damage: U64(damage),
+            boss_hp: raid.hp,
+        };
+    }
+
+    /// Pay the caller their share of an ended raid's reward pool.
+    pub fn claim_raid_reward(&mut self, raid_id: u64) -> U128 {
+        let raid = self.raid(raid_id);
+        if !raid.has_ended() {
+            panic_error(KartError::RaidNotEnded);
+        }
+
+        let account_id = env::predecessor_account_id();
+        let key = raid_key(raid_id, &account_id);
+        if self.raid_claims.contains(&key) {
+            panic_error(KartError::RaidRewardAlreadyClaimed);
+        }
+
+        let reward = self.raid_reward(&raid, &account_id);
+        if reward == 0 {
+            panic_error(KartError::NoRaidReward);
+        }
+
+        self.raid_claims.insert(&key);
+        self.raid_funds = self.raid_funds - reward;
+        Promise::new(account_id).transfer(reward);
+
+        return U128(reward);
+    }
+
+    /// Release the reward pool of an ended raid nobody damaged into the owner treasury.
+    pub fn reclaim_raid_pool(&mut self, raid_id: u64) -> U128 {
+        Contract::assert_contract_owner();
+
+        let mut raid = self.raid(raid_id);
+        if !raid.has_ended() {
+            panic_error(KartError::RaidNotEnded);
+        }
+        if raid.total_damage.0 > 0 {
+            panic_error(KartError::RaidPoolOwed);
+        }
+        if raid.reward_pool.0 == 0 {
+            panic_error(KartError::RaidPoolEmpty);
+        }
+
+        let pool = raid.reward_pool.0;
+        raid.reward_pool = U128(0);
+        self.raids.replace(raid_id, &raid);
+        self.raid_funds = self.raid_funds - pool;
+
+        return U128(pool);
+    }
+
+    pub fn get_raid(&self, raid_id: u64) -> Option<Raid> {
+        return self.raids.get(raid_id);
+    }
+
+    /// Raids among the `limit` raids from id `from` that have not ended yet, including ones
+    /// still to open.
+    pub fn get_active_raids(&self, from: u64, limit: u64) -> Vec<Raid> {
+        let end = cmp::min(from.saturating_add(limit), self.raids.len());
+
+        let mut raids = Vec::new();
+        for raid_id in from..end {
+            let raid = self.raids.get(raid_id).unwrap();
+            if !raid.has_ended() {
+                raids.push(raid);
+            }
+        }
+        return raids;
+    }
+
+    pub fn get_raid_damage(&self, raid_id: u64, account_id: ValidAccountId) -> U64 {
+        let damage = self
+            .raid_damage
+            .get(&raid_key(raid_id, account_id.as_ref()));
+        return U64(damage.unwrap_or(0));
+    }
+
+    /// The account's share of the reward pool, whether claimed or not.
+    pub fn get_raid_reward(&self, raid_id: u64, account_id: ValidAccountId) -> U128 {
+        let raid = self.raid(raid_id);
+        return U128(self.raid_reward(&raid, account_id.as_ref()));
+    }
+}
+
+fn raid_key(raid_id: u64, id: &str) -> String {
+    format!("{}:{}", raid_id, id)
+}
+
+impl Contract {
+    fn raid(&self, raid_id: u64) -> Raid {
+        return self
+            .raids
+            .get(raid_id)
+            .unwrap_or_else(|| panic_error(KartError::RaidNotFound));
+    }
+
+    /// Part of the reward pool matching the account's share of the damage, rounded down.
+    fn raid_reward(&self, raid: &Raid, account_id: &AccountId) -> u128 {
+        if raid.total_damage.0 == 0 {
+            return 0;
+        }
+
+        let damage = self
+            .raid_damage
+            .get(&raid_key(raid.id, account_id))
+            .unwrap_or(0);
+        return raid.reward_pool.0 * damage as u128 / raid.total_damage.0 as u128;
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    const REWARD_POOL: u128 = 1e24 as u128;
+    const RAID_END: u64 = 1_000 * RAID_ATTACK_COOLDOWN;
+
+    /// Raid 0 with the given HP, and karts "0" and "1" owned by accounts(1) and accounts(2).
+    fn setup_raid(max_hp: u64) -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) =
+            setup_with_karts(&[("0", accounts(1)), ("1", accounts(2))]);
+
+        set_caller(&mut context, owner_id(), REWARD_POOL);
+        contract.create_raid(
+            "Big Bertha".to_string(),
+            TEST_CID.to_string(),
+            U64(max_hp),
+            0,
+            U64(0),
+            U64(RAID_END),
+        );
+
+        set_caller(&mut context, accounts(1), 0);
+        return (contract, context);
+    }
+
+    #[test]
+    fn test_raid_damage() {
+        let mut nk = NearKart::new();
+        let bump = raid_damage(&nk, 0, 1);
+        assert!(bump >= (BOTTOM_SCORE + 1) as u64 && bump <= (TOP_SCORE + 1) as u64);
+
+        // Every weapon hits once
+        nk.left = 1;
+        nk.right = 2;
+        nk.front = 1;
+        let armed = raid_damage(&nk, 0, 1);
+        assert!(armed >= 3 * (BOTTOM_SCORE + 1) as u64);
+
+        // Armor never stops a hit completely
+        assert_eq!(raid_damage(&nk, 1_000, 1), 3);
+    }
+
+    #[test]
+    fn test_raid_attack() {
+        let (mut contract, _) = setup_raid(1_000_000);
+
+        let attack = contract.raid_attack(0, "0".to_string());
+        assert!(attack.damage.0 > 0);
+        assert_eq!(attack.boss_hp.0, 1_000_000 - attack.damage.0);
+        assert_eq!(contract.get_raid_damage(0, accounts(1)).0, attack.damage.0);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"raid_attack""#)));
+
+        // Attacks wear the kart
+        let nk = contract.near_kart_get_config("0".to_string());
+        assert!(nk.dur_skin < MAX_DURABILITY);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:1005:The kart attacked this raid too recently")]
+    fn test_raid_attack_cooldown() {
+        let (mut contract, _) = setup_raid(1_000_000);
+
+        contract.raid_attack(0, "0".to_string());
+        contract.raid_attack(0, "0".to_string());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:106:Caller must be the token owner or an approved operator"
+    )]
+    fn test_raid_attack_with_other_kart() {
+        let (mut contract, _) = setup_raid(1_000_000);
+        contract.raid_attack(0, "1".to_string());
+    }
+
+    #[test]
+    fn test_raid_rewards_by_damage_share() {
+        let (mut contract, mut context) = setup_raid(1_000_000);
+
+        let first = contract.raid_attack(0, "0".to_string()).damage.0;
+        set_caller(&mut context, accounts(2), 0);
+        let second = contract.raid_attack(0, "1".to_string()).damage.0;
+
+        testing_env!(context.block_timestamp(RAID_END).build());
+        let reward = contract.claim_raid_reward(0);
+        assert_eq!(
+            reward.0,
+            REWARD_POOL * second as u128 / (first + second) as u128
+        );
+        assert_eq!(
+            contract.get_raid_reward(0, accounts(1)).0,
+            REWARD_POOL * first as u128 / (first + second) as u128
+        );
+    }
+
+    #[test]
+    fn test_defeated_boss_ends_raid() {
+        let (mut contract, _) = setup_raid(1);
+
+        let attack = contract.raid_attack(0, "0".to_string());
+        assert_eq!(attack.damage.0, 1);
+        assert_eq!(attack.boss_hp.0, 0);
+        assert!(contract.get_active_raids(0, 10).is_empty());
+
+        assert_eq!(contract.claim_raid_reward(0).0, REWARD_POOL);
+    }
+
+    #[test]
+    fn test_active_raids_are_paged() {
+        let (mut contract, mut context) = setup_raid(1);
+        contract.raid_attack(0, "0".to_string());
+
+        set_caller(&mut context, owner_id(), 0);
+        for _ in 0..2 {
+            contract.create_raid(
+                "Big Bertha".to_string(),
+                TEST_CID.to_string(),
+                U64(10),
+                0,
+                U64(0),
+                U64(RAID_END),
+            );
+        }
+
+        let raids = contract.get_active_raids(0, 2);
+        assert_eq!(raids.len(), 1);
+        assert_eq!(raids[0].id, 1);
+        let raids = contract.get_active_raids(2, 10);
+        assert_eq!(raids.len(), 1);
+        assert_eq!(raids[0].id, 2);
+    }
+
+    #[test]
+    fn test_reclaim_pool_without_damage() {
+        let (mut contract, mut context) = setup_raid(1_000_000);
+
+        set_caller(&mut context, owner_id(), 0);
+        testing_env!(context.block_timestamp(RAID_END).build());
+        assert_eq!(contract.reclaim_raid_pool(0).0, REWARD_POOL);
+        assert_eq!(contract.get_raid(0).unwrap().reward_pool.0, 0);
+        assert_eq!(contract.raid_funds, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:1010:Karts dealt damage in this raid, its pool is theirs")]
+    fn test_reclaim_pool_with_damage() {
+        let (mut contract, mut context) = setup_raid(1);
+        contract.raid_attack(0, "0".to_string());
+
+        set_caller(&mut context, owner_id(), 0);
+        contract.reclaim_raid_pool(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:1011:The raid has no reward pool left")]
+    fn test_reclaim_pool_twice() {
+        let (mut contract, mut context) = setup_raid(1_000_000);
+
+        set_caller(&mut context, owner_id(), 0);
+        testing_env!(context.block_timestamp(RAID_END).build());
+        contract.reclaim_raid_pool(0);
+        contract.reclaim_raid_pool(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:1006:The raid has not ended")]
+    fn test_claim_before_raid_ends() {
+        let (mut contract, _) = setup_raid(1_000_000);
+
+        contract.raid_attack(0, "0".to_string());
+        contract.claim_raid_reward(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:1007:The raid reward was already claimed")]
+    fn test_claim_twice() {
+        let (mut contract, _) = setup_raid(1);
+
+        contract.raid_attack(0, "0".to_string());
+        contract.claim_raid_reward(0);
+        contract.claim_raid_reward(0);
+    }
+}
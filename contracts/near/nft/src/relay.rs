@@ -0,0 +1,403 @@
+/*
+Relayed actions.
+NOTES:
+  - A player registers ed25519 relay keys to their account with `add_relay_key`, then signs
+    battle or configure requests with them offchain. An allowed relayer submits the request
+    with `relay_action` and pays the gas, the action runs as the signing account.
+  - The signature is over the exact request JSON string, so clients don't have to reproduce
+    a canonical encoding. The request names this contract as `receiver_id`, a nonce above the
+    last one used by the account and a block timestamp it expires at.
+  - Each account can relay `RELAY_RATE_LIMIT` requests per `RELAY_RATE_WINDOW`.
+  - A relayed configure repaints the kart, changing only colors, finish and decals, with new
+    media signed by a registered signer like an upgrade. It needs no payment, so changing parts
+    is refused and stays with the paid, lock-gated `upgrade`.
+*/
+use crate::*;
+use near_sdk::json_types::U64;
+
+pub const RELAY_RATE_LIMIT: u32 = 60;
+pub const RELAY_RATE_WINDOW: u64 = 3_600_000_000_000;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayAction {
+    Battle {
+        token_id: TokenId,
+    },
+    Configure {
+        token_id: TokenId,
+        near_kart: NearKart,
+        cid: String,
+        media_hash: Base64VecU8,
+        sig: String,
+        pub_key: String,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RelayRequest {
+    pub signer_id: AccountId,
+    pub receiver_id: AccountId,
+    pub nonce: U64,
+    pub expires_at: U64,
+    pub action: RelayAction,
+}
+
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+pub struct RelayUsage {
+    pub window_start: u64,
+    pub count: u32,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn add_relayer(&mut self, account_id: ValidAccountId) {
+        Contract::assert_contract_owner();
+        self.relayers.insert(account_id.as_ref());
+    }
+
+    pub fn remove_relayer(&mut self, account_id: ValidAccountId) {
+        Contract::assert_contract_owner();
+        self.relayers.remove(account_id.as_ref());
+    }
+
+    pub fn get_relayers(&self) -> Vec<AccountId> {
+        return self.relayers.to_vec();
+    }
+
+    /// Register a hex ed25519 public key that signs relayed requests for the caller.
+    pub fn add_relay_key(&mut self, pub_key: String) {
+        Contract::assert_valid_pub_key(&pub_key);
+
+        let account_id = env::predecessor_account_id();
+        let mut keys = self.relay_keys_for(&account_id);
+        if !keys.contains(&pub_key) {
+            keys.push(pub_key);
+            self.relay_keys.insert(&account_id, &keys);
+        }
+    }
+
+    pub fn remove_relay_key(&mut self, pub_key: String) {
+        let account_id = env::predecessor_account_id();
+        let mut keys = self.relay_keys_for(&account_id);
+        keys.retain(|key| key != &pub_key);
+
+        if keys.is_empty() {
+            self.relay_keys.remove(&account_id);
+        } else {
+            self.relay_keys.insert(&account_id, &keys);
+        }
+    }
+
+    pub fn get_relay_keys(&self, account_id: ValidAccountId) -> Vec<String> {
+        return self.relay_keys_for(account_id.as_ref());
+    }
+
+    /// Last nonce the account used, the next request must use a higher one.
+    pub fn get_relay_nonce(&self, account_id: ValidAccountId) -> U64 {
+        return U64(self.relay_nonce_for(account_id.as_ref()));
+    }
+
+    /// Run a request signed by a player, on their behalf.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - `RelayRequest` JSON, exactly as it was signed
+    /// * `sig` - Hex ed25519 signature of `request`
+    /// * `pub_key` - Relay key of the signing account the signature was made with
+    ///
+    /// Returns the battle for a battle request.
+    pub fn relay_action(
+        &mut self,
+        request: String,
+        sig: String,
+        pub_key: String,
+    ) -> Option<SimpleBattle> {
+        if !self.relayers.contains(&env::predecessor_account_id()) {
+            panic_error(KartError::RelayerNotAllowed);
+        }
+
+        let relay_request: RelayRequest = serde_json::from_str(&request)
+            .unwrap_or_else(|_| panic_error(KartError::InvalidRelayRequest));
+        let account_id = relay_request.signer_id.clone();
+
+        if relay_request.receiver_id != env::current_account_id() {
+            panic_error(KartError::RelayReceiverMismatch);
+        }
+        if !self.relay_keys_for(&account_id).contains(&pub_key) {
+            panic_error(KartError::RelayKeyNotRegistered);
+        }
+        if !Contract::verify_sig(request, sig, pub_key) {
+            panic_error(KartError::SignatureVerificationFailed);
+        }
+        if env::block_timestamp() > relay_request.expires_at.0 {
+            panic_error(KartError::RelayRequestExpired);
+        }
+        if relay_request.nonce.0 <= self.relay_nonce_for(&account_id) {
+            panic_error(KartError::RelayNonceUsed);
+        }
+        self.relay_nonces.insert(&account_id, &relay_request.nonce.0);
+        self.use_relay_rate(&account_id);
+
+        match relay_request.action {
+            RelayAction::Battle { token_id } => {
+                return Some(self.internal_battle(token_id, &account_id));
+            }
+            RelayAction::Configure {
+                token_id,
+                near_kart,
+                cid,
+                media_hash,
+                sig,
+                pub_key,
+            } => {
+                self.relay_configure(
+                    &account_id,
+                    token_id,
+                    near_kart,
+                    cid,
+                    media_hash,
+                    sig,
+                    pub_key,
+                );
+                return None;
+            }
+        }
+    }
+}
+
+impl Contract {
+    fn relay_keys_for(&self, account_id: &AccountId) -> Vec<String> {
+        return self.relay_keys.get(account_id).unwrap_or(Vec::new());
+    }
+
+    fn relay_nonce_for(&self, account_id: &AccountId) -> u64 {
+        return self.relay_nonces.get(account_id).unwrap_or(0);
+    }
+
+    fn assert_valid_pub_key(pub_key: &String) {
+        let valid = hex::decode(pub_key)
+            .ok()
+            .and_then(|pub_key_bytes| PublicKey::from_bytes(&pub_key_bytes).ok())
+            .is_some();
+        if !valid {
+            panic_error(KartError::InvalidPublicKey);
+        }
+    }
+
+    /// Count a relayed request against the account's limit for the current window.
+    fn use_relay_rate(&mut self, account_id: &AccountId) {
+        let now = env::block_timestamp();
+        let mut usage = self.relay_usage.get(account_id).unwrap_or_default();
+
+        if now >= usage.window_start + RELAY_RATE_WINDOW {
+            usage.window_start = now;
+            usage.count = 0;
+        }
+        if usage.count >= RELAY_RATE_LIMIT {
+            panic_error(KartError::RelayRateLimited);
+        }
+
+        usage.count = usage.count + 1;
+        self.relay_usage.insert(account_id, &usage);
+    }
+
+    fn relay_configure(
+        &mut self,
+        account_id: &AccountId,
+        token_id: TokenId,
+        near_kart_new: NearKart,
+        cid: String,
+        media_hash: Base64VecU8,
+        sig: String,
+        pub_key: String,
+    ) {
+        self.assert_nft_owner_or_operator(token_id.clone(), account_id);
+        self.assert_signed_media(&cid, &media_hash, sig, pub_key);
+
+        let nk_prev = self.near_kart_get_config(token_id.clone());
+        if nk_prev.parts_differ(&near_kart_new) {
+            panic_error(KartError::RelayPartsChanged);
+        }
+
+        let mut nk = nk_prev.clone();
+        nk.apply_cosmetics(&near_kart_new);
+        Contract::assert_valid_equip(nk.clone(), nk_prev);
+
+        self.near_kart_set_config(token_id.clone(), &nk);
+        self.update_media(token_id.clone(), cid.clone(), media_hash);
+
+        EventLog::new(EventLogVariant::Configure(vec![KartMeta {
+            token_id: token_id.clone(),
+            name: self.nft_get_metadata_title(token_id),
+            media: cid,
+            reference: String::from(""),
+        }]))
+        .emit();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    // TEST_PUB_KEY is the public key of [7; 32], so it checks relay signatures too
+    fn sign(message: &str) -> String {
+        let secret = SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let keypair = Keypair { secret, public };
+        return hex::encode(keypair.sign(message.as_bytes()).to_bytes());
+    }
+
+    /// Contract with a kart "0" owned by the player, who registered the test relay key, and
+    /// accounts(1) as an allowed relayer. The context is left calling as the relayer.
+    fn setup_contract() -> (Contract, ValidAccountId, VMContextBuilder) {
+        let (mut contract, mut context) = setup_with_karts(&[("0", owner_id())]);
+        contract.add_relayer(accounts(1));
+        contract.add_relay_key(TEST_PUB_KEY.to_string());
+
+        set_caller(&mut context, accounts(1), 0);
+        return (contract, owner_id(), context);
+    }
+
+    fn request_json(signer_id: &ValidAccountId, nonce: u64, action: RelayAction) -> String {
+        let request = RelayRequest {
+            signer_id: signer_id.to_string(),
+            receiver_id: contract_id().to_string(),
+            nonce: U64(nonce),
+            expires_at: U64(1_000),
+            action: action,
+        };
+        return serde_json::to_string(&request).unwrap();
+    }
+
+    fn battle_action() -> RelayAction {
+        RelayAction::Battle {
+            token_id: "0".to_string(),
+        }
+    }
+
+    fn relay(contract: &mut Contract, request: String) -> Option<SimpleBattle> {
+        let sig = sign(&request);
+        return contract.relay_action(request, sig, TEST_PUB_KEY.to_string());
+    }
+
+    #[test]
+    fn test_relay_battle() {
+        let (mut contract, br_acc, _) = setup_contract();
+
+        let battle = relay(&mut contract, request_json(&br_acc, 1, battle_action())).unwrap();
+        assert_eq!(battle.home_token_id, "0");
+        assert_eq!(contract.get_last_battle(br_acc.clone()).battle, battle.battle);
+        assert_eq!(contract.get_relay_nonce(br_acc).0, 1);
+    }
+
+    #[test]
+    fn test_relay_configure() {
+        let (mut contract, br_acc, _) = setup_contract();
+
+        let mut near_kart = contract.near_kart_get_config("0".to_string());
+        near_kart.color1 = 0xff0000;
+        near_kart.decal2 = "7".to_string();
+        near_kart.level = 40;
+        let action = RelayAction::Configure {
+            token_id: "0".to_string(),
+            near_kart: near_kart,
+            cid: TEST_CID.to_string(),
+            media_hash: media_hash(),
+            sig: TEST_SIG.to_string(),
+            pub_key: TEST_PUB_KEY.to_string(),
+        };
+        assert!(relay(&mut contract, request_json(&br_acc, 1, action)).is_none());
+
+        let nk = contract.near_kart_get_config("0".to_string());
+        assert_eq!(nk.color1, 0xff0000);
+        assert_eq!(nk.decal2, "7");
+        assert_eq!(nk.level, 1);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:807:A relayed configure can't change parts, upgrade the kart instead"
+    )]
+    fn test_relay_configure_parts() {
+        let (mut contract, br_acc, _) = setup_contract();
+
+        let mut near_kart = contract.near_kart_get_config("0".to_string());
+        near_kart.right = 2;
+        let action = RelayAction::Configure {
+            token_id: "0".to_string(),
+            near_kart: near_kart,
+            cid: TEST_CID.to_string(),
+            media_hash: media_hash(),
+            sig: TEST_SIG.to_string(),
+            pub_key: TEST_PUB_KEY.to_string(),
+        };
+        relay(&mut contract, request_json(&br_acc, 1, action));
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:805:The relayed request nonce was already used")]
+    fn test_relay_replay() {
+        let (mut contract, br_acc, _) = setup_contract();
+
+        let request = request_json(&br_acc, 1, battle_action());
+        relay(&mut contract, request.clone());
+        relay(&mut contract, request);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:800:Caller is not an allowed relayer")]
+    fn test_relay_requires_allowed_relayer() {
+        let (mut contract, br_acc, mut context) = setup_contract();
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        relay(&mut contract, request_json(&br_acc, 1, battle_action()));
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:804:The relayed request has expired")]
+    fn test_relay_expired() {
+        let (mut contract, br_acc, mut context) = setup_contract();
+
+        testing_env!(context.block_timestamp(1_001).build());
+        relay(&mut contract, request_json(&br_acc, 1, battle_action()));
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:801:Public key is not registered to the signing account")]
+    fn test_relay_key_of_other_account() {
+        let (mut contract, _, _) = setup_contract();
+
+        // The key is registered to the kart owner, not to accounts(3)
+        relay(&mut contract, request_json(&accounts(3), 1, battle_action()));
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:306:Signature does not match the media")]
+    fn test_relay_tampered_request() {
+        let (mut contract, br_acc, _) = setup_contract();
+
+        let request = request_json(&br_acc, 1, battle_action());
+        let sig = sign(&request);
+        let tampered = request.replace(r#""nonce":"1""#, r#""nonce":"2""#);
+        contract.relay_action(tampered, sig, TEST_PUB_KEY.to_string());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:806:The account relayed too many requests, try again later"
+    )]
+    fn test_relay_rate_limit() {
+        let (mut contract, br_acc, _) = setup_contract();
+
+        for nonce in 1..=(RELAY_RATE_LIMIT as u64 + 1) {
+            relay(&mut contract, request_json(&br_acc, nonce, battle_action()));
+        }
+    }
+}
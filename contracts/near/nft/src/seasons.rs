@@ -0,0 +1,519 @@
+/*
+Seasons for ranked battles.
+NOTES:
+  - Each season runs from `start` to `end` (block timestamps in nanoseconds). Battles fought
+    inside that window update the per-season rating and win count of both karts.
+  - A kart's first battle in a season seeds its rating from the previous season, halfway
+    between the old rating and `INITIAL_RATING` (the soft reset).
+  - Standings are kept sorted in `season_standings` as battles are recorded. Once the current
+    season ended, its final standings are archived a page at a time with `archive_season_batch`
+    and the next season can only start when all of them are. A page costs the same however many
+    karts played.
+  - Archived standings can be paged with `season_results` and used to claim the rank tier
+    rewards.
+*/
+use crate::*;
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::U64;
+
+pub const INITIAL_RATING: u32 = 1000;
+const RATING_STEP: u32 = 16;
+
+/// Orders season standings best first: highest rating, then most wins, then token id.
+pub type StandingKey = (u32, u32, TokenId);
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct Season {
+    pub id: u32,
+    pub start: U64,
+    pub end: U64,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SeasonStats {
+    pub rating: u32,
+    pub wins: u32,
+    pub battles: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SeasonResult {
+    pub rank: u32,
+    pub token_id: TokenId,
+    pub rating: u32,
+    pub wins: u32,
+    pub battles: u32,
+}
+
+/// Reward for every kart finishing a season at `max_rank` or better, which is not covered
+/// by a better tier.
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SeasonRewardTier {
+    pub max_rank: u32,
+    pub decal: String,
+    pub repair_kits: u32,
+}
+
+fn soft_reset(rating: u32) -> u32 {
+    let diff = rating as i64 - INITIAL_RATING as i64;
+    return (INITIAL_RATING as i64 + diff / 2) as u32;
+}
+
+fn season_token_key(season_id: u32, token_id: &TokenId) -> String {
+    format!("{}:{}", season_id, token_id)
+}
+
+fn standing_key(token_id: &TokenId, rating: u32, wins: u32) -> StandingKey {
+    (u32::MAX - rating, u32::MAX - wins, token_id.clone())
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Start the next season, once the standings of the current one are archived.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Block timestamp the season starts at
+    /// * `end` - Block timestamp the season ends at
+    ///
+    pub fn start_season(&mut self, start: U64, end: U64) -> Season {
+        Contract::assert_contract_owner();
+
+        if end.0 <= start.0 {
+            panic_error(KartError::SeasonEndBeforeStart);
+        }
+
+        let mut id = 1;
+        if let Some(current) = self.seasons.get(self.seasons.len().saturating_sub(1)) {
+            if env::block_timestamp() < current.end.0 {
+                panic_error(KartError::CurrentSeasonNotEnded);
+            }
+            if start.0 < current.end.0 {
+                panic_error(KartError::SeasonOverlapsPrevious);
+            }
+            if !self.is_season_archived(current.id) {
+                panic_error(KartError::SeasonNotArchived);
+            }
+            id = current.id + 1;
+        }
+
+        let season = Season {
+            id: id,
+            start: start,
+            end: end,
+        };
+        self.seasons.push(&season);
+
+        return season;
+    }
+
+    /// Archive the next `limit` final standings of the ended current season, best rank first.
+    /// Returns the number of karts still to archive.
+    pub fn archive_season_batch(&mut self, limit: u64) -> u64 {
+        Contract::assert_contract_owner();
+
+        let current = match self.get_current_season() {
+            Some(current) => current,
+            None => panic_error(KartError::SeasonNotFound),
+        };
+        if env::block_timestamp() < current.end.0 {
+            panic_error(KartError::CurrentSeasonNotEnded);
+        }
+
+        let season_id = current.id;
+        let standings = self.season_standings_tree(season_id);
+        let mut results = self.season_results_vec(season_id);
+
+        // Carry on after the last kart archived
+        let mut next = match results.get(results.len().saturating_sub(1)) {
+            Some(last) => standings.higher(&standing_key(&last.token_id, last.rating, last.wins)),
+            None => standings.min(),
+        };
+
+        let mut archived = 0;
+        while archived < limit {
+            let key = match next {
+                Some(key) => key,
+                None => break,
+            };
+            let stats = standings.get(&key).unwrap();
+            let rank = results.len() as u32 + 1;
+            self.season_ranks
+                .insert(&season_token_key(season_id, &key.2), &rank);
+            results.push(&SeasonResult {
+                rank: rank,
+                token_id: key.2.clone(),
+                rating: stats.rating,
+                wins: stats.wins,
+                battles: stats.battles,
+            });
+
+            next = standings.higher(&key);
+            archived += 1;
+        }
+        self.season_results.insert(&season_id, &results);
+
+        return standings.len() - results.len();
+    }
+
+    pub fn get_current_season(&self) -> Option<Season> {
+        if self.seasons.len() == 0 {
+            return None;
+        }
+        return self.seasons.get(self.seasons.len() - 1);
+    }
+
+    pub fn get_season(&self, season_id: u32) -> Option<Season> {
+        if season_id == 0 {
+            return None;
+        }
+        return self.seasons.get((season_id - 1) as u64);
+    }
+
+    pub fn get_season_stats(&self, season_id: u32, token_id: TokenId) -> SeasonStats {
+        let stats = self.season_stats_map(season_id).get(&token_id);
+        return stats.unwrap_or_else(|| self.seed_season_stats(season_id, &token_id));
+    }
+
+    /// Archived final standings of an ended season, best rank first.
+    pub fn season_results(&self, season_id: u32, from: u64, limit: u64) -> Vec<SeasonResult> {
+        let results = self.season_results_vec(season_id);
+        let end = cmp::min(from.saturating_add(limit), results.len());
+
+        let mut page = Vec::new();
+        let mut i = from;
+        while i < end {
+            page.push(results.get(i).unwrap());
+            i += 1;
+        }
+        return page;
+    }
+
+    /// Set the rank reward tiers for a season, replacing any existing tiers.
+    pub fn set_season_rewards(&mut self, season_id: u32, tiers: Vec<SeasonRewardTier>) {
+        Contract::assert_contract_owner();
+
+        if self.get_season(season_id).is_none() {
+            panic_error(KartError::SeasonNotFound);
+        }
+
+        let mut tiers = tiers;
+        for tier in tiers.iter() {
+            if tier.max_rank == 0 {
+                panic_error(KartError::SeasonRewardRankInvalid);
+            }
+            if tier.decal != "" && !Contract::is_valid_decal(&tier.decal) {
+                panic_error(KartError::SeasonRewardDecalInvalid);
+            }
+        }
+        tiers.sort_by_key(|tier| tier.max_rank);
+
+        self.season_rewards.insert(&season_id, &tiers);
+    }
+
+    pub fn get_season_rewards(&self, season_id: u32) -> Vec<SeasonRewardTier> {
+        return self.season_rewards.get(&season_id).unwrap_or(Vec::new());
+    }
+
+    /// Claim the reward for the rank a kart finished an archived season at.
+    pub fn claim_season_reward(&mut self, season_id: u32, token_id: TokenId) -> SeasonRewardTier {
+        self.assert_nft_owner(token_id.clone());
+
+        let key = season_token_key(season_id, &token_id);
+        let rank = match self.season_ranks.get(&key) {
+            Some(rank) => rank,
+            None => panic_error(KartError::KartNotRankedInSeason),
+        };
+
+        if self.season_claims.contains(&key) {
+            panic_error(KartError::SeasonRewardAlreadyClaimed);
+        }
+
+        let tiers = self.get_season_rewards(season_id);
+        let tier = match tiers.into_iter().find(|tier| rank <= tier.max_rank) {
+            Some(tier) => tier,
+            None => panic_error(KartError::NoSeasonRewardForRank),
+        };
+
+        let mut nk = self.near_kart_get_config(token_id.clone());
+        if tier.decal != "" {
+            nk.unlock_decal(&tier.decal);
+        }
+        nk.repair_kits = nk.repair_kits + tier.repair_kits;
+        self.near_kart_set_config(token_id.clone(), &nk);
+
+        self.season_claims.insert(&key);
+
+        EventLog::new(EventLogVariant::Prize(vec![PrizeLog {
+            owner_id: env::predecessor_account_id(),
+            token_id: token_id,
+            decal: if tier.decal == "" {
+                String::from("0")
+            } else {
+                tier.decal.clone()
+            },
+            repair_kits: tier.repair_kits,
+        }]))
+        .emit();
+
+        return tier;
+    }
+}
+
+impl Contract {
+    fn season_stats_map(&self, season_id: u32) -> UnorderedMap<TokenId, SeasonStats> {
+        self.season_stats
+            .get(&season_id)
+            .unwrap_or_else(|| UnorderedMap::new(StorageKey::SeasonStats { season_id }))
+    }
+
+    fn season_standings_tree(&self, season_id: u32) -> TreeMap<StandingKey, SeasonStats> {
+        self.season_standings
+            .get(&season_id)
+            .unwrap_or_else(|| TreeMap::new(StorageKey::SeasonStandings { season_id }))
+    }
+
+    fn is_season_archived(&self, season_id: u32) -> bool {
+        return self.season_results_vec(season_id).len()
+            == self.season_standings_tree(season_id).len();
+    }
+
+    fn season_results_vec(&self, season_id: u32) -> Vector<SeasonResult> {
+        self.season_results
+            .get(&season_id)
+            .unwrap_or_else(|| Vector::new(StorageKey::SeasonResults { season_id }))
+    }
+
+    fn seed_season_stats(&self, season_id: u32, token_id: &TokenId) -> SeasonStats {
+        let mut rating = INITIAL_RATING;
+        if season_id > 1 {
+            if let Some(prev) = self.season_stats_map(season_id - 1).get(token_id) {
+                rating = soft_reset(prev.rating);
+            }
+        }
+
+        SeasonStats {
+            rating: rating,
+            wins: 0,
+            battles: 0,
+        }
+    }
+
+    /// The season battles count towards, if one is running.
+    fn active_season_id(&self) -> Option<u32> {
+        let now = env::block_timestamp();
+        match self.get_current_season() {
+            Some(season) if season.start.0 <= now && now < season.end.0 => Some(season.id),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn record_season_battle(
+        &mut self,
+        home_token_id: &TokenId,
+        away_token_id: &TokenId,
+        home_won: bool,
+    ) {
+        // Fighting itself or an NPC doesn't count for the season
+        if home_token_id == away_token_id || npc_id_from_token_id(away_token_id).is_some() {
+            return;
+        }
+
+        let season_id = match self.active_season_id() {
+            Some(season_id) => season_id,
+            None => return,
+        };
+
+        let mut stats_map = self.season_stats_map(season_id);
+        let mut standings = self.season_standings_tree(season_id);
+        let mut home = self.get_season_stats(season_id, home_token_id.clone());
+        let mut away = self.get_season_stats(season_id, away_token_id.clone());
+        standings.remove(&standing_key(home_token_id, home.rating, home.wins));
+        standings.remove(&standing_key(away_token_id, away.rating, away.wins));
+
+        home.battles = home.battles + 1;
+        away.battles = away.battles + 1;
+
+        if home_won {
+            home.wins = home.wins + 1;
+            home.rating = home.rating + RATING_STEP;
+            away.rating = away.rating.saturating_sub(RATING_STEP);
+        } else {
+            away.wins = away.wins + 1;
+            away.rating = away.rating + RATING_STEP;
+            home.rating = home.rating.saturating_sub(RATING_STEP);
+        }
+
+        stats_map.insert(home_token_id, &home);
+        stats_map.insert(away_token_id, &away);
+        self.season_stats.insert(&season_id, &stats_map);
+
+        standings.insert(&standing_key(home_token_id, home.rating, home.wins), &home);
+        standings.insert(&standing_key(away_token_id, away.rating, away.wins), &away);
+        self.season_standings.insert(&season_id, &standings);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Season 1 from 0 to 1_000 with karts "a", "b" and "c" of the caller, ranked a, c, b.
+    fn setup_contract() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) =
+            setup_with_karts(&[("a", owner_id()), ("b", owner_id()), ("c", owner_id())]);
+        testing_env!(context.block_timestamp(10).build());
+        contract.start_season(U64(0), U64(1_000));
+
+        // a beats b, c beats b, a beats c
+        contract.record_season_battle(&"a".to_string(), &"b".to_string(), true);
+        contract.record_season_battle(&"c".to_string(), &"b".to_string(), true);
+        contract.record_season_battle(&"a".to_string(), &"c".to_string(), true);
+        return (contract, context);
+    }
+
+    #[test]
+    fn test_archive_season_in_batches() {
+        let (mut contract, mut context) = setup_contract();
+
+        testing_env!(context.block_timestamp(1_000).build());
+        assert_eq!(contract.archive_season_batch(2), 1);
+        assert_eq!(contract.archive_season_batch(2), 0);
+
+        let results = contract.season_results(1, 0, 10);
+        let ranked: Vec<(u32, &str, u32)> = results
+            .iter()
+            .map(|r| (r.rank, r.token_id.as_str(), r.rating))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (1, "a", INITIAL_RATING + 2 * RATING_STEP),
+                (2, "c", INITIAL_RATING),
+                (3, "b", INITIAL_RATING - 2 * RATING_STEP),
+            ]
+        );
+        assert_eq!(results[0].wins, 2);
+        assert_eq!(results[2].battles, 2);
+
+        let season = contract.start_season(U64(1_000), U64(2_000));
+        assert_eq!(season.id, 2);
+    }
+
+    /// End season 1, archive it and set its rewards: decal "5" and 2 kits for the winner,
+    /// 1 kit for the runner-up.
+    fn setup_rewards(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.archive_season_batch(10);
+        contract.set_season_rewards(
+            1,
+            vec![
+                SeasonRewardTier {
+                    max_rank: 2,
+                    decal: String::new(),
+                    repair_kits: 1,
+                },
+                SeasonRewardTier {
+                    max_rank: 1,
+                    decal: String::from("5"),
+                    repair_kits: 2,
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn test_claim_season_reward() {
+        let (mut contract, mut context) = setup_contract();
+        setup_rewards(&mut contract, &mut context);
+
+        let tier = contract.claim_season_reward(1, "a".to_string());
+        assert_eq!(tier.max_rank, 1);
+        let nk = contract.near_kart_get_config("a".to_string());
+        assert_eq!(nk.extra1, "7,5");
+        assert_eq!(nk.repair_kits, 2);
+
+        let tier = contract.claim_season_reward(1, "c".to_string());
+        assert_eq!(tier.max_rank, 2);
+        let nk = contract.near_kart_get_config("c".to_string());
+        assert_eq!(nk.extra1, "7");
+        assert_eq!(nk.repair_kits, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:606:The season reward was already claimed")]
+    fn test_claim_season_reward_twice() {
+        let (mut contract, mut context) = setup_contract();
+        setup_rewards(&mut contract, &mut context);
+
+        contract.claim_season_reward(1, "a".to_string());
+        contract.claim_season_reward(1, "a".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:605:No reward for this rank")]
+    fn test_claim_season_reward_out_of_tiers() {
+        let (mut contract, mut context) = setup_contract();
+        setup_rewards(&mut contract, &mut context);
+
+        // "b" finished third
+        contract.claim_season_reward(1, "b".to_string());
+    }
+
+    #[test]
+    fn test_soft_reset_into_next_season() {
+        let (mut contract, mut context) = setup_contract();
+
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.archive_season_batch(10);
+        contract.start_season(U64(1_000), U64(2_000));
+
+        // Halfway back to the initial rating: 1032 -> 1016, 968 -> 984
+        assert_eq!(
+            contract.get_season_stats(2, "a".to_string()).rating,
+            INITIAL_RATING + RATING_STEP
+        );
+        assert_eq!(
+            contract.get_season_stats(2, "b".to_string()).rating,
+            INITIAL_RATING - RATING_STEP
+        );
+
+        testing_env!(context.block_timestamp(1_500).build());
+        contract.record_season_battle(&"b".to_string(), &"a".to_string(), true);
+        let a = contract.get_season_stats(2, "a".to_string());
+        assert_eq!(a.rating, INITIAL_RATING);
+        assert_eq!(a.battles, 1);
+        assert_eq!(
+            contract.get_season_stats(2, "b".to_string()).rating,
+            INITIAL_RATING
+        );
+        // Season 1 is left as it ended
+        assert_eq!(
+            contract.get_season_stats(1, "a".to_string()).rating,
+            INITIAL_RATING + 2 * RATING_STEP
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "KART_ERR:609:The standings of the current season are not archived yet"
+    )]
+    fn test_start_season_before_archived() {
+        let (mut contract, mut context) = setup_contract();
+
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.archive_season_batch(2);
+        contract.start_season(U64(1_000), U64(2_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:601:The current season has not ended")]
+    fn test_archive_running_season() {
+        let (mut contract, _) = setup_contract();
+        contract.archive_season_batch(10);
+    }
+}
@@ -0,0 +1,347 @@
+/*
+Session keys.
+NOTES:
+  - `register_session_key` adds a function call access key to the contract account, limited to
+    `SESSION_METHOD_NAMES` on this contract. Which player it belongs to is recorded in
+    `on_session_key_added` once the key was added, if adding it failed the deposit is refunded.
+    The web client signs with it as the contract account, so battles and guild challenges need
+    no wallet popup.
+  - A call made with a session key has the contract as predecessor and signer, the player is
+    looked up by `env::signer_account_pk()`.
+  - Gas spent with the key comes out of the contract balance, so the player attaches the
+    allowance as a deposit when registering. The deposit is not refunded when the key is
+    removed: the contract can't tell what the key spent, calls that fail burn gas too. While
+    the key may still spend it, the allowance is counted in `session_funds` and is not part of
+    the owner treasury. Only a key that was never added gets its deposit back.
+  - Sessions expire after `SESSION_DURATION`. An expired key is refused, and anyone may remove
+    it with `revoke_session_key`.
+*/
+use crate::*;
+use near_sdk::json_types::{Base58PublicKey, U64};
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+// Comma separated, the format access key method names are given in
+pub const SESSION_METHOD_NAMES: &str =
+    "game_simple_battle,challenge_guild,accept_guild_challenge,decline_guild_challenge";
+pub const SESSION_DURATION: u64 = 86_400_000_000_000;
+const GAS_FOR_SESSION_KEY_ADDED: Gas = 10_000_000_000_000;
+
+#[ext_contract(ext_self)]
+trait SessionCallbacks {
+    fn on_session_key_added(&mut self, public_key: Base58PublicKey, session: SessionKey) -> bool;
+}
+
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SessionKey {
+    pub account_id: AccountId,
+    pub allowance: U128,
+    pub expires_at: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Add a session key for the caller, limited to the game methods.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key` - Key the web client signs with, e.g. "ed25519:..."
+    /// * `allowance` - yoctoNEAR of gas the key may spend, must be attached
+    ///
+    #[payable]
+    pub fn register_session_key(
+        &mut self,
+        public_key: Base58PublicKey,
+        allowance: U128,
+    ) -> Promise {
+        if allowance.0 == 0 {
+            panic_error(KartError::SessionAllowanceZero);
+        }
+        if env::attached_deposit() < allowance.0 {
+            panic_error(KartError::SessionPaymentTooLow);
+        }
+
+        let key: Vec<u8> = public_key.clone().into();
+        if self.session_keys.get(&key).is_some() {
+            panic_error(KartError::SessionKeyExists);
+        }
+
+        let session = SessionKey {
+            account_id: env::predecessor_account_id(),
+            allowance: allowance,
+            expires_at: U64(env::block_timestamp() + SESSION_DURATION),
+        };
+        self.session_funds = self.session_funds + allowance.0;
+
+        return Promise::new(env::current_account_id())
+            .add_access_key(
+                key,
+                allowance.0,
+                env::current_account_id(),
+                SESSION_METHOD_NAMES.as_bytes().to_vec(),
+            )
+            .then(ext_self::on_session_key_added(
+                public_key,
+                session,
+                &env::current_account_id(),
+                0,
+                GAS_FOR_SESSION_KEY_ADDED,
+            ));
+    }
+
+    /// Record the session once its access key was added, or refund the allowance.
+    #[private]
+    pub fn on_session_key_added(
+        &mut self,
+        public_key: Base58PublicKey,
+        session: SessionKey,
+    ) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            let public_key: Vec<u8> = public_key.into();
+            self.session_keys.insert(&public_key, &session);
+            return true;
+        }
+
+        self.session_funds = self.session_funds - session.allowance.0;
+        Promise::new(session.account_id).transfer(session.allowance.0);
+        return false;
+    }
+
+    /// Remove a session key, by the player it belongs to or by anyone once it has expired.
+    ///
+    /// Its allowance is not refunded and goes to the owner treasury.
+    pub fn revoke_session_key(&mut self, public_key: Base58PublicKey) {
+        let public_key: Vec<u8> = public_key.into();
+        let session = self
+            .session_keys
+            .get(&public_key)
+            .unwrap_or_else(|| panic_error(KartError::SessionKeyNotFound));
+
+        let expired = env::block_timestamp() >= session.expires_at.0;
+        if !expired && env::predecessor_account_id() != session.account_id {
+            panic_error(KartError::NotSessionKeyAccount);
+        }
+
+        self.session_keys.remove(&public_key);
+        self.session_funds = self.session_funds - session.allowance.0;
+        Promise::new(env::current_account_id()).delete_key(public_key);
+    }
+
+    pub fn get_session_key(&self, public_key: Base58PublicKey) -> Option<SessionKey> {
+        let public_key: Vec<u8> = public_key.into();
+        return self.session_keys.get(&public_key);
+    }
+}
+
+impl Contract {
+    /// Account a game method acts for: the session's player when called with a session key,
+    /// otherwise the predecessor.
+    pub(crate) fn session_or_predecessor(&self) -> AccountId {
+        let predecessor_id = env::predecessor_account_id();
+        let contract_id = env::current_account_id();
+        if predecessor_id != contract_id || env::signer_account_id() != contract_id {
+            return predecessor_id;
+        }
+
+        let session = match self.session_keys.get(&env::signer_account_pk()) {
+            Some(session) => session,
+            None => return predecessor_id,
+        };
+        if env::block_timestamp() >= session.expires_at.0 {
+            panic_error(KartError::SessionKeyExpired);
+        }
+        return session.account_id;
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use core::convert::TryFrom;
+    use near_sdk::test_utils::{accounts, testing_env_with_promise_results, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    const ALLOWANCE: u128 = 250_000_000_000_000_000_000_000;
+
+    fn session_public_key() -> Base58PublicKey {
+        let mut key = vec![0u8];
+        key.extend_from_slice(&[9; 32]);
+        Base58PublicKey(key)
+    }
+
+    /// Contract with a kart "0" owned by the player, calling as the player.
+    fn setup_contract() -> (Contract, ValidAccountId, VMContextBuilder) {
+        let (contract, mut context) = setup_with_karts(&[("0", owner_id())]);
+        set_caller(&mut context, owner_id(), ALLOWANCE);
+        return (contract, owner_id(), context);
+    }
+
+    /// Register the session key as the player and run the callback with `result` of adding
+    /// its access key.
+    fn register(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        result: PromiseResult,
+    ) -> bool {
+        let player = env::predecessor_account_id();
+        contract.register_session_key(session_public_key(), U128(ALLOWANCE));
+
+        let session = SessionKey {
+            account_id: player.clone(),
+            allowance: U128(ALLOWANCE),
+            expires_at: U64(env::block_timestamp() + SESSION_DURATION),
+        };
+        testing_env_with_promise_results(
+            context
+                .predecessor_account_id(contract_id())
+                .attached_deposit(0)
+                .build(),
+            result,
+        );
+        let added = contract.on_session_key_added(session_public_key(), session);
+
+        let player = ValidAccountId::try_from(player).unwrap();
+        testing_env!(context.predecessor_account_id(player).build());
+        return added;
+    }
+
+    fn register_added(contract: &mut Contract, context: &mut VMContextBuilder) {
+        let added = register(contract, context, PromiseResult::Successful(vec![]));
+        assert!(added);
+    }
+
+    /// Switch the context to a call signed with the session key.
+    fn call_with_session_key(context: &mut VMContextBuilder, block_timestamp: u64) {
+        testing_env!(context
+            .predecessor_account_id(contract_id())
+            .signer_account_id(contract_id())
+            .signer_account_pk(session_public_key().into())
+            .attached_deposit(0)
+            .block_timestamp(block_timestamp)
+            .build());
+    }
+
+    #[test]
+    fn test_register_session_key() {
+        let (mut contract, br_acc, mut context) = setup_contract();
+
+        // Nothing is recorded until the access key was added
+        contract.register_session_key(session_public_key(), U128(ALLOWANCE));
+        assert!(contract.get_session_key(session_public_key()).is_none());
+        assert_eq!(contract.session_funds, ALLOWANCE);
+
+        let session = SessionKey {
+            account_id: br_acc.to_string(),
+            allowance: U128(ALLOWANCE),
+            expires_at: U64(SESSION_DURATION),
+        };
+        testing_env_with_promise_results(
+            context.predecessor_account_id(contract_id()).build(),
+            PromiseResult::Successful(vec![]),
+        );
+        assert!(contract.on_session_key_added(session_public_key(), session));
+        let stored = contract.get_session_key(session_public_key()).unwrap();
+        assert_eq!(stored.account_id, br_acc.to_string());
+        assert_eq!(stored.expires_at.0, SESSION_DURATION);
+        assert_eq!(stored.allowance.0, ALLOWANCE);
+        assert_eq!(contract.session_funds, ALLOWANCE);
+    }
+
+    #[test]
+    fn test_session_key_not_added() {
+        let (mut contract, _, mut context) = setup_contract();
+
+        let added = register(&mut contract, &mut context, PromiseResult::Failed);
+        assert!(!added);
+        assert!(contract.get_session_key(session_public_key()).is_none());
+        assert_eq!(contract.session_funds, 0);
+    }
+
+    #[test]
+    fn test_battle_with_session_key() {
+        let (mut contract, br_acc, mut context) = setup_contract();
+        register_added(&mut contract, &mut context);
+
+        call_with_session_key(&mut context, 1_000);
+        let battle = contract.game_simple_battle("0".to_string());
+        assert_eq!(contract.get_last_battle(br_acc).battle, battle.battle);
+    }
+
+    #[test]
+    fn test_guild_challenge_with_session_key() {
+        let (mut contract, _, mut context) = setup_contract();
+        register_added(&mut contract, &mut context);
+        contract.create_guild("Karts".to_string());
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.create_guild("Rivals".to_string());
+
+        call_with_session_key(&mut context, 1_000);
+        let challenge = contract.challenge_guild(0, 1, 1, U128(0));
+        assert_eq!(challenge.challenger_id, 0);
+        contract.decline_guild_challenge(challenge.id);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:110:The session key has expired")]
+    fn test_expired_session_key() {
+        let (mut contract, _, mut context) = setup_contract();
+        register_added(&mut contract, &mut context);
+
+        call_with_session_key(&mut context, SESSION_DURATION);
+        contract.game_simple_battle("0".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:406:Attached deposit is below the session key allowance")]
+    fn test_allowance_must_be_attached() {
+        let (mut contract, _, mut context) = setup_contract();
+
+        testing_env!(context.attached_deposit(ALLOWANCE - 1).build());
+        contract.register_session_key(session_public_key(), U128(ALLOWANCE));
+    }
+
+    #[test]
+    fn test_session_funds_are_not_owner_treasury() {
+        let (mut contract, _, mut context) = setup_contract();
+        register_added(&mut contract, &mut context);
+
+        testing_env!(context.account_balance(3 * ALLOWANCE).build());
+        let locked = env::storage_usage() as u128 * env::storage_byte_cost();
+        assert_eq!(
+            contract.get_treasury_balance().0,
+            3 * ALLOWANCE - locked - ALLOWANCE
+        );
+    }
+
+    #[test]
+    fn test_revoke_session_key() {
+        let (mut contract, _, mut context) = setup_contract();
+        register_added(&mut contract, &mut context);
+
+        contract.revoke_session_key(session_public_key());
+        assert!(contract.get_session_key(session_public_key()).is_none());
+        assert_eq!(contract.session_funds, 0);
+
+        // Once expired, anyone can clean up a session
+        testing_env!(context.attached_deposit(ALLOWANCE).build());
+        register_added(&mut contract, &mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(SESSION_DURATION)
+            .build());
+        contract.revoke_session_key(session_public_key());
+        assert!(contract.get_session_key(session_public_key()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:111:Caller must be the account the session key belongs to")]
+    fn test_revoke_session_key_of_other_account() {
+        let (mut contract, _, mut context) = setup_contract();
+        register_added(&mut contract, &mut context);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.revoke_session_key(session_public_key());
+    }
+}
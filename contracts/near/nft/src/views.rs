@@ -0,0 +1,304 @@
+/*
+Batch views for the web client.
+NOTES:
+  - Each view returns everything a screen needs in one call, instead of a `near_kart_get_config`
+    and a `nft_get_token_metadata` call per kart.
+  - Battles are numbered in the order they were fought. Only the last `MAX_STORED_BATTLES` are
+    kept, in a ring buffer in `battles`, so battling can't grow the contract storage without
+    bound. Older ids are not found. The karts returned with a battle are the karts as they are
+    now, like `get_last_battle_replay`.
+  - `rating` is the kart's rating in the current season, or `INITIAL_RATING` when no season was
+    started yet.
+*/
+use crate::*;
+
+pub const MAX_STORED_BATTLES: u64 = 10_000;
+
+/// A kart with its decoded config, as the garage shows it.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct KartView {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+    pub near_kart: NearKart,
+    pub metadata: TokenMetadata,
+    pub rating: u32,
+    pub energy: u32,
+}
+
+/// A battle with both karts, in the shape `Battle.load()` takes.
+///
+/// `karts` and `metadata` are in home, away order.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BattleWithKarts {
+    pub battle_id: u64,
+    #[serde(flatten)]
+    pub result: SimpleBattle,
+    pub karts: Vec<NearKart>,
+    pub metadata: Vec<TokenMetadata>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Karts of `account_id`, paged in the order of `nft_tokens_for_owner`.
+    pub fn karts_for_owner(
+        &self,
+        account_id: ValidAccountId,
+        from: u64,
+        limit: u64,
+    ) -> Vec<KartView> {
+        let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().unwrap();
+        let token_set = match tokens_per_owner.get(account_id.as_ref()) {
+            Some(token_set) => token_set,
+            None => return Vec::new(),
+        };
+
+        let karts = token_set
+            .iter()
+            .skip(from as usize)
+            .take(limit as usize)
+            .filter_map(|token_id| self.kart_view(token_id))
+            .collect();
+        return karts;
+    }
+
+    /// Karts by token id, unknown ids are left out.
+    pub fn karts_by_ids(&self, token_ids: Vec<TokenId>) -> Vec<KartView> {
+        let karts = token_ids
+            .into_iter()
+            .filter_map(|token_id| self.kart_view(token_id))
+            .collect();
+        return karts;
+    }
+
+    pub fn battle_with_karts(&self, battle_id: u64) -> BattleWithKarts {
+        let oldest = self.battle_count.saturating_sub(MAX_STORED_BATTLES);
+        if battle_id < oldest || battle_id >= self.battle_count {
+            panic_error(KartError::BattleNotFound);
+        }
+        let result = self.battles.get(battle_id % MAX_STORED_BATTLES).unwrap();
+        return self.with_karts(battle_id, result);
+    }
+
+    /// The account's last battle with both karts.
+    pub fn last_battle_with_karts(&self, account_id: ValidAccountId) -> BattleWithKarts {
+        let battle_id = match self.last_battle_ids.get(account_id.as_ref()) {
+            Some(battle_id) => battle_id,
+            None => panic_error(KartError::NoLastBattle),
+        };
+        return self.battle_with_karts(battle_id);
+    }
+}
+
+impl Contract {
+    pub(crate) fn kart_view(&self, token_id: TokenId) -> Option<KartView> {
+        let owner_id = self.token_owner(token_id.clone())?;
+        let metadata = self
+            .tokens
+            .token_metadata_by_id
+            .as_ref()
+            .unwrap()
+            .get(&token_id)?;
+        let near_kart =
+            NearKart::from_data(metadata.extra.as_ref()?).unwrap_or_else(|e| panic_error(e));
+
+        let rating = match self.get_current_season() {
+            Some(season) => self.get_season_stats(season.id, token_id.clone()).rating,
+            None => INITIAL_RATING,
+        };
+
+        let energy = self.get_kart_energy(token_id.clone());
+
+        return Some(KartView {
+            token_id: token_id,
+            owner_id: owner_id,
+            near_kart: near_kart,
+            metadata: metadata,
+            rating: rating,
+            energy: energy,
+        });
+    }
+
+    fn with_karts(&self, battle_id: u64, result: SimpleBattle) -> BattleWithKarts {
+        let mut karts = Vec::new();
+        let mut metadata = Vec::new();
+        for token_id in [&result.home_token_id, &result.away_token_id].iter() {
+            karts.push(self.battle_kart((*token_id).clone()));
+            metadata.push(self.battle_kart_metadata((*token_id).clone()));
+        }
+
+        return BattleWithKarts {
+            battle_id: battle_id,
+            result: result,
+            karts: karts,
+            metadata: metadata,
+        };
+    }
+
+    /// Store a battle under the next id, as the last battle of the home kart's owner.
+    pub(crate) fn record_battle(&mut self, result: &SimpleBattle) -> u64 {
+        let battle_id = self.store_battle(result);
+
+        let account_id = self.token_owner(result.home_token_id.clone()).unwrap();
+        self.last_battle.insert(&account_id, result);
+        self.last_battle_ids.insert(&account_id, &battle_id);
+        return battle_id;
+    }
+
+    /// Keep a battle under the next id, overwriting the oldest once `MAX_STORED_BATTLES` are kept.
+    pub(crate) fn store_battle(&mut self, result: &SimpleBattle) -> u64 {
+        let battle_id = self.battle_count;
+        if self.battles.len() < MAX_STORED_BATTLES {
+            self.battles.push(result);
+        } else {
+            self.battles.replace(battle_id % MAX_STORED_BATTLES, result);
+        }
+
+        self.battle_count = battle_id + 1;
+        return battle_id;
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::json_types::U64;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_contract() -> (Contract, ValidAccountId) {
+        let (contract, _) = setup_with_karts(&[
+            ("megakart", owner_id()),
+            ("fluffykart", owner_id()),
+            ("turbokart", owner_id()),
+        ]);
+        return (contract, owner_id());
+    }
+
+    #[test]
+    fn test_karts_for_owner() {
+        let (contract, br_acc) = setup_contract();
+
+        let karts = contract.karts_for_owner(br_acc.clone(), 0, 10);
+        assert_eq!(karts.len(), 3);
+        assert_eq!(karts[0].token_id, "megakart");
+        assert_eq!(karts[0].owner_id, br_acc.to_string());
+        assert_eq!(karts[0].near_kart.level, NearKart::new().level);
+        assert_eq!(karts[0].metadata.title, Some("Kart megakart".to_string()));
+        assert_eq!(karts[0].rating, INITIAL_RATING);
+        assert_eq!(karts[0].energy, MAX_ENERGY);
+
+        let page = contract.karts_for_owner(br_acc, 1, 1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].token_id, "fluffykart");
+
+        let nobody = ValidAccountId::try_from("nobody.testnet".to_string()).unwrap();
+        assert_eq!(contract.karts_for_owner(nobody, 0, 10).len(), 0);
+    }
+
+    #[test]
+    fn test_karts_by_ids() {
+        let (contract, _) = setup_contract();
+
+        let karts = contract.karts_by_ids(vec![
+            "turbokart".to_string(),
+            "nokart".to_string(),
+            "megakart".to_string(),
+        ]);
+        assert_eq!(karts.len(), 2);
+        assert_eq!(karts[0].token_id, "turbokart");
+        assert_eq!(karts[1].token_id, "megakart");
+    }
+
+    #[test]
+    fn test_kart_rating_in_season() {
+        let (mut contract, _) = setup_contract();
+        contract.start_season(U64(0), U64(u64::MAX));
+        contract.game_simple_battle("megakart".to_string());
+
+        let karts = contract.karts_by_ids(vec!["megakart".to_string()]);
+        let stats = contract.get_season_stats(1, "megakart".to_string());
+        assert_eq!(stats.battles, 1);
+        assert_eq!(karts[0].rating, stats.rating);
+        assert_eq!(karts[0].energy, MAX_ENERGY - BATTLE_ENERGY_COST);
+    }
+
+    #[test]
+    fn test_battle_with_karts() {
+        let (mut contract, br_acc) = setup_contract();
+
+        let first = contract.game_simple_battle("megakart".to_string());
+        let second = contract.game_simple_battle("fluffykart".to_string());
+
+        let battle = contract.battle_with_karts(0);
+        assert_eq!(battle.battle_id, 0);
+        assert_eq!(battle.result.home_token_id, first.home_token_id);
+        assert_eq!(battle.result.battle, first.battle);
+        assert_eq!(battle.karts.len(), 2);
+        assert_eq!(battle.metadata.len(), 2);
+
+        let last = contract.last_battle_with_karts(br_acc);
+        assert_eq!(last.battle_id, 1);
+        assert_eq!(last.result.away_token_id, second.away_token_id);
+        assert_eq!(
+            last.karts[1].level,
+            contract
+                .near_kart_get_config(second.away_token_id.clone())
+                .level
+        );
+
+        // Flat like `get_last_battle`, with the karts next to the result fields
+        let json = serde_json::to_value(&last).unwrap();
+        assert_eq!(json["home_token_id"], "fluffykart");
+        assert_eq!(json["battle"], second.battle);
+        assert!(json["karts"].is_array());
+        assert!(json["metadata"][0]["title"].is_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:501:No battle has this id")]
+    fn test_battle_with_karts_not_found() {
+        let (contract, _) = setup_contract();
+        contract.battle_with_karts(0);
+    }
+
+    /// Fill the battle ring buffer and go `extra` battles past it.
+    fn overflow_battles(contract: &mut Contract, extra: u64) {
+        for battle in 0..(MAX_STORED_BATTLES + extra) {
+            contract.store_battle(&SimpleBattle {
+                home_token_id: "megakart".to_string(),
+                away_token_id: "fluffykart".to_string(),
+                winner: 0,
+                battle: battle as u32,
+                prize: "0".to_string(),
+                extra: String::new(),
+            });
+        }
+    }
+
+    #[test]
+    fn test_battles_are_bounded() {
+        let (mut contract, _) = setup_contract();
+        overflow_battles(&mut contract, 2);
+
+        assert_eq!(contract.battles.len(), MAX_STORED_BATTLES);
+        let battle = contract.battle_with_karts(MAX_STORED_BATTLES + 1);
+        assert_eq!(battle.battle_id, MAX_STORED_BATTLES + 1);
+        assert_eq!(battle.result.battle, MAX_STORED_BATTLES as u32 + 1);
+        assert_eq!(contract.battle_with_karts(2).result.battle, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:501:No battle has this id")]
+    fn test_overwritten_battle_not_found() {
+        let (mut contract, _) = setup_contract();
+        overflow_battles(&mut contract, 2);
+        contract.battle_with_karts(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "KART_ERR:500:The account has not battled yet")]
+    fn test_last_battle_with_karts_none() {
+        let (contract, br_acc) = setup_contract();
+        contract.last_battle_with_karts(br_acc);
+    }
+}
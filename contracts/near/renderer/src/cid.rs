@@ -0,0 +1,64 @@
+use sha2::{Digest, Sha256};
+
+const CID_VERSION_1: u8 = 0x01;
+const CODEC_RAW: u8 = 0x55;
+const MULTIHASH_SHA2_256: u8 = 0x12;
+const SHA2_256_LENGTH: u8 = 0x20;
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+fn base32_lower(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 8).div_ceil(5));
+    let (mut buffer, mut bits) = (0u32, 0u32);
+
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// CIDv1 of a file stored as a single raw block, base32 with the multibase `b` prefix.
+///
+/// This is the CID IPFS gives the file with `ipfs add --cid-version 1 --raw-leaves` as long as
+/// it fits in one chunk, e.g. `--chunker size-1048576` for the 400x400 kart images.
+pub fn raw_cid(data: &[u8]) -> String {
+    let mut cid = vec![
+        CID_VERSION_1,
+        CODEC_RAW,
+        MULTIHASH_SHA2_256,
+        SHA2_256_LENGTH,
+    ];
+    cid.extend_from_slice(&sha256(data));
+    format!("b{}", base32_lower(&cid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base32() {
+        assert_eq!(base32_lower(b"foobar"), "mzxw6ytboi");
+        assert_eq!(base32_lower(b"f"), "my");
+    }
+
+    #[test]
+    fn test_raw_cid() {
+        // `echo -n hello | ipfs add --cid-version 1 --raw-leaves`
+        assert_eq!(
+            raw_cid(b"hello"),
+            "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq"
+        );
+    }
+}
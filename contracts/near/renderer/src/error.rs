@@ -0,0 +1,41 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum RenderError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Model(String),
+    Texture(png::DecodingError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Io(e) => write!(f, "io error: {}", e),
+            RenderError::Json(e) => write!(f, "invalid json: {}", e),
+            RenderError::Model(msg) => write!(f, "invalid model: {}", msg),
+            RenderError::Texture(e) => write!(f, "invalid texture: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<io::Error> for RenderError {
+    fn from(e: io::Error) -> Self {
+        RenderError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for RenderError {
+    fn from(e: serde_json::Error) -> Self {
+        RenderError::Json(e)
+    }
+}
+
+impl From<png::DecodingError> for RenderError {
+    fn from(e: png::DecodingError) -> Self {
+        RenderError::Texture(e)
+    }
+}
@@ -0,0 +1,329 @@
+use crate::math::{Mat4, Vec3};
+use crate::RenderError;
+use serde::Deserialize;
+use std::convert::TryInto;
+
+const GLB_MAGIC: u32 = 0x4654_6c67; // "glTF"
+const CHUNK_JSON: u32 = 0x4e4f_534a;
+const CHUNK_BIN: u32 = 0x004e_4942;
+
+const COMPONENT_U8: u32 = 5121;
+const COMPONENT_U16: u32 = 5123;
+const COMPONENT_U32: u32 = 5125;
+const COMPONENT_F32: u32 = 5126;
+
+// Only the parts of glTF 2.0 the kart model uses are read: TRS nodes, indexed triangle
+// primitives, base color factors / textures and embedded PNG images.
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Document {
+    #[serde(default)]
+    pub scene: usize,
+    pub scenes: Vec<Scene>,
+    pub nodes: Vec<Node>,
+    #[serde(default)]
+    pub meshes: Vec<Mesh>,
+    #[serde(default)]
+    pub accessors: Vec<Accessor>,
+    #[serde(default)]
+    pub buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    pub materials: Vec<Material>,
+    #[serde(default)]
+    pub textures: Vec<Texture>,
+    #[serde(default)]
+    pub images: Vec<Image>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Scene {
+    pub nodes: Vec<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Node {
+    #[serde(default)]
+    pub name: String,
+    pub mesh: Option<usize>,
+    #[serde(default)]
+    pub children: Vec<usize>,
+    pub matrix: Option<[f32; 16]>,
+    pub translation: Option<[f32; 3]>,
+    pub rotation: Option<[f32; 4]>,
+    pub scale: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Mesh {
+    pub primitives: Vec<Primitive>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Primitive {
+    pub attributes: Attributes,
+    pub indices: Option<usize>,
+    pub material: Option<usize>,
+    #[serde(default = "triangles")]
+    pub mode: u32,
+}
+
+fn triangles() -> u32 {
+    4
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Attributes {
+    #[serde(rename = "POSITION")]
+    pub position: usize,
+    #[serde(rename = "NORMAL")]
+    pub normal: Option<usize>,
+    #[serde(rename = "TEXCOORD_0")]
+    pub texcoord: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Accessor {
+    pub buffer_view: Option<usize>,
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub component_type: u32,
+    pub count: usize,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferView {
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub byte_length: usize,
+    pub byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Material {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub pbr_metallic_roughness: Pbr,
+    pub emissive_factor: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Pbr {
+    #[serde(default = "white")]
+    pub base_color_factor: [f32; 4],
+    pub base_color_texture: Option<TextureRef>,
+    #[serde(default = "one")]
+    pub metallic_factor: f32,
+    #[serde(default = "one")]
+    pub roughness_factor: f32,
+}
+
+impl Default for Pbr {
+    fn default() -> Self {
+        Pbr {
+            base_color_factor: white(),
+            base_color_texture: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+        }
+    }
+}
+
+fn white() -> [f32; 4] {
+    [1.0; 4]
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TextureRef {
+    pub index: usize,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Texture {
+    pub source: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Image {
+    pub buffer_view: Option<usize>,
+}
+
+/// A parsed binary glTF file.
+pub struct Glb {
+    pub document: Document,
+    pub bin: Vec<u8>,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, RenderError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| RenderError::Model("unexpected end of file".to_string()))
+}
+
+impl Node {
+    pub fn local_matrix(&self) -> Mat4 {
+        if let Some(matrix) = self.matrix {
+            return Mat4(matrix);
+        }
+        Mat4::from_trs(
+            self.translation.unwrap_or([0.0; 3]),
+            self.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]),
+            self.scale.unwrap_or([1.0; 3]),
+        )
+    }
+}
+
+impl Glb {
+    pub fn parse(data: &[u8]) -> Result<Self, RenderError> {
+        if read_u32(data, 0)? != GLB_MAGIC {
+            return Err(RenderError::Model("not a binary glTF file".to_string()));
+        }
+
+        let mut document = None;
+        let mut bin = Vec::new();
+        let mut offset = 12;
+        while offset < data.len() {
+            let length = read_u32(data, offset)? as usize;
+            let kind = read_u32(data, offset + 4)?;
+            let chunk = data
+                .get(offset + 8..offset + 8 + length)
+                .ok_or_else(|| RenderError::Model("truncated chunk".to_string()))?;
+
+            match kind {
+                CHUNK_JSON => document = Some(serde_json::from_slice(chunk)?),
+                CHUNK_BIN => bin = chunk.to_vec(),
+                _ => {}
+            }
+            offset += 8 + length;
+        }
+
+        let document =
+            document.ok_or_else(|| RenderError::Model("missing JSON chunk".to_string()))?;
+        Ok(Glb { document, bin })
+    }
+
+    fn view_bytes(&self, view: usize) -> Result<&[u8], RenderError> {
+        let view = self
+            .document
+            .buffer_views
+            .get(view)
+            .ok_or_else(|| RenderError::Model(format!("missing buffer view {}", view)))?;
+        self.bin
+            .get(view.byte_offset..view.byte_offset + view.byte_length)
+            .ok_or_else(|| RenderError::Model("buffer view out of range".to_string()))
+    }
+
+    /// Raw component values of an accessor, converted to f32 / u32 by the caller.
+    fn elements(
+        &self,
+        accessor: usize,
+        components: usize,
+    ) -> Result<(&Accessor, Vec<&[u8]>), RenderError> {
+        let accessor = self
+            .document
+            .accessors
+            .get(accessor)
+            .ok_or_else(|| RenderError::Model(format!("missing accessor {}", accessor)))?;
+        let view_index = accessor
+            .buffer_view
+            .ok_or_else(|| RenderError::Model("sparse accessors are not supported".to_string()))?;
+        let bytes = self.view_bytes(view_index)?;
+
+        let component_size = match accessor.component_type {
+            COMPONENT_U8 => 1,
+            COMPONENT_U16 => 2,
+            COMPONENT_U32 | COMPONENT_F32 => 4,
+            other => {
+                return Err(RenderError::Model(format!(
+                    "unsupported component type {}",
+                    other
+                )))
+            }
+        };
+        let element_size = component_size * components;
+        let stride = self.document.buffer_views[view_index]
+            .byte_stride
+            .unwrap_or(element_size);
+
+        let mut elements = Vec::with_capacity(accessor.count);
+        for i in 0..accessor.count {
+            let start = accessor.byte_offset + i * stride;
+            elements.push(
+                bytes
+                    .get(start..start + element_size)
+                    .ok_or_else(|| RenderError::Model("accessor out of range".to_string()))?,
+            );
+        }
+        Ok((accessor, elements))
+    }
+
+    pub fn read_vec3(&self, accessor: usize) -> Result<Vec<Vec3>, RenderError> {
+        let (info, elements) = self.elements(accessor, 3)?;
+        if info.component_type != COMPONENT_F32 || info.kind != "VEC3" {
+            return Err(RenderError::Model(
+                "expected a float VEC3 accessor".to_string(),
+            ));
+        }
+
+        Ok(elements
+            .iter()
+            .map(|e| {
+                let f = |i: usize| f32::from_le_bytes(e[i * 4..i * 4 + 4].try_into().unwrap());
+                Vec3::new(f(0), f(1), f(2))
+            })
+            .collect())
+    }
+
+    pub fn read_vec2(&self, accessor: usize) -> Result<Vec<[f32; 2]>, RenderError> {
+        let (info, elements) = self.elements(accessor, 2)?;
+        if info.component_type != COMPONENT_F32 || info.kind != "VEC2" {
+            return Err(RenderError::Model(
+                "expected a float VEC2 accessor".to_string(),
+            ));
+        }
+
+        Ok(elements
+            .iter()
+            .map(|e| {
+                let f = |i: usize| f32::from_le_bytes(e[i * 4..i * 4 + 4].try_into().unwrap());
+                [f(0), f(1)]
+            })
+            .collect())
+    }
+
+    pub fn read_indices(&self, accessor: usize) -> Result<Vec<u32>, RenderError> {
+        let (info, elements) = self.elements(accessor, 1)?;
+
+        Ok(elements
+            .iter()
+            .map(|e| match info.component_type {
+                COMPONENT_U8 => e[0] as u32,
+                COMPONENT_U16 => u16::from_le_bytes([e[0], e[1]]) as u32,
+                _ => u32::from_le_bytes(e[0..4].try_into().unwrap()),
+            })
+            .collect())
+    }
+
+    /// Encoded bytes of an embedded image.
+    pub fn image_bytes(&self, image: usize) -> Result<&[u8], RenderError> {
+        let view = self
+            .document
+            .images
+            .get(image)
+            .and_then(|image| image.buffer_view)
+            .ok_or_else(|| RenderError::Model(format!("image {} is not embedded", image)))?;
+        self.view_bytes(view)
+    }
+}
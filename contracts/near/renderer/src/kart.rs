@@ -0,0 +1,204 @@
+use crate::math::Vec3;
+use crate::texture::srgb_to_linear;
+use nearkarts_types::{NearKart, SHIELD_START_INDEX};
+
+// Part ids by index, as in `web/src/data/world/config.js`
+pub const WEAPONS_RANGE: [&str; 6] = [
+    "WeaponRangeEmpty",
+    "WeaponLaser",
+    "WeaponRocket",
+    "WeaponFist",
+    "WeaponFlamethrower",
+    "WeaponAceed",
+];
+pub const WEAPONS_MELEE: [&str; 5] = [
+    "WeaponMeleeEmpty",
+    "WeaponFlipper",
+    "WeaponSword",
+    "WeaponAxe",
+    "WeaponHammer",
+];
+pub const SHIELDS_SIDE: [&str; 2] = ["ShieldKitten", "ShieldKevlar"];
+pub const TRANSPORTS: [&str; 3] = [
+    "TransportWheels",
+    "TransportTracks",
+    "TransportDoubleTracks",
+];
+const START_HIDDEN: [&str; 4] = ["Transport", "BotTurret", "Weapon", "Shield"];
+
+/// Surface of the kart body for a skin, roughness and metalness as styleScene sets them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Skin {
+    pub roughness: f32,
+    pub metalness: f32,
+}
+
+pub const SKINS: [Skin; 4] = [
+    // Plastic
+    Skin {
+        roughness: 0.0,
+        metalness: 0.0,
+    },
+    // Carbon fibre
+    Skin {
+        roughness: 0.8,
+        metalness: 0.0,
+    },
+    // Aluminium
+    Skin {
+        roughness: 0.4,
+        metalness: 0.5,
+    },
+    // Steel
+    Skin {
+        roughness: 0.2,
+        metalness: 1.0,
+    },
+];
+
+// Stand-in colors for the decal badges, the badge artwork is served from the image host and
+// isn't part of the model. Index is the decal id.
+const DECAL_COLORS: [u32; 8] = [
+    0x000000, 0xe0245e, 0xffd700, 0x9b30ff, 0xffe135, 0x00c8c8, 0xf5f5f5, 0x00ec97,
+];
+
+/// Scene settings derived from a kart, mirrors `nftDataToKartConfig` and `styleScene` in the
+/// web client.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KartStyle {
+    pub left: &'static str,
+    pub right: &'static str,
+    pub front: &'static str,
+    pub transport: &'static str,
+    pub skin: Skin,
+    pub color: Vec3,
+    pub decal: Option<Vec3>,
+}
+
+fn side_part(index: u8) -> &'static str {
+    if index >= SHIELD_START_INDEX {
+        return SHIELDS_SIDE
+            .get((index - SHIELD_START_INDEX) as usize)
+            .copied()
+            .unwrap_or("empty");
+    }
+    WEAPONS_RANGE
+        .get(index as usize)
+        .copied()
+        .unwrap_or("empty")
+}
+
+pub fn color_to_linear(color: u32) -> Vec3 {
+    Vec3::new(
+        srgb_to_linear((color >> 16) as u8),
+        srgb_to_linear((color >> 8) as u8),
+        srgb_to_linear(color as u8),
+    )
+}
+
+impl KartStyle {
+    pub fn new(nk: &NearKart) -> Self {
+        let decal = match nk.decal1.parse::<usize>() {
+            Ok(id) if id > 0 && id < DECAL_COLORS.len() => Some(color_to_linear(DECAL_COLORS[id])),
+            _ => None,
+        };
+
+        KartStyle {
+            left: side_part(nk.left),
+            right: side_part(nk.right),
+            front: WEAPONS_MELEE
+                .get(nk.front as usize)
+                .copied()
+                .unwrap_or("empty"),
+            transport: TRANSPORTS
+                .get(nk.transport as usize)
+                .copied()
+                .unwrap_or(TRANSPORTS[0]),
+            skin: SKINS.get(nk.skin as usize).copied().unwrap_or(SKINS[0]),
+            color: color_to_linear(nk.color1),
+            decal,
+        }
+    }
+
+    /// Visibility flag of a single node, before its parents are taken into account.
+    pub fn is_visible(&self, name: &str) -> bool {
+        let mut visible = !START_HIDDEN.iter().any(|start| name.starts_with(start));
+        let is_weapon = |part: &str| part.starts_with("Weapon") && !part.ends_with("Empty");
+
+        if (name == "BotTurretL" && is_weapon(self.left))
+            || (name == "BotTurretR" && is_weapon(self.right))
+            || (name == "BotTurretFront" && is_weapon(self.front))
+        {
+            visible = true;
+        }
+
+        if name.starts_with(&format!("{}L", self.left))
+            || name.starts_with(&format!("{}R", self.right))
+            || name.starts_with(self.front)
+            || name.starts_with(self.transport)
+        {
+            visible = true;
+        }
+
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kart() -> NearKart {
+        let mut nk = NearKart::new();
+        nk.left = 1;
+        nk.right = SHIELD_START_INDEX + 1;
+        nk.front = 3;
+        nk.transport = 1;
+        nk.skin = 3;
+        nk.decal1 = "7".to_string();
+        nk
+    }
+
+    #[test]
+    fn test_style() {
+        let style = KartStyle::new(&kart());
+
+        assert_eq!(style.left, "WeaponLaser");
+        assert_eq!(style.right, "ShieldKevlar");
+        assert_eq!(style.front, "WeaponAxe");
+        assert_eq!(style.transport, "TransportTracks");
+        assert_eq!(style.skin, SKINS[3]);
+        assert!(style.decal.is_some());
+    }
+
+    #[test]
+    fn test_visibility() {
+        let style = KartStyle::new(&kart());
+
+        assert!(style.is_visible("BotBody1"));
+        assert!(style.is_visible("BotTurretL"));
+        assert!(!style.is_visible("BotTurretR"));
+        assert!(style.is_visible("WeaponLaserLTube"));
+        assert!(!style.is_visible("WeaponLaserRTube"));
+        assert!(style.is_visible("ShieldKevlarREmpty"));
+        assert!(!style.is_visible("ShieldKevlarLEmpty"));
+        assert!(style.is_visible("WeaponAxeBlade"));
+        assert!(style.is_visible("TransportTracksWheels"));
+        assert!(!style.is_visible("TransportWheels"));
+    }
+
+    #[test]
+    fn test_empty_slots() {
+        let style = KartStyle::new(&NearKart::new());
+
+        assert!(!style.is_visible("BotTurretL"));
+        assert!(!style.is_visible("BotTurretFront"));
+        assert!(style.is_visible("TransportWheels"));
+        assert!(style.decal.is_none());
+    }
+
+    #[test]
+    fn test_color_to_linear() {
+        assert_eq!(color_to_linear(0xff0000), Vec3::new(1.0, 0.0, 0.0));
+    }
+}
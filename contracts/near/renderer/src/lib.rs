@@ -0,0 +1,25 @@
+/*
+Headless kart image renderer.
+NOTES:
+  - Draws the kart glTF model of the web client with the part visibility, body color and skin
+    its `styleScene` applies, from the same camera and point lights as the photo scene.
+  - Software rasterized with supersampling. Floats only go through +, -, *, / and sqrt, which
+    are exactly rounded, so a kart gives the same PNG bytes on every machine.
+  - The PNG is written with stored deflate blocks so the bytes don't depend on a compressor
+    version. `cid::raw_cid` gives the CIDv1 the image is pinned under.
+  - Decal badges are drawn as a colored disc per decal id, the badge artwork is served by the
+    image host and isn't part of the model.
+*/
+pub mod cid;
+pub mod encode;
+pub mod error;
+pub mod glb;
+pub mod kart;
+pub mod math;
+pub mod raster;
+pub mod render;
+pub mod texture;
+
+pub use crate::cid::{raw_cid, sha256};
+pub use crate::error::RenderError;
+pub use crate::render::{Renderer, IMAGE_SIZE};
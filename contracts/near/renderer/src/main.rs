@@ -0,0 +1,52 @@
+/*
+nearkarts-renderer
+
+USAGE:
+  nearkarts-renderer render <kart.json> <out.png>
+  nearkarts-renderer cid <kart.json>
+
+kart.json is a NearKart as JSON, e.g. the `near_kart` of a signer request.
+*/
+use nearkarts_renderer::{raw_cid, RenderError, Renderer};
+use nearkarts_types::NearKart;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage:
+  nearkarts-renderer render <kart.json> <out.png>
+  nearkarts-renderer cid <kart.json>";
+
+fn load_kart(path: &str) -> Result<NearKart, RenderError> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn render(path: &str, out: &str) -> Result<(), RenderError> {
+    let png = Renderer::new()?.render_png(&load_kart(path)?);
+    fs::write(out, &png)?;
+    println!("{}", raw_cid(&png));
+    Ok(())
+}
+
+fn cid(path: &str) -> Result<(), RenderError> {
+    println!("{}", Renderer::new()?.media_cid(&load_kart(path)?));
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+
+    let result = match args.as_slice() {
+        ["render", path, out] => render(path, out),
+        ["cid", path] => cid(path),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
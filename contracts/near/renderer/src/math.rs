@@ -0,0 +1,272 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Only +, -, *, / and sqrt are used on floats. They are exactly rounded by IEEE 754, so the
+// same inputs give the same bits on every platform, unlike sin / cos / pow from the system libm.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vec3 {
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Vec3 { x, y, z }
+    }
+
+    pub fn dot(self, other: Vec3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Vec3 {
+        let length = self.length();
+        if length == 0.0 {
+            return self;
+        }
+        self * (1.0 / length)
+    }
+
+    /// Component-wise product, used for colors.
+    pub fn scale(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+
+    pub fn max(self, value: f32) -> Vec3 {
+        Vec3::new(self.x.max(value), self.y.max(value), self.z.max(value))
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+    fn add(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+    fn sub(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f32> for Vec3 {
+    type Output = Vec3;
+    fn mul(self, s: f32) -> Vec3 {
+        Vec3::new(self.x * s, self.y * s, self.z * s)
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+    fn neg(self) -> Vec3 {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// Column-major 4x4 matrix, the glTF layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4(pub [f32; 16]);
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4([
+        1.0, 0.0, 0.0, 0.0, //
+        0.0, 1.0, 0.0, 0.0, //
+        0.0, 0.0, 1.0, 0.0, //
+        0.0, 0.0, 0.0, 1.0,
+    ]);
+
+    fn at(&self, row: usize, col: usize) -> f32 {
+        self.0[col * 4 + row]
+    }
+
+    /// Translation * rotation (unit quaternion x, y, z, w) * scale.
+    pub fn from_trs(t: [f32; 3], r: [f32; 4], s: [f32; 3]) -> Mat4 {
+        let [x, y, z, w] = r;
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        let (xy, xz, yz) = (x * y, x * z, y * z);
+        let (wx, wy, wz) = (w * x, w * y, w * z);
+
+        Mat4([
+            (1.0 - 2.0 * (yy + zz)) * s[0],
+            (2.0 * (xy + wz)) * s[0],
+            (2.0 * (xz - wy)) * s[0],
+            0.0,
+            (2.0 * (xy - wz)) * s[1],
+            (1.0 - 2.0 * (xx + zz)) * s[1],
+            (2.0 * (yz + wx)) * s[1],
+            0.0,
+            (2.0 * (xz + wy)) * s[2],
+            (2.0 * (yz - wx)) * s[2],
+            (1.0 - 2.0 * (xx + yy)) * s[2],
+            0.0,
+            t[0],
+            t[1],
+            t[2],
+            1.0,
+        ])
+    }
+
+    /// Right handed view matrix looking from `eye` at `target`.
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+        let f = (target - eye).normalize();
+        let s = f.cross(up).normalize();
+        let u = s.cross(f);
+
+        Mat4([
+            s.x,
+            u.x,
+            -f.x,
+            0.0,
+            s.y,
+            u.y,
+            -f.y,
+            0.0,
+            s.z,
+            u.z,
+            -f.z,
+            0.0,
+            -s.dot(eye),
+            -u.dot(eye),
+            f.dot(eye),
+            1.0,
+        ])
+    }
+
+    /// OpenGL style projection. `focal` is 1 / tan(fov_y / 2), passed in so no tan is needed.
+    pub fn perspective(focal: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+        Mat4([
+            focal / aspect,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            focal,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            (far + near) / (near - far),
+            -1.0,
+            0.0,
+            0.0,
+            2.0 * far * near / (near - far),
+            0.0,
+        ])
+    }
+
+    pub fn mul(&self, other: &Mat4) -> Mat4 {
+        let mut out = [0.0; 16];
+        for col in 0..4 {
+            for row in 0..4 {
+                let mut sum = 0.0;
+                for k in 0..4 {
+                    sum += self.at(row, k) * other.at(k, col);
+                }
+                out[col * 4 + row] = sum;
+            }
+        }
+        Mat4(out)
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        Vec3::new(
+            self.at(0, 0) * p.x + self.at(0, 1) * p.y + self.at(0, 2) * p.z + self.at(0, 3),
+            self.at(1, 0) * p.x + self.at(1, 1) * p.y + self.at(1, 2) * p.z + self.at(1, 3),
+            self.at(2, 0) * p.x + self.at(2, 1) * p.y + self.at(2, 2) * p.z + self.at(2, 3),
+        )
+    }
+
+    /// Homogeneous transform of a point, returns x, y, z, w.
+    pub fn project(&self, p: Vec3) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        for (row, value) in out.iter_mut().enumerate() {
+            *value = self.at(row, 0) * p.x
+                + self.at(row, 1) * p.y
+                + self.at(row, 2) * p.z
+                + self.at(row, 3);
+        }
+        out
+    }
+
+    /// Transform a normal by the cofactor matrix of the upper 3x3, the inverse transpose up to
+    /// a scale factor. Normals are normalized afterwards so the scale doesn't matter.
+    pub fn transform_normal(&self, n: Vec3) -> Vec3 {
+        let c0 = Vec3::new(self.at(0, 0), self.at(1, 0), self.at(2, 0));
+        let c1 = Vec3::new(self.at(0, 1), self.at(1, 1), self.at(2, 1));
+        let c2 = Vec3::new(self.at(0, 2), self.at(1, 2), self.at(2, 2));
+
+        let r0 = c1.cross(c2);
+        let r1 = c2.cross(c0);
+        let r2 = c0.cross(c1);
+        (r0 * n.x + r1 * n.y + r2 * n.z).normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trs_identity() {
+        let m = Mat4::from_trs([0.0; 3], [0.0, 0.0, 0.0, 1.0], [1.0; 3]);
+        assert_eq!(m, Mat4::IDENTITY);
+    }
+
+    #[test]
+    fn test_trs_point() {
+        // 90 degrees around y, x goes to -z
+        let half = 0.5f32.sqrt();
+        let m = Mat4::from_trs([1.0, 2.0, 3.0], [0.0, half, 0.0, half], [2.0; 3]);
+        let p = m.transform_point(Vec3::new(1.0, 0.0, 0.0));
+
+        assert!((p.x - 1.0).abs() < 1e-6);
+        assert!((p.y - 2.0).abs() < 1e-6);
+        assert!((p.z - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mul_applies_right_first() {
+        let t = Mat4::from_trs([1.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0], [1.0; 3]);
+        let s = Mat4::from_trs([0.0; 3], [0.0, 0.0, 0.0, 1.0], [3.0; 3]);
+        let p = t.mul(&s).transform_point(Vec3::new(1.0, 1.0, 1.0));
+
+        assert_eq!(p, Vec3::new(4.0, 3.0, 3.0));
+    }
+
+    #[test]
+    fn test_look_at_puts_target_on_negative_z() {
+        let view = Mat4::look_at(
+            Vec3::new(0.0, 0.0, 5.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(
+            view.transform_point(Vec3::new(0.0, 0.0, 0.0)),
+            Vec3::new(0.0, 0.0, -5.0)
+        );
+    }
+
+    #[test]
+    fn test_normal_under_non_uniform_scale() {
+        let m = Mat4::from_trs([0.0; 3], [0.0, 0.0, 0.0, 1.0], [4.0, 1.0, 1.0]);
+        let n = m.transform_normal(Vec3::new(1.0, 1.0, 0.0).normalize());
+
+        // Stretching along x tilts the normal towards y
+        assert!(n.y > n.x);
+        assert!((n.length() - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::math::Vec3;
+
+/// A vertex after the projection, with the attributes interpolated across the triangle.
+#[derive(Clone, Copy, Debug)]
+pub struct ClipVertex {
+    pub clip: [f32; 4],
+    pub world: Vec3,
+    pub normal: Vec3,
+    pub uv: [f32; 2],
+}
+
+/// Attributes at a covered pixel, perspective correct.
+pub struct Fragment {
+    pub world: Vec3,
+    pub normal: Vec3,
+    pub uv: [f32; 2],
+}
+
+/// Linear color and depth buffers.
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<Vec3>,
+    depth: Vec<f32>,
+}
+
+fn lerp_vertex(a: &ClipVertex, b: &ClipVertex, t: f32) -> ClipVertex {
+    let mix = |x: f32, y: f32| x + (y - x) * t;
+    ClipVertex {
+        clip: [
+            mix(a.clip[0], b.clip[0]),
+            mix(a.clip[1], b.clip[1]),
+            mix(a.clip[2], b.clip[2]),
+            mix(a.clip[3], b.clip[3]),
+        ],
+        world: a.world + (b.world - a.world) * t,
+        normal: a.normal + (b.normal - a.normal) * t,
+        uv: [mix(a.uv[0], b.uv[0]), mix(a.uv[1], b.uv[1])],
+    }
+}
+
+/// Clip a triangle against the near plane (z >= -w), returns a convex polygon.
+fn clip_near(triangle: &[ClipVertex; 3]) -> Vec<ClipVertex> {
+    let distance = |v: &ClipVertex| v.clip[2] + v.clip[3];
+    let mut polygon = Vec::with_capacity(4);
+
+    for i in 0..3 {
+        let a = &triangle[i];
+        let b = &triangle[(i + 1) % 3];
+        let (da, db) = (distance(a), distance(b));
+
+        if da >= 0.0 {
+            polygon.push(*a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            polygon.push(lerp_vertex(a, b, da / (da - db)));
+        }
+    }
+    polygon
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize, clear: Vec3) -> Self {
+        Framebuffer {
+            width,
+            height,
+            color: vec![clear; width * height],
+            depth: vec![f32::INFINITY; width * height],
+        }
+    }
+
+    /// Draw a triangle, `shade` gives the color of each covered fragment or `None` to discard it.
+    pub fn draw_triangle<F>(&mut self, triangle: &[ClipVertex; 3], shade: &mut F)
+    where
+        F: FnMut(&Fragment) -> Option<Vec3>,
+    {
+        let polygon = clip_near(triangle);
+        for i in 1..polygon.len().saturating_sub(1) {
+            self.rasterize(&polygon[0], &polygon[i], &polygon[i + 1], shade);
+        }
+    }
+
+    fn rasterize<F>(&mut self, a: &ClipVertex, b: &ClipVertex, c: &ClipVertex, shade: &mut F)
+    where
+        F: FnMut(&Fragment) -> Option<Vec3>,
+    {
+        let (w, h) = (self.width as f32, self.height as f32);
+        let screen = |v: &ClipVertex| {
+            let inv_w = 1.0 / v.clip[3];
+            [
+                (v.clip[0] * inv_w * 0.5 + 0.5) * w,
+                (0.5 - v.clip[1] * inv_w * 0.5) * h,
+                v.clip[2] * inv_w,
+                inv_w,
+            ]
+        };
+        let (sa, sb, sc) = (screen(a), screen(b), screen(c));
+
+        let edge = |p: &[f32; 4], q: &[f32; 4], x: f32, y: f32| {
+            (q[0] - p[0]) * (y - p[1]) - (q[1] - p[1]) * (x - p[0])
+        };
+        let area = edge(&sa, &sb, sc[0], sc[1]);
+        if area == 0.0 {
+            return;
+        }
+
+        let min_x = sa[0].min(sb[0]).min(sc[0]).floor().max(0.0) as usize;
+        let min_y = sa[1].min(sb[1]).min(sc[1]).floor().max(0.0) as usize;
+        let max_x = (sa[0].max(sb[0]).max(sc[0]).ceil().max(0.0) as usize).min(self.width);
+        let max_y = (sa[1].max(sb[1]).max(sc[1]).ceil().max(0.0) as usize).min(self.height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let b0 = edge(&sb, &sc, px, py) / area;
+                let b1 = edge(&sc, &sa, px, py) / area;
+                let b2 = edge(&sa, &sb, px, py) / area;
+                if b0 < 0.0 || b1 < 0.0 || b2 < 0.0 {
+                    continue;
+                }
+
+                let depth = b0 * sa[2] + b1 * sb[2] + b2 * sc[2];
+                let index = y * self.width + x;
+                if !(-1.0..=1.0).contains(&depth) || depth >= self.depth[index] {
+                    continue;
+                }
+
+                // Perspective correct weights
+                let (p0, p1, p2) = (b0 * sa[3], b1 * sb[3], b2 * sc[3]);
+                let inv = 1.0 / (p0 + p1 + p2);
+                let (p0, p1, p2) = (p0 * inv, p1 * inv, p2 * inv);
+
+                let fragment = Fragment {
+                    world: a.world * p0 + b.world * p1 + c.world * p2,
+                    normal: a.normal * p0 + b.normal * p1 + c.normal * p2,
+                    uv: [
+                        a.uv[0] * p0 + b.uv[0] * p1 + c.uv[0] * p2,
+                        a.uv[1] * p0 + b.uv[1] * p1 + c.uv[1] * p2,
+                    ],
+                };
+
+                if let Some(color) = shade(&fragment) {
+                    self.depth[index] = depth;
+                    self.color[index] = color;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32, z: f32) -> ClipVertex {
+        ClipVertex {
+            clip: [x, y, z, 1.0],
+            world: Vec3::default(),
+            normal: Vec3::default(),
+            uv: [0.0, 0.0],
+        }
+    }
+
+    #[test]
+    fn test_fill_and_depth() {
+        let mut fb = Framebuffer::new(4, 4, Vec3::default());
+        let red = Vec3::new(1.0, 0.0, 0.0);
+        let green = Vec3::new(0.0, 1.0, 0.0);
+
+        // Covers the whole viewport, two triangles
+        let quad = [
+            [
+                vertex(-1.0, -1.0, 0.5),
+                vertex(1.0, -1.0, 0.5),
+                vertex(1.0, 1.0, 0.5),
+            ],
+            [
+                vertex(-1.0, -1.0, 0.5),
+                vertex(1.0, 1.0, 0.5),
+                vertex(-1.0, 1.0, 0.5),
+            ],
+        ];
+        for triangle in quad.iter() {
+            fb.draw_triangle(triangle, &mut |_| Some(red));
+        }
+        assert!(fb.color.iter().all(|c| *c == red));
+
+        // Behind the red quad, must not be drawn
+        let behind = [
+            vertex(-1.0, -1.0, 0.9),
+            vertex(1.0, -1.0, 0.9),
+            vertex(1.0, 1.0, 0.9),
+        ];
+        fb.draw_triangle(&behind, &mut |_| Some(green));
+        assert!(fb.color.iter().all(|c| *c == red));
+    }
+
+    #[test]
+    fn test_discard() {
+        let mut fb = Framebuffer::new(4, 4, Vec3::default());
+        let triangle = [
+            vertex(-1.0, -1.0, 0.0),
+            vertex(1.0, -1.0, 0.0),
+            vertex(1.0, 1.0, 0.0),
+        ];
+        fb.draw_triangle(&triangle, &mut |_| None);
+
+        assert!(fb.color.iter().all(|c| *c == Vec3::default()));
+    }
+
+    #[test]
+    fn test_clip_near() {
+        let mut behind = vertex(0.0, 0.0, -2.0);
+        behind.clip[3] = 1.0;
+        let triangle = [vertex(-1.0, -1.0, 0.0), vertex(1.0, -1.0, 0.0), behind];
+
+        let polygon = clip_near(&triangle);
+        assert_eq!(polygon.len(), 4);
+        assert!(polygon.iter().all(|v| v.clip[2] + v.clip[3] >= 0.0));
+    }
+}
@@ -0,0 +1,348 @@
+use crate::cid::raw_cid;
+use crate::encode::encode_png;
+use crate::glb::Glb;
+use crate::kart::KartStyle;
+use crate::math::{Mat4, Vec3};
+use crate::raster::{ClipVertex, Fragment, Framebuffer};
+use crate::texture::Texture;
+use crate::RenderError;
+use nearkarts_types::NearKart;
+
+/// The kart model the web client renders.
+pub const KART_MODEL: &[u8] = include_bytes!("../../../../web/src/data/models/bot-3.glb");
+
+/// Width and height of the kart images, as the web client uploads them.
+pub const IMAGE_SIZE: u32 = 400;
+const SUPERSAMPLE: usize = 2;
+
+// Photo camera and lights of the web client's createScene
+const CAMERA_EYE: Vec3 = Vec3::new(0.0, 1.6, 3.6);
+const CAMERA_TARGET: Vec3 = Vec3::new(0.0, -0.5, 0.0);
+const CAMERA_FOCAL: f32 = 2.144_507; // 1 / tan(25 degrees), a 50 degree vertical fov
+const CAMERA_NEAR: f32 = 1.0;
+const CAMERA_FAR: f32 = 10.0;
+const LIGHTS: [Vec3; 3] = [
+    Vec3::new(5.0, 5.0, 5.0),
+    Vec3::new(-5.0, 5.0, 5.0),
+    Vec3::new(0.0, 5.0, -2.0),
+];
+const LIGHT_INTENSITY: f32 = 14.0;
+const LIGHT_DISTANCE: f32 = 10.0;
+const AMBIENT: f32 = 0.05;
+
+const BODY_MATERIALS: [&str; 2] = ["MatBody", "MatBodyDecal1"];
+const DECAL_MATERIAL: &str = "MatBodyDecal1";
+const DECAL_RADIUS_SQUARED: f32 = 0.16;
+
+struct Primitive {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+    material: Option<usize>,
+}
+
+#[derive(Clone, Copy)]
+struct Material {
+    base_color: Vec3,
+    texture: Option<usize>,
+    metalness: f32,
+    roughness: f32,
+    emissive: Vec3,
+    decal: bool,
+}
+
+/// Software rasterizer for kart images.
+///
+/// Output only depends on the kart and the model, no GPU, threads or system math library
+/// are involved, so a kart renders to the same PNG bytes everywhere.
+pub struct Renderer {
+    glb: Glb,
+    meshes: Vec<Vec<Primitive>>,
+    textures: Vec<Texture>,
+}
+
+impl Renderer {
+    /// Renderer for the bundled kart model.
+    pub fn new() -> Result<Self, RenderError> {
+        Renderer::from_glb(KART_MODEL)
+    }
+
+    pub fn from_glb(data: &[u8]) -> Result<Self, RenderError> {
+        let glb = Glb::parse(data)?;
+
+        let mut meshes = Vec::with_capacity(glb.document.meshes.len());
+        for mesh in glb.document.meshes.iter() {
+            let mut primitives = Vec::new();
+            // Only indexed triangle lists are drawn
+            for p in mesh.primitives.iter().filter(|p| p.mode == 4) {
+                let positions = glb.read_vec3(p.attributes.position)?;
+                let normals = match p.attributes.normal {
+                    Some(normal) => glb.read_vec3(normal)?,
+                    None => vec![Vec3::new(0.0, 1.0, 0.0); positions.len()],
+                };
+                let uvs = match p.attributes.texcoord {
+                    Some(texcoord) => glb.read_vec2(texcoord)?,
+                    None => vec![[0.0, 0.0]; positions.len()],
+                };
+                let indices = match p.indices {
+                    Some(indices) => glb.read_indices(indices)?,
+                    None => (0..positions.len() as u32).collect(),
+                };
+                if indices.iter().any(|i| *i as usize >= positions.len()) {
+                    return Err(RenderError::Model("vertex index out of range".to_string()));
+                }
+
+                primitives.push(Primitive {
+                    positions,
+                    normals,
+                    uvs,
+                    indices,
+                    material: p.material,
+                });
+            }
+            meshes.push(primitives);
+        }
+
+        let mut textures = Vec::with_capacity(glb.document.images.len());
+        for image in 0..glb.document.images.len() {
+            textures.push(Texture::decode_png(glb.image_bytes(image)?)?);
+        }
+
+        Ok(Renderer {
+            glb,
+            meshes,
+            textures,
+        })
+    }
+
+    fn material(&self, index: Option<usize>, style: &KartStyle) -> Material {
+        let default = crate::glb::Material::default();
+        let m = index
+            .and_then(|i| self.glb.document.materials.get(i))
+            .unwrap_or(&default);
+        let pbr = &m.pbr_metallic_roughness;
+        let [r, g, b, _] = pbr.base_color_factor;
+        let [er, eg, eb] = m.emissive_factor.unwrap_or([0.0; 3]);
+
+        let mut material = Material {
+            base_color: Vec3::new(r, g, b),
+            texture: pbr
+                .base_color_texture
+                .as_ref()
+                .and_then(|t| self.glb.document.textures.get(t.index))
+                .and_then(|t| t.source),
+            metalness: pbr.metallic_factor,
+            roughness: pbr.roughness_factor,
+            emissive: Vec3::new(er, eg, eb),
+            decal: m.name == DECAL_MATERIAL,
+        };
+
+        if BODY_MATERIALS.contains(&m.name.as_str()) {
+            material.base_color = style.color;
+            material.metalness = style.skin.metalness;
+            material.roughness = style.skin.roughness;
+            material.emissive = Vec3::default();
+        }
+        material
+    }
+
+    fn shade(&self, material: &Material, style: &KartStyle, f: &Fragment) -> Option<Vec3> {
+        let mut base = material.base_color;
+
+        if material.decal {
+            // Badge stand-in, a disc in the middle of the decal panel
+            let (du, dv) = (f.uv[0] - 0.5, f.uv[1] - 0.5);
+            match style.decal {
+                Some(color) if du * du + dv * dv <= DECAL_RADIUS_SQUARED => base = color,
+                _ => return None,
+            }
+        }
+        if let Some(texture) = material.texture.and_then(|t| self.textures.get(t)) {
+            base = base.scale(texture.sample(f.uv[0], f.uv[1]));
+        }
+
+        let view = (CAMERA_EYE - f.world).normalize();
+        let mut normal = f.normal.normalize();
+        // Everything in the model is double sided
+        if normal.dot(view) < 0.0 {
+            normal = -normal;
+        }
+
+        let f0 =
+            Vec3::new(0.04, 0.04, 0.04) * (1.0 - material.metalness) + base * material.metalness;
+        let diffuse = base * (1.0 - material.metalness);
+        let smoothness = 1.0 - material.roughness.clamp(0.0, 1.0);
+        // Specular exponent 2^(3..8) by repeated squaring, no powf
+        let squarings = 3 + (smoothness * 5.0) as u32;
+
+        let mut color = diffuse * AMBIENT + f0 * (AMBIENT * 4.0);
+        for light in LIGHTS.iter() {
+            let to_light = *light - f.world;
+            let distance = to_light.length();
+            let falloff = (1.0 - distance / LIGHT_DISTANCE).max(0.0);
+            let attenuation = falloff * falloff * LIGHT_INTENSITY;
+            let l = to_light * (1.0 / distance);
+
+            let n_dot_l = normal.dot(l).max(0.0);
+            let mut highlight = normal.dot((l + view).normalize()).max(0.0);
+            for _ in 0..squarings {
+                highlight = highlight * highlight;
+            }
+            let specular = f0 * (highlight * (1.0 + smoothness * 4.0) * n_dot_l);
+
+            color = color + (diffuse * n_dot_l + specular) * attenuation;
+        }
+
+        Some(color + material.emissive.scale(base))
+    }
+
+    /// Render a kart, returns `size` x `size` 8 bit RGB pixels.
+    pub fn render(&self, nk: &NearKart, size: u32) -> Vec<u8> {
+        let style = KartStyle::new(nk);
+        let samples = size as usize * SUPERSAMPLE;
+        let mut fb = Framebuffer::new(samples, samples, Vec3::default());
+
+        let view_projection = Mat4::perspective(CAMERA_FOCAL, 1.0, CAMERA_NEAR, CAMERA_FAR).mul(
+            &Mat4::look_at(CAMERA_EYE, CAMERA_TARGET, Vec3::new(0.0, 1.0, 0.0)),
+        );
+
+        // Depth first over the scene, a node is drawn if it and all its parents are visible
+        let document = &self.glb.document;
+        let mut stack: Vec<(usize, Mat4)> = document
+            .scenes
+            .get(document.scene)
+            .map(|scene| {
+                scene
+                    .nodes
+                    .iter()
+                    .rev()
+                    .map(|n| (*n, Mat4::IDENTITY))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        while let Some((index, parent)) = stack.pop() {
+            let node = match document.nodes.get(index) {
+                Some(node) => node,
+                None => continue,
+            };
+            if !style.is_visible(&node.name) {
+                continue;
+            }
+
+            let world = parent.mul(&node.local_matrix());
+            for child in node.children.iter().rev() {
+                stack.push((*child, world));
+            }
+
+            let primitives = match node.mesh.and_then(|m| self.meshes.get(m)) {
+                Some(primitives) => primitives,
+                None => continue,
+            };
+            for p in primitives.iter() {
+                let material = self.material(p.material, &style);
+                let vertex = |i: u32| {
+                    let i = i as usize;
+                    let position = world.transform_point(p.positions[i]);
+                    ClipVertex {
+                        clip: view_projection.project(position),
+                        world: position,
+                        normal: world.transform_normal(p.normals[i]),
+                        uv: p.uvs[i],
+                    }
+                };
+
+                for triangle in p.indices.chunks_exact(3) {
+                    let triangle = [
+                        vertex(triangle[0]),
+                        vertex(triangle[1]),
+                        vertex(triangle[2]),
+                    ];
+                    fb.draw_triangle(&triangle, &mut |f| self.shade(&material, &style, f));
+                }
+            }
+        }
+
+        resolve(&fb, size as usize)
+    }
+
+    pub fn render_png(&self, nk: &NearKart) -> Vec<u8> {
+        encode_png(IMAGE_SIZE, IMAGE_SIZE, &self.render(nk, IMAGE_SIZE))
+    }
+
+    /// CID of the kart image, see `cid::raw_cid` for how to pin it under the same CID.
+    pub fn media_cid(&self, nk: &NearKart) -> String {
+        raw_cid(&self.render_png(nk))
+    }
+}
+
+/// Average the supersamples, tone map (Reinhard) and convert to 8 bit with a 2.0 gamma.
+fn resolve(fb: &Framebuffer, size: usize) -> Vec<u8> {
+    let mut rgb = Vec::with_capacity(size * size * 3);
+    let scale = 1.0 / (SUPERSAMPLE * SUPERSAMPLE) as f32;
+
+    for y in 0..size {
+        for x in 0..size {
+            let mut sum = Vec3::default();
+            for sy in 0..SUPERSAMPLE {
+                for sx in 0..SUPERSAMPLE {
+                    let index = (y * SUPERSAMPLE + sy) * fb.width + x * SUPERSAMPLE + sx;
+                    sum = sum + fb.color[index];
+                }
+            }
+
+            let c = (sum * scale).max(0.0);
+            for v in [c.x, c.y, c.z].iter() {
+                let mapped = (v / (1.0 + v)).sqrt();
+                rgb.push((mapped * 255.0 + 0.5).min(255.0) as u8);
+            }
+        }
+    }
+    rgb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nearkarts_types::SHIELD_START_INDEX;
+
+    const TEST_SIZE: u32 = 48;
+
+    fn kart() -> NearKart {
+        let mut nk = NearKart::new();
+        nk.left = 1;
+        nk.right = SHIELD_START_INDEX;
+        nk.front = 2;
+        nk.color1 = 0x3366ff;
+        nk.decal1 = "7".to_string();
+        nk
+    }
+
+    #[test]
+    fn test_render_is_reproducible() {
+        let renderer = Renderer::new().unwrap();
+        let first = renderer.render(&kart(), TEST_SIZE);
+
+        assert_eq!(first.len(), (TEST_SIZE * TEST_SIZE * 3) as usize);
+        assert_eq!(first, Renderer::new().unwrap().render(&kart(), TEST_SIZE));
+        assert!(first.iter().any(|v| *v > 0));
+    }
+
+    #[test]
+    fn test_config_changes_image() {
+        let renderer = Renderer::new().unwrap();
+        let mut other = kart();
+        other.color1 = 0xff3300;
+
+        assert_ne!(
+            renderer.render(&kart(), TEST_SIZE),
+            renderer.render(&other, TEST_SIZE)
+        );
+    }
+
+    #[test]
+    fn test_invalid_model() {
+        assert!(Renderer::from_glb(b"not a model").is_err());
+    }
+}
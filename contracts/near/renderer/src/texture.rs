@@ -0,0 +1,83 @@
+use crate::math::Vec3;
+use crate::RenderError;
+
+/// Decoded RGBA8 texture, sampled nearest neighbour with repeat wrapping.
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// sRGB byte to linear, squared rather than the exact curve to stay free of `powf`.
+pub fn srgb_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    v * v
+}
+
+impl Texture {
+    pub fn decode_png(data: &[u8]) -> Result<Self, RenderError> {
+        let mut decoder = png::Decoder::new(data);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let channels = info.color_type.samples();
+        let pixels = buf[..info.buffer_size()]
+            .chunks(channels)
+            .map(|p| match channels {
+                1 => [p[0], p[0], p[0], 255],
+                2 => [p[0], p[0], p[0], p[1]],
+                3 => [p[0], p[1], p[2], 255],
+                _ => [p[0], p[1], p[2], p[3]],
+            })
+            .collect();
+
+        Ok(Texture {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        })
+    }
+
+    /// Linear color at a texture coordinate.
+    pub fn sample(&self, u: f32, v: f32) -> Vec3 {
+        let wrap = |t: f32, size: usize| -> usize {
+            let t = t - t.floor();
+            ((t * size as f32) as usize).min(size - 1)
+        };
+        let p = self.pixels[wrap(v, self.height) * self.width + wrap(u, self.width)];
+        Vec3::new(
+            srgb_to_linear(p[0]),
+            srgb_to_linear(p[1]),
+            srgb_to_linear(p[2]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker() -> Texture {
+        Texture {
+            width: 2,
+            height: 2,
+            pixels: vec![[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [0; 4]],
+        }
+    }
+
+    #[test]
+    fn test_sample_wraps() {
+        let texture = checker();
+        assert_eq!(texture.sample(0.25, 0.25), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(texture.sample(1.75, 0.25), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(texture.sample(0.25, -0.25), Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_srgb_to_linear() {
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert_eq!(srgb_to_linear(255), 1.0);
+    }
+}
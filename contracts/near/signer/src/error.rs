@@ -0,0 +1,62 @@
+use nearkarts_types::KartError;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum SignerError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Key(String),
+    /// The kart config was rejected, holds the error code the contract would panic with
+    InvalidKart(KartError),
+    InvalidRequest(String),
+    /// The media CID or hash was rejected, holds the error code the contract would panic with
+    InvalidMedia(KartError),
+    Media(String),
+    Server(String),
+}
+
+impl SignerError {
+    /// Short machine readable code returned by the HTTP service.
+    pub fn code(&self) -> &str {
+        match self {
+            SignerError::Io(_) => "error_io",
+            SignerError::Json(_) => "error_invalid_json",
+            SignerError::Key(_) => "error_signer_key",
+            SignerError::InvalidKart(e) => e.id(),
+            SignerError::InvalidRequest(_) => "error_invalid_request",
+            SignerError::InvalidMedia(e) => e.id(),
+            SignerError::Media(_) => "error_media",
+            SignerError::Server(_) => "error_server",
+        }
+    }
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerError::Io(e) => write!(f, "io error: {}", e),
+            SignerError::Json(e) => write!(f, "invalid json: {}", e),
+            SignerError::Key(msg) => write!(f, "signer key: {}", msg),
+            SignerError::InvalidKart(e) => write!(f, "invalid kart: {}", e.id()),
+            SignerError::InvalidRequest(msg) => write!(f, "invalid request: {}", msg),
+            SignerError::InvalidMedia(e) => write!(f, "invalid media: {}", e.id()),
+            SignerError::Media(msg) => write!(f, "media: {}", msg),
+            SignerError::Server(msg) => write!(f, "server: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SignerError {}
+
+impl From<io::Error> for SignerError {
+    fn from(e: io::Error) -> Self {
+        SignerError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SignerError {
+    fn from(e: serde_json::Error) -> Self {
+        SignerError::Json(e)
+    }
+}
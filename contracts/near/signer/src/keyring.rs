@@ -0,0 +1,197 @@
+use crate::SignerError;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// A signing key, both halves hex encoded like `signer_pub_keys` in the contract.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SignerKey {
+    pub id: String,
+    pub public_key: String,
+    pub secret_key: String,
+}
+
+/// Keyring file, `active` is the id of the key new signatures are made with.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct KeyRing {
+    pub active: Option<String>,
+    pub keys: Vec<SignerKey>,
+}
+
+impl SignerKey {
+    pub fn generate(id: &str) -> Self {
+        let keypair = Keypair::generate(&mut OsRng);
+        SignerKey::from_keypair(id, &keypair)
+    }
+
+    pub fn from_keypair(id: &str, keypair: &Keypair) -> Self {
+        SignerKey {
+            id: id.to_string(),
+            public_key: hex::encode(keypair.public.as_bytes()),
+            secret_key: hex::encode(keypair.secret.as_bytes()),
+        }
+    }
+
+    /// The keypair, checked against the stored public key.
+    pub fn keypair(&self) -> Result<Keypair, SignerError> {
+        let secret_bytes = hex::decode(&self.secret_key)
+            .map_err(|e| SignerError::Key(format!("{}: {}", self.id, e)))?;
+        let secret = SecretKey::from_bytes(&secret_bytes)
+            .map_err(|e| SignerError::Key(format!("{}: {}", self.id, e)))?;
+        let public = PublicKey::from(&secret);
+
+        if hex::encode(public.as_bytes()) != self.public_key {
+            return Err(SignerError::Key(format!(
+                "{}: public_key does not match secret_key",
+                self.id
+            )));
+        }
+
+        Ok(Keypair { secret, public })
+    }
+}
+
+impl KeyRing {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SignerError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Load a keyring, or start an empty one if the file doesn't exist yet.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self, SignerError> {
+        if !path.as_ref().exists() {
+            return Ok(KeyRing::default());
+        }
+        KeyRing::load(path)
+    }
+
+    /// Write the keyring, readable by the owner only as it holds secret keys.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SignerError> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(path)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SignerKey> {
+        self.keys.iter().find(|key| key.id == id)
+    }
+
+    /// Add a key. The first key of a keyring is activated, later ones wait for `activate`.
+    pub fn add(&mut self, key: SignerKey) -> Result<(), SignerError> {
+        if self.get(&key.id).is_some() {
+            return Err(SignerError::Key(format!("{} already exists", key.id)));
+        }
+
+        if self.active.is_none() {
+            self.active = Some(key.id.clone());
+        }
+        self.keys.push(key);
+        Ok(())
+    }
+
+    /// Switch signing to another key, it should already be registered with `add_signer_key`.
+    pub fn activate(&mut self, id: &str) -> Result<(), SignerError> {
+        if self.get(id).is_none() {
+            return Err(SignerError::Key(format!("{} not found", id)));
+        }
+        self.active = Some(id.to_string());
+        Ok(())
+    }
+
+    /// Remove a key that is no longer used. The active key can't be retired.
+    pub fn retire(&mut self, id: &str) -> Result<SignerKey, SignerError> {
+        if self.active.as_deref() == Some(id) {
+            return Err(SignerError::Key(format!(
+                "{} is the active key, activate another key first",
+                id
+            )));
+        }
+
+        let index = self
+            .keys
+            .iter()
+            .position(|key| key.id == id)
+            .ok_or_else(|| SignerError::Key(format!("{} not found", id)))?;
+        Ok(self.keys.remove(index))
+    }
+
+    pub fn active_key(&self) -> Result<&SignerKey, SignerError> {
+        let id = self
+            .active
+            .as_ref()
+            .ok_or_else(|| SignerError::Key("keyring has no active key".to_string()))?;
+        self.get(id)
+            .ok_or_else(|| SignerError::Key(format!("active key {} not found", id)))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn test_keypair() -> Keypair {
+        let secret = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    pub(crate) fn test_keyring() -> KeyRing {
+        let mut keyring = KeyRing::default();
+        keyring
+            .add(SignerKey::from_keypair("k1", &test_keypair()))
+            .unwrap();
+        keyring
+    }
+
+    #[test]
+    fn test_first_key_is_active() {
+        let keyring = test_keyring();
+        assert_eq!(keyring.active_key().unwrap().id, "k1");
+        assert_eq!(
+            keyring.active_key().unwrap().keypair().unwrap().public,
+            test_keypair().public
+        );
+    }
+
+    #[test]
+    fn test_rotation() {
+        let mut keyring = test_keyring();
+        keyring.add(SignerKey::generate("k2")).unwrap();
+        assert_eq!(keyring.active_key().unwrap().id, "k1");
+
+        assert!(keyring.retire("k1").is_err());
+        keyring.activate("k2").unwrap();
+        keyring.retire("k1").unwrap();
+
+        assert_eq!(keyring.keys.len(), 1);
+        assert_eq!(keyring.active_key().unwrap().id, "k2");
+        assert!(keyring.active_key().unwrap().keypair().is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_id() {
+        let mut keyring = test_keyring();
+        assert!(keyring.add(SignerKey::generate("k1")).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_public_key() {
+        let mut key = SignerKey::from_keypair("k1", &test_keypair());
+        key.public_key = hex::encode([1u8; 32]);
+        assert!(key.keypair().is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let keyring = test_keyring();
+        let json = serde_json::to_string(&keyring).unwrap();
+        assert_eq!(serde_json::from_str::<KeyRing>(&json).unwrap(), keyring);
+    }
+}
@@ -0,0 +1,26 @@
+/*
+Off-chain signing for kart media.
+NOTES:
+  - `nft_mint` and `upgrade` only accept media signed by a key in `signer_pub_keys`. The contract
+    verifies an ed25519 signature over `media_message(cid, media_hash)`, the CID and the hex
+    sha256 of the image, given as hex with the hex public key.
+  - A requested kart is checked with the same `validate_equip` rules the contract enforces, after
+    applying the same mint / upgrade changes, so nothing gets signed the contract would reject.
+  - Signing keys live in a keyring file. New keys are added inactive so they can be registered
+    with `add_signer_key` before being activated, retired keys are removed from the file.
+  - By default the CID and hash the client uploaded are signed once they pass the contract's
+    `validate_media` checks. With `--render` the kart is rendered with nearkarts-renderer and
+    only the CID and hash of that image are signed.
+  - The HTTP service binds to localhost by default, `sign` on the command line is the same
+    request / response without a server.
+*/
+pub mod error;
+pub mod keyring;
+pub mod media;
+pub mod server;
+pub mod sign;
+
+pub use crate::error::SignerError;
+pub use crate::keyring::{KeyRing, SignerKey};
+pub use crate::media::{AcceptCid, Media, MediaSource, RenderedMedia};
+pub use crate::sign::{sign_kart, KartAction, SignRequest, SignedKart};
@@ -0,0 +1,129 @@
+/*
+nearkarts-signer
+
+USAGE:
+  nearkarts-signer keygen <keyring.json> <key_id>
+  nearkarts-signer activate <keyring.json> <key_id>
+  nearkarts-signer retire <keyring.json> <key_id>
+  nearkarts-signer keys <keyring.json>
+  nearkarts-signer sign <keyring.json> <request.json> [--render]
+  nearkarts-signer serve <keyring.json> [addr] [--render]
+
+Rotating keys:
+  1. `keygen` a new key and register its public key with `nearkarts-admin add-signer-key`
+  2. `activate` it, a running service picks it up on the next request
+  3. once nothing signed with the old key is pending, `remove-signer-key` and `retire` it
+
+--render renders the kart and signs the CID of that image instead of the requested one.
+*/
+use nearkarts_signer::server::{SigningService, DEFAULT_ADDR};
+use nearkarts_signer::{
+    sign_kart, AcceptCid, KeyRing, MediaSource, RenderedMedia, SignRequest, SignerError, SignerKey,
+};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "usage:
+  nearkarts-signer keygen <keyring.json> <key_id>
+  nearkarts-signer activate <keyring.json> <key_id>
+  nearkarts-signer retire <keyring.json> <key_id>
+  nearkarts-signer keys <keyring.json>
+  nearkarts-signer sign <keyring.json> <request.json> [--render]
+  nearkarts-signer serve <keyring.json> [addr] [--render]";
+
+fn keygen(path: &str, id: &str) -> Result<(), SignerError> {
+    let mut keyring = KeyRing::load_or_default(path)?;
+    let key = SignerKey::generate(id);
+    let public_key = key.public_key.clone();
+
+    keyring.add(key)?;
+    keyring.save(path)?;
+    println!("{}", public_key);
+    Ok(())
+}
+
+fn activate(path: &str, id: &str) -> Result<(), SignerError> {
+    let mut keyring = KeyRing::load(path)?;
+    keyring.activate(id)?;
+    keyring.save(path)
+}
+
+fn retire(path: &str, id: &str) -> Result<(), SignerError> {
+    let mut keyring = KeyRing::load(path)?;
+    let key = keyring.retire(id)?;
+    keyring.save(path)?;
+    println!(
+        "retired {}, remove {} from the contract",
+        key.id, key.public_key
+    );
+    Ok(())
+}
+
+fn keys(path: &str) -> Result<(), SignerError> {
+    let keyring = KeyRing::load(path)?;
+    for key in keyring.keys.iter() {
+        let active = keyring.active.as_deref() == Some(key.id.as_str());
+        println!(
+            "{} {}{}",
+            key.id,
+            key.public_key,
+            if active { " (active)" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+fn media(render: bool) -> Result<Box<dyn MediaSource + Send + Sync>, SignerError> {
+    if render {
+        return Ok(Box::new(RenderedMedia::new()?));
+    }
+    Ok(Box::new(AcceptCid))
+}
+
+fn sign(path: &str, request: &str, render: bool) -> Result<(), SignerError> {
+    let keyring = KeyRing::load(path)?;
+    let request: SignRequest = serde_json::from_str(&fs::read_to_string(request)?)?;
+    let signed = sign_kart(&keyring, media(render)?.as_ref(), &request)?;
+
+    println!("{}", serde_json::to_string_pretty(&signed)?);
+    Ok(())
+}
+
+fn serve(path: &str, addr: &str, render: bool) -> Result<(), SignerError> {
+    // Fail on a broken keyring at startup rather than on the first request
+    KeyRing::load(path)?.active_key()?.keypair()?;
+
+    let service = SigningService::new(PathBuf::from(path), media(render)?);
+    println!("listening on http://{}", addr);
+    service.serve(addr)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let render = args.iter().any(|arg| arg == "--render");
+    let args: Vec<&str> = args
+        .iter()
+        .map(|arg| arg.as_str())
+        .filter(|arg| *arg != "--render")
+        .collect();
+
+    let result = match args.as_slice() {
+        ["keygen", path, id] => keygen(path, id),
+        ["activate", path, id] => activate(path, id),
+        ["retire", path, id] => retire(path, id),
+        ["keys", path] => keys(path),
+        ["sign", path, request] => sign(path, request, render),
+        ["serve", path] => serve(path, DEFAULT_ADDR, render),
+        ["serve", path, addr] => serve(path, addr, render),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::SignerError;
+use nearkarts_renderer::{raw_cid, sha256, Renderer};
+use nearkarts_types::{validate_media, NearKart};
+
+/// Media a kart gets signed with, the image CID and the sha256 of the image file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Media {
+    pub cid: String,
+    pub hash: Vec<u8>,
+}
+
+/// Decides the media that gets signed for a kart.
+pub trait MediaSource {
+    /// `requested` is the media sent with the request, if any. `kart` is the kart as it will be
+    /// stored after the mint or upgrade.
+    fn media(&self, kart: &NearKart, requested: Option<&Media>) -> Result<Media, SignerError>;
+}
+
+/// Sign whatever media the client uploaded, the image is rendered by the browser.
+///
+/// The CID and hash are checked the same way the contract checks them.
+pub struct AcceptCid;
+
+impl MediaSource for AcceptCid {
+    fn media(&self, _kart: &NearKart, requested: Option<&Media>) -> Result<Media, SignerError> {
+        let media = requested
+            .ok_or_else(|| SignerError::Media("a cid and media_hash are required".to_string()))?;
+        validate_media(&media.cid, &media.hash).map_err(SignerError::InvalidMedia)?;
+        Ok(media.clone())
+    }
+}
+
+/// Render the kart server side and sign the CID of that image.
+///
+/// Requested media must match the render, so a client can only get its own upload signed when
+/// it is the image the kart actually renders to. Without one the rendered media is signed, the
+/// client then pins `nearkarts-renderer render` output under it.
+pub struct RenderedMedia {
+    renderer: Renderer,
+}
+
+impl RenderedMedia {
+    pub fn new() -> Result<Self, SignerError> {
+        let renderer = Renderer::new().map_err(|e| SignerError::Media(e.to_string()))?;
+        Ok(RenderedMedia { renderer })
+    }
+}
+
+impl MediaSource for RenderedMedia {
+    fn media(&self, kart: &NearKart, requested: Option<&Media>) -> Result<Media, SignerError> {
+        let png = self.renderer.render_png(kart);
+        let media = Media {
+            cid: raw_cid(&png),
+            hash: sha256(&png).to_vec(),
+        };
+        match requested {
+            Some(requested) if *requested != media => Err(SignerError::Media(format!(
+                "cid {} doesn't match the render {}",
+                requested.cid, media.cid
+            ))),
+            _ => Ok(media),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// `hello` as a raw CID.
+    pub(crate) const TEST_CID: &str = "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq";
+    pub(crate) const TEST_MEDIA_HASH: &str = "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+
+    pub(crate) fn test_media() -> Media {
+        Media {
+            cid: TEST_CID.to_string(),
+            hash: base64::decode(TEST_MEDIA_HASH).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_accept_cid() {
+        let kart = NearKart::new();
+        assert_eq!(
+            AcceptCid.media(&kart, Some(&test_media())).unwrap(),
+            test_media()
+        );
+        assert!(AcceptCid.media(&kart, None).is_err());
+
+        let mut tampered = test_media();
+        tampered.hash[0] ^= 1;
+        assert_eq!(
+            AcceptCid.media(&kart, Some(&tampered)).unwrap_err().code(),
+            "error_media_hash_mismatch"
+        );
+
+        let mut invalid = test_media();
+        invalid.cid = "bafy".to_string();
+        assert_eq!(
+            AcceptCid.media(&kart, Some(&invalid)).unwrap_err().code(),
+            "error_invalid_cid"
+        );
+    }
+
+    #[test]
+    fn test_rendered_media() {
+        let media = RenderedMedia::new().unwrap();
+        let kart = NearKart::new();
+        let rendered = media.media(&kart, None).unwrap();
+
+        assert!(validate_media(&rendered.cid, &rendered.hash).is_ok());
+        assert_eq!(media.media(&kart, Some(&rendered)).unwrap(), rendered);
+        assert!(media.media(&kart, Some(&test_media())).is_err());
+    }
+}
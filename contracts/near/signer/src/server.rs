@@ -0,0 +1,147 @@
+use crate::{sign_kart, KeyRing, MediaSource, SignRequest, SignerError};
+use serde_json::json;
+use std::path::PathBuf;
+use tiny_http::{Header, Method, Response, Server};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8787";
+
+/// HTTP front end for `sign_kart`.
+///
+/// The keyring is read on every request, so activating or retiring a key takes effect without
+/// restarting the service.
+pub struct SigningService {
+    keyring_path: PathBuf,
+    media: Box<dyn MediaSource + Send + Sync>,
+}
+
+fn error_body(e: &SignerError) -> String {
+    json!({ "error": e.code(), "message": e.to_string() }).to_string()
+}
+
+impl SigningService {
+    pub fn new(keyring_path: PathBuf, media: Box<dyn MediaSource + Send + Sync>) -> Self {
+        SigningService {
+            keyring_path,
+            media,
+        }
+    }
+
+    fn sign(&self, body: &str) -> Result<String, SignerError> {
+        let request: SignRequest = serde_json::from_str(body)?;
+        let keyring = KeyRing::load(&self.keyring_path)?;
+        let signed = sign_kart(&keyring, self.media.as_ref(), &request)?;
+        Ok(serde_json::to_string(&signed)?)
+    }
+
+    fn keys(&self) -> Result<String, SignerError> {
+        let keyring = KeyRing::load(&self.keyring_path)?;
+        let keys: Vec<_> = keyring
+            .keys
+            .iter()
+            .map(|key| json!({ "id": key.id, "public_key": key.public_key }))
+            .collect();
+        Ok(json!({ "active": keyring.active, "keys": keys }).to_string())
+    }
+
+    /// Route a request, returns the status code and JSON body.
+    pub fn handle(&self, method: &Method, path: &str, body: &str) -> (u16, String) {
+        let result = match (method, path) {
+            (Method::Post, "/sign") => self.sign(body),
+            (Method::Get, "/keys") => self.keys(),
+            (Method::Get, "/health") => Ok(json!({ "ok": true }).to_string()),
+            _ => return (404, json!({ "error": "error_not_found" }).to_string()),
+        };
+
+        match result {
+            Ok(body) => (200, body),
+            Err(e @ SignerError::Io(_)) | Err(e @ SignerError::Key(_)) => (500, error_body(&e)),
+            Err(e) => (400, error_body(&e)),
+        }
+    }
+
+    pub fn serve(&self, addr: &str) -> Result<(), SignerError> {
+        let server = Server::http(addr).map_err(|e| SignerError::Server(e.to_string()))?;
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+            let (status, response) = match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => self.handle(request.method(), request.url(), &body),
+                Err(e) => (400, error_body(&SignerError::Io(e))),
+            };
+
+            let response = Response::from_string(response)
+                .with_status_code(status)
+                .with_header(content_type.clone());
+            if let Err(e) = request.respond(response) {
+                eprintln!("error: {}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyring::tests::test_keyring;
+    use crate::media::tests::{TEST_CID, TEST_MEDIA_HASH};
+    use crate::AcceptCid;
+    use nearkarts_types::NearKart;
+    use std::env;
+    use std::fs;
+
+    fn service(name: &str) -> SigningService {
+        let path = env::temp_dir().join(format!("nearkarts-signer-{}.json", name));
+        test_keyring().save(&path).unwrap();
+        SigningService::new(path, Box::new(AcceptCid))
+    }
+
+    #[test]
+    fn test_sign_route() {
+        let service = service("sign");
+        let body = json!({
+            "action": "mint",
+            "near_kart": NearKart::new(),
+            "cid": TEST_CID,
+            "media_hash": TEST_MEDIA_HASH,
+        })
+        .to_string();
+
+        let (status, response) = service.handle(&Method::Post, "/sign", &body);
+        assert_eq!(status, 200);
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["cid"], TEST_CID);
+        assert_eq!(response["media_hash"], TEST_MEDIA_HASH);
+        assert_eq!(response["key_id"], "k1");
+
+        fs::remove_file(&service.keyring_path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_kart_is_bad_request() {
+        let service = service("invalid");
+        let mut kart = NearKart::new();
+        kart.skin = 9;
+        let body = json!({ "action": "mint", "near_kart": kart, "cid": "bafy" }).to_string();
+
+        let (status, response) = service.handle(&Method::Post, "/sign", &body);
+        assert_eq!(status, 400);
+        assert!(response.contains("error_skin_index_too_high"));
+
+        fs::remove_file(&service.keyring_path).unwrap();
+    }
+
+    #[test]
+    fn test_keys_route() {
+        let service = service("keys");
+
+        let (status, response) = service.handle(&Method::Get, "/keys", "");
+        assert_eq!(status, 200);
+        assert!(response.contains(r#""active":"k1""#));
+        assert!(!response.contains("secret_key"));
+
+        assert_eq!(service.handle(&Method::Get, "/nope", "").0, 404);
+        fs::remove_file(&service.keyring_path).unwrap();
+    }
+}
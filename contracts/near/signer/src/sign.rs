@@ -0,0 +1,250 @@
+use crate::{KeyRing, Media, MediaSource, SignerError};
+use ed25519_dalek::Signer;
+use nearkarts_types::{media_message, validate_equip, KartError, NearKart};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum KartAction {
+    Mint,
+    Upgrade,
+    /// A relayed configure, see `relay_action` in the contract.
+    Configure,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SignRequest {
+    pub action: KartAction,
+    pub near_kart: NearKart,
+    /// The kart as currently stored on chain, required for an upgrade or configure.
+    #[serde(default)]
+    pub near_kart_prev: Option<NearKart>,
+    #[serde(default)]
+    pub cid: Option<String>,
+    /// Base64 sha256 of the image, required with `cid`.
+    #[serde(default)]
+    pub media_hash: Option<String>,
+}
+
+/// Arguments for `nft_mint` / `upgrade` / a relayed configure, plus the kart the contract will
+/// end up storing.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SignedKart {
+    pub cid: String,
+    /// Base64 sha256 of the image, the `media_hash` argument.
+    pub media_hash: String,
+    pub sig: String,
+    pub pub_key: String,
+    pub key_id: String,
+    pub near_kart: NearKart,
+}
+
+/// Apply the contract's mint / upgrade / configure changes to a request and check the result.
+///
+/// Returns the kart the contract would store.
+pub fn resolve_kart(request: &SignRequest) -> Result<NearKart, SignerError> {
+    match request.action {
+        KartAction::Mint => {
+            let mut kart = request.near_kart.clone();
+            kart.reset_for_mint();
+            validate_equip(&kart, &kart).map_err(SignerError::InvalidKart)?;
+            Ok(kart)
+        }
+        KartAction::Upgrade => {
+            let prev = request.near_kart_prev.as_ref().ok_or_else(|| {
+                SignerError::InvalidRequest("an upgrade needs near_kart_prev".to_string())
+            })?;
+            if prev.locked {
+                return Err(SignerError::InvalidKart(KartError::KartLocked));
+            }
+            validate_equip(&request.near_kart, prev).map_err(SignerError::InvalidKart)?;
+
+            let mut kart = prev.clone();
+            kart.apply_upgrade(&request.near_kart);
+            Ok(kart)
+        }
+        KartAction::Configure => {
+            let prev = request.near_kart_prev.as_ref().ok_or_else(|| {
+                SignerError::InvalidRequest("a configure needs near_kart_prev".to_string())
+            })?;
+            let mut near_kart = request.near_kart.clone();
+            near_kart.level = prev.level;
+            validate_equip(&near_kart, prev).map_err(SignerError::InvalidKart)?;
+
+            let mut kart = prev.clone();
+            kart.apply_config(&near_kart);
+            Ok(kart)
+        }
+    }
+}
+
+fn requested_media(request: &SignRequest) -> Result<Option<Media>, SignerError> {
+    match (&request.cid, &request.media_hash) {
+        (None, None) => Ok(None),
+        (Some(cid), Some(media_hash)) => Ok(Some(Media {
+            cid: cid.clone(),
+            hash: base64::decode(media_hash)
+                .map_err(|_| SignerError::InvalidMedia(KartError::InvalidMediaHash))?,
+        })),
+        _ => Err(SignerError::InvalidRequest(
+            "cid and media_hash go together".to_string(),
+        )),
+    }
+}
+
+/// Validate a request, resolve its media and sign the CID and media hash with the active key.
+pub fn sign_kart(
+    keyring: &KeyRing,
+    media: &dyn MediaSource,
+    request: &SignRequest,
+) -> Result<SignedKart, SignerError> {
+    let kart = resolve_kart(request)?;
+    let media = media.media(&kart, requested_media(request)?.as_ref())?;
+
+    let key = keyring.active_key()?;
+    let keypair = key.keypair()?;
+    let signature = keypair.sign(media_message(&media.cid, &media.hash).as_bytes());
+
+    Ok(SignedKart {
+        cid: media.cid,
+        media_hash: base64::encode(&media.hash),
+        sig: hex::encode(signature.to_bytes()),
+        pub_key: key.public_key.clone(),
+        key_id: key.id.clone(),
+        near_kart: kart,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyring::tests::{test_keypair, test_keyring};
+    use crate::media::tests::{TEST_CID, TEST_MEDIA_HASH};
+    use crate::AcceptCid;
+    use ed25519_dalek::{PublicKey, Signature, Verifier};
+
+    fn mint_request() -> SignRequest {
+        SignRequest {
+            action: KartAction::Mint,
+            near_kart: NearKart::new(),
+            near_kart_prev: None,
+            cid: Some(TEST_CID.to_string()),
+            media_hash: Some(TEST_MEDIA_HASH.to_string()),
+        }
+    }
+
+    // Same steps as verify_sig in the contract
+    fn contract_verify(message: &str, sig: &str, pub_key: &str) -> bool {
+        let s = Signature::from_bytes(&hex::decode(sig).unwrap()).unwrap();
+        let pub_key_obj = PublicKey::from_bytes(&hex::decode(pub_key).unwrap()).unwrap();
+        pub_key_obj.verify(message.as_bytes(), &s).is_ok()
+    }
+
+    #[test]
+    fn test_sign_mint() {
+        let signed = sign_kart(&test_keyring(), &AcceptCid, &mint_request()).unwrap();
+
+        assert_eq!(signed.cid, TEST_CID);
+        assert_eq!(signed.media_hash, TEST_MEDIA_HASH);
+        assert_eq!(signed.key_id, "k1");
+        assert_eq!(
+            signed.pub_key,
+            hex::encode(test_keypair().public.as_bytes())
+        );
+        let message = media_message(&signed.cid, &base64::decode(&signed.media_hash).unwrap());
+        assert!(contract_verify(&message, &signed.sig, &signed.pub_key));
+        assert_eq!(signed.near_kart.extra1, "7");
+        assert!(signed.near_kart.locked);
+    }
+
+    #[test]
+    fn test_mint_ignores_client_level() {
+        let mut request = mint_request();
+        request.near_kart.level = 50;
+        request.near_kart.right = 5;
+
+        match sign_kart(&test_keyring(), &AcceptCid, &request) {
+            Err(SignerError::InvalidKart(e)) => {
+                assert_eq!(e, KartError::LevelTooLowForRightWeapon)
+            }
+            other => panic!("expected an invalid kart, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_upgrade_requires_unlocked_prev() {
+        let mut prev = NearKart::new();
+        prev.reset_for_mint();
+
+        let request = SignRequest {
+            action: KartAction::Upgrade,
+            near_kart: prev.clone(),
+            near_kart_prev: Some(prev.clone()),
+            cid: Some(TEST_CID.to_string()),
+            media_hash: Some(TEST_MEDIA_HASH.to_string()),
+        };
+        assert_eq!(
+            resolve_kart(&request).unwrap_err().code(),
+            "error_cannot_upgrade_while_kart_is_locked"
+        );
+
+        let mut request = request;
+        request.near_kart_prev.as_mut().unwrap().locked = false;
+        let kart = resolve_kart(&request).unwrap();
+        assert_eq!(kart.level, 2);
+    }
+
+    #[test]
+    fn test_configure_keeps_level() {
+        let mut prev = NearKart::new();
+        prev.reset_for_mint();
+
+        let mut near_kart = prev.clone();
+        near_kart.level = 50;
+        near_kart.right = 5;
+        let request = SignRequest {
+            action: KartAction::Configure,
+            near_kart: near_kart,
+            near_kart_prev: Some(prev.clone()),
+            cid: Some(TEST_CID.to_string()),
+            media_hash: Some(TEST_MEDIA_HASH.to_string()),
+        };
+        assert_eq!(
+            resolve_kart(&request).unwrap_err().code(),
+            "error_level_not_high_enough_to_equip_right_weapon"
+        );
+
+        let mut request = request;
+        request.near_kart.right = 2;
+        let kart = resolve_kart(&request).unwrap();
+        assert_eq!(kart.level, 1);
+        assert_eq!(kart.right, 2);
+        assert!(kart.locked);
+    }
+
+    #[test]
+    fn test_upgrade_without_prev() {
+        let mut request = mint_request();
+        request.action = KartAction::Upgrade;
+        assert!(matches!(
+            resolve_kart(&request),
+            Err(SignerError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_request_json() {
+        let request: SignRequest = serde_json::from_str(&format!(
+            r#"{{"action":"mint","near_kart":{},"cid":"bafy"}}"#,
+            serde_json::to_string(&NearKart::new()).unwrap()
+        ))
+        .unwrap();
+
+        assert_eq!(request.action, KartAction::Mint);
+        assert!(request.near_kart_prev.is_none());
+        assert!(matches!(
+            requested_media(&request),
+            Err(SignerError::InvalidRequest(_))
+        ));
+    }
+}
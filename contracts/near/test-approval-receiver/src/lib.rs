@@ -1,5 +1,6 @@
 /*!
-A stub contract that implements nft_on_approve for simulation testing nft_approve.
+A stub contract that implements nft_on_approve for simulation testing nft_approve, and battles
+karts it operates like a game bot for simulation testing kart operators.
 */
 use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApprovalReceiver;
 use near_contract_standards::non_fungible_token::TokenId;
@@ -7,7 +8,7 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::ValidAccountId;
 use near_sdk::{
     env, ext_contract, log, near_bindgen, setup_alloc, AccountId, Balance, Gas, PanicOnDefault,
-    PromiseOrValue,
+    Promise, PromiseOrValue,
 };
 
 setup_alloc!();
@@ -30,6 +31,11 @@ pub trait ValueReturnTrait {
     fn ok_go(&self, msg: String) -> PromiseOrValue<String>;
 }
 
+#[ext_contract(ext_nft)]
+pub trait NearKarts {
+    fn game_simple_battle(&mut self, token_id: TokenId);
+}
+
 // Have to repeat the same trait for our own implementation.
 trait ValueReturnTrait {
     fn ok_go(&self, msg: String) -> PromiseOrValue<String>;
@@ -41,6 +47,16 @@ impl ApprovalReceiver {
     pub fn new(non_fungible_token_account_id: ValidAccountId) -> Self {
         Self { non_fungible_token_account_id: non_fungible_token_account_id.into() }
     }
+
+    /// Battle with a kart this contract was made an operator of.
+    pub fn battle(&mut self, token_id: TokenId) -> Promise {
+        ext_nft::game_simple_battle(
+            token_id,
+            &self.non_fungible_token_account_id,
+            NO_DEPOSIT,
+            env::prepaid_gas() - BASE_GAS,
+        )
+    }
 }
 
 #[near_bindgen]
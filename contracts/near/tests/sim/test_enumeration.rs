@@ -10,22 +10,9 @@ fn mint_more(root: &UserAccount, nft: &ContractAccount<NftContract>) {
         &root,
         &nft,
         "Black as the Night".to_string(),
-        "In charcoal".to_string(),
-    );
-    helper_mint(
-        "2".to_string(),
-        &root,
-        &nft,
-        "Hamakua".to_string(),
-        "Vintage recording".to_string(),
-    );
-    helper_mint(
-        "3".to_string(),
-        &root,
-        &nft,
-        "Aloha ke akua".to_string(),
-        "Original with piano".to_string(),
     );
+    helper_mint("2".to_string(), &root, &nft, "Hamakua".to_string());
+    helper_mint("3".to_string(), &root, &nft, "Aloha ke akua".to_string());
 }
 
 #[test]
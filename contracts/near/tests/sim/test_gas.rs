@@ -0,0 +1,54 @@
+use crate::utils::{helper_mint, init, TOKEN_ID};
+use near_sdk_sim::{call, view, ContractAccount, UserAccount};
+use non_fungible_token::ContractContract as NftContract;
+
+const SMALL_COLLECTION: u32 = 10;
+const LARGE_COLLECTION: u32 = 2000;
+// Gas may vary a little with the random buffer refills, a scan of the collection would not
+const MAX_GAS_GROWTH_PERCENT: u64 = 10;
+
+fn mint_karts(root: &UserAccount, nft: &ContractAccount<NftContract>, from: u32, to: u32) {
+    for i in from..to {
+        helper_mint(i.to_string(), root, nft, format!("Kart {}", i));
+    }
+}
+
+/// Gas burnt by the enumeration methods a battle depends on.
+fn enumeration_gas(root: &UserAccount, nft: &ContractAccount<NftContract>) -> (u64, u64) {
+    let opponent = call!(root, nft.get_random_opponent(TOKEN_ID.into()));
+    opponent.assert_success();
+
+    let last_index = view!(nft.get_num_karts()).unwrap_json::<u32>() - 1;
+    let by_index = call!(root, nft.get_token_id_by_index(last_index));
+    by_index.assert_success();
+
+    (opponent.gas_burnt(), by_index.gas_burnt())
+}
+
+fn assert_flat(small: u64, large: u64) {
+    assert!(
+        large * 100 <= small * (100 + MAX_GAS_GROWTH_PERCENT),
+        "gas grew from {} to {}",
+        small,
+        large
+    );
+}
+
+#[test]
+fn simulate_enumeration_gas_is_flat() {
+    let (root, nft, _, _, _) = init();
+
+    // `init` mints the first kart
+    mint_karts(&root, &nft, 1, SMALL_COLLECTION);
+    let num_karts: u32 = view!(nft.get_num_karts()).unwrap_json();
+    assert_eq!(num_karts, SMALL_COLLECTION);
+    let (small_opponent, small_by_index) = enumeration_gas(&root, &nft);
+
+    mint_karts(&root, &nft, SMALL_COLLECTION, LARGE_COLLECTION);
+    let num_karts: u32 = view!(nft.get_num_karts()).unwrap_json();
+    assert_eq!(num_karts, LARGE_COLLECTION);
+    let (large_opponent, large_by_index) = enumeration_gas(&root, &nft);
+
+    assert_flat(small_opponent, large_opponent);
+    assert_flat(small_by_index, large_by_index);
+}
@@ -0,0 +1,174 @@
+use crate::utils::{init, TOKEN_ID};
+use near_contract_standards::non_fungible_token::Token;
+use near_sdk_sim::{call, view};
+use non_fungible_token::{NearKart, SimpleBattle};
+
+#[test]
+fn simulate_operator_battles_and_configures() {
+    let (root, nft, _, _, approval_receiver) = init();
+
+    // root lets the approval receiver contract operate its kart
+    call!(
+        root,
+        nft.kart_approve_operator(TOKEN_ID.into(), approval_receiver.valid_account_id()),
+        deposit = 1
+    )
+    .assert_success();
+
+    let is_operator: bool = view!(nft.kart_is_operator(
+        TOKEN_ID.into(),
+        approval_receiver.valid_account_id()
+    ))
+    .unwrap_json();
+    assert!(is_operator);
+
+    // the operator contract battles with the kart
+    let outcome = call!(root, approval_receiver.battle(TOKEN_ID.into()));
+    outcome.assert_success();
+    assert!(outcome.promise_errors().is_empty());
+
+    // and the battle counts as root's
+    let last_battle: SimpleBattle =
+        view!(nft.get_last_battle(root.valid_account_id())).unwrap_json();
+    assert_eq!(last_battle.home_token_id, TOKEN_ID);
+
+    let near_kart: NearKart = view!(nft.near_kart_get_config(TOKEN_ID.into())).unwrap_json();
+    assert!(near_kart.level >= 1);
+}
+
+#[test]
+fn simulate_operator_cannot_transfer() {
+    let (root, nft, _, _, approval_receiver) = init();
+
+    call!(
+        root,
+        nft.kart_approve_operator(TOKEN_ID.into(), approval_receiver.valid_account_id()),
+        deposit = 1
+    )
+    .assert_success();
+
+    let outcome = call!(
+        approval_receiver.user_account,
+        nft.nft_transfer(
+            approval_receiver.valid_account_id(),
+            TOKEN_ID.into(),
+            None,
+            None
+        ),
+        deposit = 1
+    );
+    assert!(!outcome.is_ok());
+
+    let token: Token = view!(nft.nft_token(TOKEN_ID.into())).unwrap_json();
+    assert_eq!(token.owner_id, root.account_id());
+}
+
+#[test]
+fn simulate_revoked_operator_cannot_battle() {
+    let (root, nft, _, _, approval_receiver) = init();
+
+    call!(
+        root,
+        nft.kart_approve_operator(TOKEN_ID.into(), approval_receiver.valid_account_id()),
+        deposit = 1
+    )
+    .assert_success();
+    call!(
+        root,
+        nft.kart_revoke_operator(TOKEN_ID.into(), approval_receiver.valid_account_id()),
+        deposit = 1
+    )
+    .assert_success();
+
+    let outcome = call!(root, approval_receiver.battle(TOKEN_ID.into()));
+    let errors = outcome.promise_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(format!("{:?}", errors[0].as_ref().unwrap().status())
+        .contains("KART_ERR:106:"));
+}
+
+#[test]
+fn simulate_transfer_clears_operators() {
+    let (root, nft, alice, _, approval_receiver) = init();
+
+    call!(
+        root,
+        nft.kart_approve_operator(TOKEN_ID.into(), approval_receiver.valid_account_id()),
+        deposit = 1
+    )
+    .assert_success();
+
+    call!(
+        root,
+        nft.nft_transfer(alice.valid_account_id(), TOKEN_ID.into(), None, None),
+        deposit = 1
+    )
+    .assert_success();
+
+    let operators: Vec<String> = view!(nft.kart_operators(TOKEN_ID.into())).unwrap_json();
+    assert!(operators.is_empty());
+
+    // an NEP-178 approval the approval receiver accepts does not make an operator either
+    let outcome = call!(
+        alice,
+        nft.nft_approve(
+            TOKEN_ID.into(),
+            approval_receiver.valid_account_id(),
+            Some("return-now".into())
+        ),
+        deposit = 290000000000000000000
+    );
+    outcome.assert_success();
+    assert!(outcome.promise_errors().is_empty());
+
+    let outcome = call!(alice, approval_receiver.battle(TOKEN_ID.into()));
+    assert_eq!(outcome.promise_errors().len(), 1);
+}
+
+#[test]
+fn simulate_returned_transfer_call_keeps_operators() {
+    let (root, nft, _, token_receiver, approval_receiver) = init();
+
+    call!(
+        root,
+        nft.kart_approve_operator(TOKEN_ID.into(), approval_receiver.valid_account_id()),
+        deposit = 1
+    )
+    .assert_success();
+
+    // the receiver returns the kart, so root's operator can still battle with it
+    call!(
+        root,
+        nft.nft_transfer_call(
+            token_receiver.valid_account_id(),
+            TOKEN_ID.into(),
+            None,
+            None,
+            "return-it-later".into()
+        ),
+        deposit = 1
+    );
+    let token: Token = view!(nft.nft_token(TOKEN_ID.into())).unwrap_json();
+    assert_eq!(token.owner_id, root.account_id());
+
+    let outcome = call!(root, approval_receiver.battle(TOKEN_ID.into()));
+    assert!(outcome.promise_errors().is_empty());
+
+    // once a receiver keeps it, the operators are gone
+    call!(
+        root,
+        nft.nft_transfer_call(
+            token_receiver.valid_account_id(),
+            TOKEN_ID.into(),
+            None,
+            None,
+            "keep-it-later".into()
+        ),
+        deposit = 1
+    );
+    let token: Token = view!(nft.nft_token(TOKEN_ID.into())).unwrap_json();
+    assert_eq!(token.owner_id, token_receiver.account_id());
+
+    let operators: Vec<String> = view!(nft.kart_operators(TOKEN_ID.into())).unwrap_json();
+    assert!(operators.is_empty());
+}
@@ -1,6 +1,7 @@
 use approval_receiver::ApprovalReceiverContract;
-use near_contract_standards::non_fungible_token::metadata::TokenMetadata;
+use near_sdk::json_types::Base64VecU8;
 use non_fungible_token::ContractContract as NftContract;
+use non_fungible_token::{parse_cid, NearKart};
 use token_receiver::TokenReceiverContract;
 
 use near_contract_standards::non_fungible_token::TokenId;
@@ -13,13 +14,20 @@ near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
     APPROVAL_RECEIVER_WASM_BYTES => "res/approval_receiver.wasm",
 }
 
-const NFT_ID: &str = "nft";
+// A sub-account, so the contract account passes the contract owner check
+const NFT_ID: &str = "nearkarts.root";
 const TOKEN_RECEIVER_ID: &str = "token-receiver";
 const APPROVAL_RECEIVER_ID: &str = "approval-receiver";
 
 // TODO: how to export String instead of &str? Way too much `into`/`to_string` with &str.
 pub const TOKEN_ID: &str = "0";
 
+// Media signed by the test signer key, any number of karts can be minted with it
+const TEST_CID: &str = "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm";
+const TEST_SIG: &str = "d41b344431e490b1d533d30f923a239f839fb120ece2dc5619fdb9a4b30cb7d51166a209afaae300cf5b77c2cf4477f42fa1740d9032114d8680e5f1644edf0f";
+const TEST_PUB_KEY: &str = "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+const MINT_DEPOSIT: &str = "0.2";
+
 /// Initialize simulator and return:
 /// * root: the root user, set as owner_id for NFT contract, owns a token with ID=1
 /// * nft: the NFT contract, callable with `call!` and `view!`
@@ -50,27 +58,12 @@ pub fn init() -> (
     );
 
     call!(
-        root,
-        nft.nft_mint(
-            TOKEN_ID.into(),
-            root.valid_account_id(),
-            TokenMetadata {
-                title: Some("Olympus Mons".into()),
-                description: Some("The tallest mountain in the charted solar system".into()),
-                media: None,
-                media_hash: None,
-                copies: Some(1u64),
-                issued_at: None,
-                expires_at: None,
-                starts_at: None,
-                updated_at: None,
-                extra: None,
-                reference: None,
-                reference_hash: None,
-            }
-        ),
-        deposit = 7000000000000000000000
-    );
+        nft.user_account,
+        nft.add_signer_key(TEST_PUB_KEY.to_string())
+    )
+    .assert_success();
+
+    helper_mint(TOKEN_ID.into(), &root, &nft, "Olympus Mons".into());
 
     let alice = root.create_user("alice".to_string(), to_yocto("100"));
 
@@ -101,29 +94,22 @@ pub fn helper_mint(
     token_id: TokenId,
     root: &UserAccount,
     nft: &ContractAccount<NftContract>,
-    title: String,
-    desc: String,
+    name: String,
 ) {
+    let media_hash = Base64VecU8::from(parse_cid(TEST_CID).unwrap().digest.to_vec());
     call!(
         root,
         nft.nft_mint(
             token_id,
             root.valid_account_id(),
-            TokenMetadata {
-                title: Some(title),
-                description: Some(desc),
-                media: None,
-                media_hash: None,
-                copies: Some(1u64),
-                issued_at: None,
-                expires_at: None,
-                starts_at: None,
-                updated_at: None,
-                extra: None,
-                reference: None,
-                reference_hash: None,
-            }
+            name,
+            NearKart::new(),
+            TEST_CID.to_string(),
+            media_hash,
+            TEST_SIG.to_string(),
+            TEST_PUB_KEY.to_string()
         ),
-        deposit = 7000000000000000000000
-    );
+        deposit = to_yocto(MINT_DEPOSIT)
+    )
+    .assert_success();
 }
@@ -0,0 +1,88 @@
+use alloc::format;
+use alloc::string::String;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// Token ids of NPC opponents start with this, e.g. "npc:3". NPCs are not NFTs.
+pub const NPC_TOKEN_PREFIX: &str = "npc:";
+
+/// Result of `game_simple_battle`.
+///
+/// `winner` is 0 when the home kart won, `battle` seeds the replay and `prize` is the unlocked
+/// decal or "0". `away_token_id` is an NPC token id when no human opponent was found.
+#[derive(Default, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SimpleBattle {
+    pub home_token_id: String,
+    pub away_token_id: String,
+    pub winner: u8,
+    pub battle: u32,
+    pub prize: String,
+    pub extra: String,
+}
+
+impl SimpleBattle {
+    pub fn is_npc_battle(&self) -> bool {
+        return npc_id_from_token_id(&self.away_token_id).is_some();
+    }
+}
+
+pub fn npc_token_id(npc_id: u64) -> String {
+    format!("{}{}", NPC_TOKEN_PREFIX, npc_id)
+}
+
+/// NPC id of an NPC token id, `None` for any other token id.
+pub fn npc_id_from_token_id(token_id: &str) -> Option<u64> {
+    return token_id.strip_prefix(NPC_TOKEN_PREFIX)?.parse().ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn battle() -> SimpleBattle {
+        SimpleBattle {
+            home_token_id: "megakart".into(),
+            away_token_id: "fluffykart".into(),
+            winner: 1,
+            battle: 1234,
+            prize: "0".into(),
+            extra: "".into(),
+        }
+    }
+
+    #[test]
+    fn test_borsh_round_trip() {
+        let bytes = battle().try_to_vec().unwrap();
+        let decoded = SimpleBattle::try_from_slice(&bytes).unwrap();
+
+        assert_eq!(decoded.away_token_id, "fluffykart");
+        assert_eq!(decoded.winner, 1);
+        assert_eq!(decoded.battle, 1234);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = serde_json::to_string(&battle()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"home_token_id":"megakart","away_token_id":"fluffykart","winner":1,"battle":1234,"prize":"0","extra":""}"#
+        );
+
+        let decoded: SimpleBattle = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.home_token_id, "megakart");
+    }
+
+    #[test]
+    fn test_npc_token_ids() {
+        assert_eq!(npc_token_id(3), "npc:3");
+        assert_eq!(npc_id_from_token_id("npc:3"), Some(3));
+        assert_eq!(npc_id_from_token_id("npc:"), None);
+        assert_eq!(npc_id_from_token_id("fluffykart"), None);
+
+        let mut battle = battle();
+        assert!(!battle.is_npc_battle());
+        battle.away_token_id = npc_token_id(0);
+        assert!(battle.is_npc_battle());
+    }
+}
@@ -0,0 +1,14 @@
+/*
+Print the JSON Schema of the shared NEAR Karts types.
+
+USAGE:
+  cargo run -p nearkarts-types --features schema --bin nearkarts-schema > schema.json
+*/
+use nearkarts_types::schema::json_schemas;
+
+fn main() {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json_schemas()).expect("schemas serialize to json")
+    );
+}
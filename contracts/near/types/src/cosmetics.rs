@@ -0,0 +1,117 @@
+use crate::error::KartError;
+use crate::kart::NearKart;
+
+/// A named set of paint colors, `0xRRGGBB`.
+pub struct Palette {
+    pub name: &'static str,
+    pub colors: &'static [u32],
+}
+
+/// Palettes karts can paint `color1` and `color2` from, by index.
+///
+/// The standard palette is free, the others are unlocked per kart. Never reorder, karts store
+/// the indexes they unlocked.
+pub const PALETTES: [Palette; 4] = [
+    Palette {
+        name: "standard",
+        colors: &[
+            0x000000, 0xffffff, 0x444444, 0x888888, 0xff0000, 0x00ff00, 0x0000ff, 0xffff00,
+        ],
+    },
+    Palette {
+        name: "neon",
+        colors: &[0xff00ff, 0x00ffff, 0x39ff14, 0xff6ec7, 0xfff000],
+    },
+    Palette {
+        name: "pastel",
+        colors: &[0xffd1dc, 0xaec6cf, 0x77dd77, 0xfdfd96, 0xcdb4db],
+    },
+    Palette {
+        name: "sunset",
+        colors: &[0xff4500, 0xff8c00, 0xffb347, 0x8b0000, 0x4b0082],
+    },
+];
+
+/// Paint finishes by index, the finish a kart uses is `ex1`. Standard paint is free, the
+/// premium finishes are bought per kart.
+pub const FINISHES: [&str; 4] = ["standard", "metallic", "pearl", "animated"];
+
+pub const STANDARD_PALETTE: u8 = 0;
+pub const STANDARD_FINISH: u8 = 0;
+pub const NUM_PALETTES: u8 = PALETTES.len() as u8;
+pub const NUM_FINISHES: u8 = FINISHES.len() as u8;
+
+/// The color is in the standard palette or one the kart unlocked.
+///
+/// Karts painted before palettes existed keep their paint, a color equal to `prev_color` is
+/// always allowed.
+pub fn is_color_unlocked(color: u32, prev_color: u32, nk_prev: &NearKart) -> bool {
+    if color == prev_color {
+        return true;
+    }
+
+    return PALETTES
+        .iter()
+        .enumerate()
+        .any(|(i, palette)| palette.colors.contains(&color) && nk_prev.has_palette(i as u8));
+}
+
+/// Paint of a kart being minted. It has only the standard palette and finish, and no previous
+/// colors to keep.
+pub fn validate_mint_paint(nk: &NearKart) -> Result<(), KartError> {
+    let standard = &PALETTES[STANDARD_PALETTE as usize];
+    if !standard.colors.contains(&nk.color1) {
+        return Err(KartError::Color1NotUnlocked);
+    } else if !standard.colors.contains(&nk.color2) {
+        return Err(KartError::Color2NotUnlocked);
+    } else if nk.ex1 != STANDARD_FINISH {
+        return Err(KartError::FinishNotOwned);
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_colors_are_unique() {
+        let colors: alloc::vec::Vec<u32> = PALETTES
+            .iter()
+            .flat_map(|palette| palette.colors.iter().copied())
+            .collect();
+        for (i, a) in colors.iter().enumerate() {
+            assert!(*a <= 0xffffff);
+            assert!(!colors[i + 1..].contains(a), "{:06x}", a);
+        }
+    }
+
+    #[test]
+    fn test_is_color_unlocked() {
+        let mut nk = NearKart::new();
+        nk.color1 = 0x123456;
+
+        assert!(is_color_unlocked(0xff0000, nk.color1, &nk));
+        assert!(is_color_unlocked(0x123456, nk.color1, &nk));
+        assert!(!is_color_unlocked(0xff00ff, nk.color1, &nk));
+
+        nk.unlock_palette(1);
+        assert!(is_color_unlocked(0xff00ff, nk.color1, &nk));
+    }
+
+    #[test]
+    fn test_validate_mint_paint() {
+        let mut nk = NearKart::new();
+        nk.color1 = 0x444444;
+        assert_eq!(validate_mint_paint(&nk), Ok(()));
+
+        // Unlocks requested on mint don't count
+        nk.unlock_palette(1);
+        nk.color2 = 0xff00ff;
+        assert_eq!(validate_mint_paint(&nk), Err(KartError::Color2NotUnlocked));
+
+        nk.color2 = 0;
+        nk.ex1 = 1;
+        assert_eq!(validate_mint_paint(&nk), Err(KartError::FinishNotOwned));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::cosmetics::*;
+use crate::error::KartError;
+use crate::kart::*;
+
+// Left / right slot values from here on are shields, below are weapons
+pub const SHIELD_START_INDEX: u8 = 200;
+
+/// Highest weapon, shield, skin or transport index a kart of this level may use.
+pub fn max_part_index_for_level(level: u32) -> u8 {
+    // let weapon_index = ((level as u8 / 5) + 1) * 5;
+    let mut weapon_index = level.saturating_add(2);
+    if weapon_index > 255 {
+        weapon_index = 255;
+    }
+    return weapon_index as u8;
+}
+
+/// Empty slots and the NEAR decal everyone gets are always allowed, anything else must be
+/// in the CSV list of unlocked decals in `extra1`.
+pub fn is_decal_unlocked(decal: &str, nk_prev: &NearKart) -> bool {
+    if decal == "" || decal == "0" || decal == "7" {
+        return true;
+    }
+
+    return nk_prev.extra1.split(",").any(|unlocked| unlocked == decal);
+}
+
+/// Check a requested kart configuration against the kart it replaces.
+///
+/// The error is the code the contract panics with, so off-chain tools can reject a config
+/// before a signature or a transaction is spent on it.
+pub fn validate_equip(nk: &NearKart, nk_prev: &NearKart) -> Result<(), KartError> {
+    let max_index = max_part_index_for_level(nk.level);
+
+    let mut weapon_or_shield_index_left = nk.left;
+    let mut is_shield_left = false;
+    if nk.left >= SHIELD_START_INDEX {
+        weapon_or_shield_index_left -= SHIELD_START_INDEX;
+        is_shield_left = true;
+    }
+
+    let mut weapon_or_shield_index_right = nk.right;
+    let mut is_shield_right = false;
+    if nk.right >= SHIELD_START_INDEX {
+        is_shield_right = true;
+        weapon_or_shield_index_right -= SHIELD_START_INDEX;
+    }
+
+    if nk.front > NUM_WEAPONS_MELEE - 1 {
+        return Err(KartError::FrontWeaponIndexTooHigh);
+    } else if nk.transport > NUM_TRANSPORTS - 1 {
+        return Err(KartError::TransportIndexTooHigh);
+    } else if nk.skin > NUM_SKINS - 1 {
+        return Err(KartError::SkinIndexTooHigh);
+    }
+
+    if is_shield_left {
+        if weapon_or_shield_index_left > NUM_SHIELDS - 1 {
+            return Err(KartError::ShieldLeftIndexTooHigh);
+        }
+    } else if weapon_or_shield_index_left > NUM_WEAPONS - 1 {
+        return Err(KartError::WeaponLeftIndexTooHigh);
+    }
+    if is_shield_right {
+        if weapon_or_shield_index_right > NUM_SHIELDS - 1 {
+            return Err(KartError::ShieldRightIndexTooHigh);
+        }
+    } else if weapon_or_shield_index_right > NUM_WEAPONS - 1 {
+        return Err(KartError::WeaponRightIndexTooHigh);
+    }
+
+    if nk.front > max_index {
+        return Err(KartError::LevelTooLowForFrontWeapon);
+    } else if weapon_or_shield_index_left > max_index {
+        return Err(KartError::LevelTooLowForLeftWeapon);
+    } else if weapon_or_shield_index_right > max_index {
+        return Err(KartError::LevelTooLowForRightWeapon);
+    } else if nk.transport > max_index {
+        return Err(KartError::LevelTooLowForTransport);
+    } else if nk.skin > max_index {
+        return Err(KartError::LevelTooLowForSkin);
+    }
+
+    if !is_decal_unlocked(&nk.decal1, nk_prev) {
+        return Err(KartError::DecalFrontNotUnlocked);
+    } else if !is_decal_unlocked(&nk.decal2, nk_prev) {
+        return Err(KartError::DecalLeftNotUnlocked);
+    } else if !is_decal_unlocked(&nk.decal3, nk_prev) {
+        return Err(KartError::DecalRightNotUnlocked);
+    }
+
+    if !is_color_unlocked(nk.color1, nk_prev.color1, nk_prev) {
+        return Err(KartError::Color1NotUnlocked);
+    } else if !is_color_unlocked(nk.color2, nk_prev.color2, nk_prev) {
+        return Err(KartError::Color2NotUnlocked);
+    } else if nk.ex1 > NUM_FINISHES - 1 {
+        return Err(KartError::FinishIndexTooHigh);
+    } else if !nk_prev.has_finish(nk.ex1) {
+        return Err(KartError::FinishNotOwned);
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    fn minted_kart() -> NearKart {
+        let mut nk = NearKart::new();
+        nk.reset_for_mint();
+        nk
+    }
+
+    #[test]
+    fn test_minted_kart_is_valid() {
+        let nk = minted_kart();
+        assert_eq!(validate_equip(&nk, &nk), Ok(()));
+    }
+
+    #[test]
+    fn test_max_part_index_for_level() {
+        assert_eq!(max_part_index_for_level(1), 3);
+        assert_eq!(max_part_index_for_level(300), 255);
+        assert_eq!(max_part_index_for_level(u32::MAX), 255);
+    }
+
+    #[test]
+    fn test_shield_index() {
+        let mut nk = minted_kart();
+        nk.left = SHIELD_START_INDEX + 1;
+        assert_eq!(validate_equip(&nk, &nk), Ok(()));
+
+        nk.left = SHIELD_START_INDEX + NUM_SHIELDS;
+        assert_eq!(
+            validate_equip(&nk, &nk),
+            Err(KartError::ShieldLeftIndexTooHigh)
+        );
+    }
+
+    #[test]
+    fn test_level_too_low() {
+        let mut nk = minted_kart();
+        nk.right = 5;
+        assert_eq!(
+            validate_equip(&nk, &nk),
+            Err(KartError::LevelTooLowForRightWeapon)
+        );
+
+        nk.level = 3;
+        assert_eq!(validate_equip(&nk, &nk), Ok(()));
+    }
+
+    #[test]
+    fn test_decal_must_be_unlocked_on_previous_kart() {
+        let prev = minted_kart();
+        let mut nk = prev.clone();
+        nk.decal2 = String::from("3");
+        assert_eq!(
+            validate_equip(&nk, &prev),
+            Err(KartError::DecalLeftNotUnlocked)
+        );
+
+        let mut prev = prev;
+        prev.unlock_decal(&String::from("3"));
+        assert_eq!(validate_equip(&nk, &prev), Ok(()));
+    }
+
+    #[test]
+    fn test_decal_prefix_is_not_unlocked() {
+        let mut prev = minted_kart();
+        prev.unlock_decal(&String::from("12"));
+        let mut nk = prev.clone();
+        nk.decal1 = String::from("1");
+        assert_eq!(
+            validate_equip(&nk, &prev),
+            Err(KartError::DecalFrontNotUnlocked)
+        );
+    }
+
+    #[test]
+    fn test_colors_must_be_unlocked_on_previous_kart() {
+        let prev = minted_kart();
+        let mut nk = prev.clone();
+        nk.color1 = 0xff0000;
+        assert_eq!(validate_equip(&nk, &prev), Ok(()));
+
+        nk.color2 = 0xff00ff;
+        assert_eq!(
+            validate_equip(&nk, &prev),
+            Err(KartError::Color2NotUnlocked)
+        );
+
+        let mut prev = prev;
+        prev.unlock_palette(1);
+        assert_eq!(validate_equip(&nk, &prev), Ok(()));
+    }
+
+    #[test]
+    fn test_legacy_colors_are_kept() {
+        let mut prev = minted_kart();
+        prev.color1 = 0x123456;
+        let mut nk = prev.clone();
+        assert_eq!(validate_equip(&nk, &prev), Ok(()));
+
+        nk.color2 = 0x123456;
+        assert_eq!(
+            validate_equip(&nk, &prev),
+            Err(KartError::Color2NotUnlocked)
+        );
+    }
+
+    #[test]
+    fn test_finish_must_be_owned() {
+        let mut prev = minted_kart();
+        let mut nk = prev.clone();
+        nk.ex1 = NUM_FINISHES;
+        assert_eq!(
+            validate_equip(&nk, &prev),
+            Err(KartError::FinishIndexTooHigh)
+        );
+
+        nk.ex1 = 1;
+        assert_eq!(validate_equip(&nk, &prev), Err(KartError::FinishNotOwned));
+
+        prev.unlock_finish(1);
+        assert_eq!(validate_equip(&nk, &prev), Ok(()));
+    }
+}
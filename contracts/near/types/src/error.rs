@@ -0,0 +1,329 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of every contract panic message, `KART_ERR:<code>:<message>`.
+pub const KART_ERR_PREFIX: &str = "KART_ERR";
+
+macro_rules! kart_errors {
+    ($($variant:ident = $code:expr, $id:expr, $description:expr;)*) => {
+        /// Every failure the contract reports.
+        ///
+        /// `code` and `id` are stable, a code is never reused for a different error. `id` is also
+        /// the text key clients look up a localized message by.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum KartError {
+            $($variant,)*
+        }
+
+        impl KartError {
+            pub const ALL: &'static [KartError] = &[$(KartError::$variant,)*];
+
+            pub fn code(&self) -> u32 {
+                match self {
+                    $(KartError::$variant => $code,)*
+                }
+            }
+
+            pub fn id(&self) -> &'static str {
+                match self {
+                    $(KartError::$variant => $id,)*
+                }
+            }
+
+            /// English description, for logs and the error catalog.
+            pub fn description(&self) -> &'static str {
+                match self {
+                    $(KartError::$variant => $description,)*
+                }
+            }
+        }
+    };
+}
+
+// Codes are grouped by area: 1xx tokens and access, 2xx kart config, 3xx media and signatures,
+// 4xx payments, 5xx battles, 6xx seasons, 7xx loot, 8xx relayed actions, 9xx guilds,
+// 10xx raids
+kart_errors! {
+    TokenNotFound = 100, "error_token_not_found", "No kart has this token id";
+    TokenIdNotUnique = 101, "error_token_id_not_unique", "The token id is already taken";
+    MetadataRequired = 102, "error_metadata_required", "Token metadata is required";
+    NotTokenOwner = 103, "error_not_token_owner", "Caller must be the token owner";
+    NotContractOwner = 104, "error_not_contract_owner", "Caller must be relative of contract owner";
+    TokenIndexOutOfRange = 105, "error_token_index_out_of_range", "No kart at this index";
+    NotTokenOwnerOrOperator = 106, "error_not_token_owner_or_operator",
+        "Caller must be the token owner or an approved operator";
+    OperatorIsOwner = 107, "error_operator_is_owner", "The owner can't be an operator of own kart";
+    SessionKeyExists = 108, "error_session_key_exists", "The session key is already registered";
+    SessionKeyNotFound = 109, "error_session_key_not_found", "No session has this key";
+    SessionKeyExpired = 110, "error_session_key_expired", "The session key has expired";
+    NotSessionKeyAccount = 111, "error_not_session_key_account",
+        "Caller must be the account the session key belongs to";
+    TokenIdReserved = 112, "error_token_id_reserved", "Token ids starting with npc: are reserved";
+    ContractStateNotFound = 113, "error_contract_state_not_found",
+        "There is no contract state to migrate";
+    TokensIndexing = 114, "error_tokens_indexing",
+        "Karts minted before the upgrade are being indexed, try again later";
+
+    InvalidKartData = 200, "error_invalid_kart_data", "The stored kart config can't be decoded";
+    FrontWeaponIndexTooHigh = 201, "error_front_weapon_index_too_high", "Unknown front weapon";
+    TransportIndexTooHigh = 202, "error_transport_index_too_high", "Unknown transport";
+    SkinIndexTooHigh = 203, "error_skin_index_too_high", "Unknown skin";
+    ShieldLeftIndexTooHigh = 204, "error_shield_left_index_too_high", "Unknown left shield";
+    WeaponLeftIndexTooHigh = 205, "error_weapon_left_index_too_high", "Unknown left weapon";
+    ShieldRightIndexTooHigh = 206, "error_shield_right_index_too_high", "Unknown right shield";
+    WeaponRightIndexTooHigh = 207, "error_weapon_right_index_too_high", "Unknown right weapon";
+    LevelTooLowForFrontWeapon = 208, "error_level_not_high_enough_to_equip_front_weapon",
+        "Kart level is too low for the front weapon";
+    LevelTooLowForLeftWeapon = 209, "error_level_not_high_enough_to_equip_left_weapon",
+        "Kart level is too low for the left weapon";
+    LevelTooLowForRightWeapon = 210, "error_level_not_high_enough_to_equip_right_weapon",
+        "Kart level is too low for the right weapon";
+    LevelTooLowForTransport = 211, "error_level_not_high_enough_to_use_transport",
+        "Kart level is too low for the transport";
+    LevelTooLowForSkin = 212, "error_level_not_high_enough_to_use_skin",
+        "Kart level is too low for the skin";
+    DecalFrontNotUnlocked = 213, "error_decal_front_is_not_unlocked",
+        "The front decal is not unlocked";
+    DecalLeftNotUnlocked = 214, "error_decal_left_is_not_unlocked",
+        "The left decal is not unlocked";
+    DecalRightNotUnlocked = 215, "error_decal_right_is_not_unlocked",
+        "The right decal is not unlocked";
+    KartLocked = 216, "error_cannot_upgrade_while_kart_is_locked",
+        "The kart is locked until it levels up";
+    KartDoesNotNeedRepair = 217, "error_kart_does_not_need_repair", "The kart is fully repaired";
+    NoRepairKits = 218, "error_no_repair_kits", "The kart has no repair kits";
+    KartNameInvalid = 219, "error_kart_name_invalid",
+        "Kart name must be 3 to 24 letters, digits, spaces, - or _";
+    KartNameTaken = 220, "error_kart_name_taken", "Another kart has this name";
+    KartNameBlocked = 221, "error_kart_name_blocked", "The kart name contains a blocked word";
+    Color1NotUnlocked = 222, "error_color1_is_not_unlocked",
+        "The primary color is not in a palette the kart unlocked";
+    Color2NotUnlocked = 223, "error_color2_is_not_unlocked",
+        "The secondary color is not in a palette the kart unlocked";
+    FinishIndexTooHigh = 224, "error_finish_index_too_high", "Unknown paint finish";
+    FinishNotOwned = 225, "error_finish_is_not_owned", "The kart does not own this paint finish";
+    PaletteIndexTooHigh = 226, "error_palette_index_too_high", "Unknown palette";
+    PaletteAlreadyUnlocked = 227, "error_palette_already_unlocked",
+        "The kart already unlocked this palette";
+    FinishAlreadyOwned = 228, "error_finish_already_owned",
+        "The kart already owns this paint finish";
+    KartNamesBackfilling = 229, "error_kart_names_backfilling",
+        "Kart names are being migrated, try again later";
+    KartOutOfEnergy = 230, "error_kart_out_of_energy", "The kart has no energy left to battle";
+
+    InvalidCid = 300, "error_invalid_cid", "Media is not a valid IPFS CID";
+    UnsupportedCidCodec = 301, "error_unsupported_cid_codec", "Media CID codec is not supported";
+    UnsupportedCidHash = 302, "error_unsupported_cid_hash", "Media CID hash is not supported";
+    InvalidMediaHash = 303, "error_invalid_media_hash", "Media hash must be a sha256";
+    MediaHashMismatch = 304, "error_media_hash_mismatch", "Media hash does not match the CID";
+    PubKeyIsNotSigner = 305, "error_pubkey_is_not_signer", "Public key is not a registered signer";
+    SignatureVerificationFailed = 306, "error_signature_verification_failed",
+        "Signature does not match the media";
+    InvalidSignature = 307, "error_invalid_signature", "Signature is not a hex ed25519 signature";
+    InvalidPublicKey = 308, "error_invalid_public_key", "Public key is not a hex ed25519 key";
+
+    MintPaymentTooLow = 400, "error_mint_payment_too_low", "Attached deposit is below the mint price";
+    UpgradePaymentTooLow = 401, "error_upgrade_payment_too_low",
+        "Attached deposit is below the upgrade price";
+    RepairPaymentTooLow = 402, "error_repair_payment_too_low",
+        "Attached deposit is below the repair cost";
+    PriceZero = 403, "error_price_zero", "Mint and upgrade prices can't be zero";
+    WithdrawAmountZero = 404, "error_withdraw_amount_zero", "Withdraw amount can't be zero";
+    TreasuryBalanceTooLow = 405, "error_treasury_balance_too_low",
+        "Withdraw amount is above the treasury balance";
+    SessionPaymentTooLow = 406, "error_session_payment_too_low",
+        "Attached deposit is below the session key allowance";
+    SessionAllowanceZero = 407, "error_session_allowance_zero",
+        "Session key allowance can't be zero";
+    RenamePaymentTooLow = 408, "error_rename_payment_too_low",
+        "Attached deposit is below the rename price";
+    CosmeticPaymentTooLow = 409, "error_cosmetic_payment_too_low",
+        "Attached deposit is below the price of the palette or finish";
+
+    NoLastBattle = 500, "error_no_last_battle", "The account has not battled yet";
+    BattleNotFound = 501, "error_battle_not_found", "No battle has this id";
+    NpcNotFound = 502, "error_npc_not_found", "No NPC has this id";
+    NpcNameInvalid = 503, "error_npc_name_invalid", "NPC name must be 1 to 32 characters";
+
+    SeasonEndBeforeStart = 600, "error_season_end_before_start", "Season must end after it starts";
+    CurrentSeasonNotEnded = 601, "error_current_season_not_ended", "The current season has not ended";
+    SeasonOverlapsPrevious = 602, "error_season_overlaps_previous",
+        "Season starts before the previous one ends";
+    SeasonNotFound = 603, "error_season_not_found", "No season has this id";
+    KartNotRankedInSeason = 604, "error_kart_not_ranked_in_season",
+        "The kart has no rank in this season";
+    NoSeasonRewardForRank = 605, "error_no_season_reward_for_rank", "No reward for this rank";
+    SeasonRewardAlreadyClaimed = 606, "error_season_reward_already_claimed",
+        "The season reward was already claimed";
+    SeasonRewardDecalInvalid = 607, "error_season_reward_decal_invalid",
+        "Season reward decal is not a decal";
+    SeasonRewardRankInvalid = 608, "error_season_reward_rank_invalid",
+        "Season reward ranks must be above zero";
+    SeasonNotArchived = 609, "error_season_not_archived",
+        "The standings of the current season are not archived yet";
+
+    LootTableEmpty = 700, "error_loot_table_empty", "The loot table has no entries";
+    LootEntryWeightZero = 701, "error_loot_entry_weight_zero", "Loot entry weights can't be zero";
+    LootEntryDecalInvalid = 702, "error_loot_entry_decal_invalid", "Loot entry decal is not a decal";
+    LootPrizeChanceInvalid = 703, "error_loot_prize_chance_invalid",
+        "Loot prize chance must be a percentage";
+    LootPityRarityHasNoEntries = 704, "error_loot_pity_rarity_has_no_entries",
+        "No loot entry has the pity rarity";
+
+    RelayerNotAllowed = 800, "error_relayer_not_allowed", "Caller is not an allowed relayer";
+    RelayKeyNotRegistered = 801, "error_relay_key_not_registered",
+        "Public key is not registered to the signing account";
+    InvalidRelayRequest = 802, "error_invalid_relay_request", "The relayed request can't be decoded";
+    RelayReceiverMismatch = 803, "error_relay_receiver_mismatch",
+        "The relayed request is for another contract";
+    RelayRequestExpired = 804, "error_relay_request_expired", "The relayed request has expired";
+    RelayNonceUsed = 805, "error_relay_nonce_used", "The relayed request nonce was already used";
+    RelayRateLimited = 806, "error_relay_rate_limited",
+        "The account relayed too many requests, try again later";
+    RelayPartsChanged = 807, "error_relay_parts_changed",
+        "A relayed configure can't change parts, upgrade the kart instead";
+
+    GuildNotFound = 900, "error_guild_not_found", "No guild has this id";
+    GuildNameInvalid = 901, "error_guild_name_invalid", "Guild name must be 1 to 32 characters";
+    GuildNameTaken = 902, "error_guild_name_taken", "Another guild has this name";
+    AlreadyInGuild = 903, "error_already_in_guild", "The account is already in a guild";
+    NotGuildMember = 904, "error_not_guild_member", "The account is not a member of the guild";
+    NotGuildOfficer = 905, "error_not_guild_officer", "Caller must be a guild officer";
+    NotGuildLeader = 906, "error_not_guild_leader", "Caller must be the guild leader";
+    GuildInviteNotFound = 907, "error_guild_invite_not_found", "The account was not invited";
+    GuildFull = 908, "error_guild_full", "The guild has no room for more members";
+    GuildLeaderCannotLeave = 909, "error_guild_leader_cannot_leave",
+        "The guild leader can't leave the guild";
+    KartInGuildRoster = 910, "error_kart_in_guild_roster", "The kart is already on a guild roster";
+    KartNotInGuildRoster = 911, "error_kart_not_in_guild_roster", "The kart is not on a guild roster";
+    GuildRosterFull = 912, "error_guild_roster_full", "The guild roster is full";
+    GuildRosterEmpty = 913, "error_guild_roster_empty", "Both guilds need karts on their roster";
+    GuildAmountZero = 914, "error_guild_amount_zero", "Guild treasury amounts can't be zero";
+    GuildTreasuryTooLow = 915, "error_guild_treasury_too_low",
+        "The amount is above the guild treasury";
+    GuildBestOfInvalid = 916, "error_guild_best_of_invalid",
+        "Team battles are best of an odd number of rounds, up to 9";
+    GuildChallengeSelf = 917, "error_guild_challenge_self", "A guild can't challenge itself";
+    GuildChallengeNotFound = 918, "error_guild_challenge_not_found", "No challenge has this id";
+    GuildChallengeNotOpen = 919, "error_guild_challenge_not_open",
+        "The challenge was already accepted or declined";
+    GuildProposalNotFound = 920, "error_guild_proposal_not_found", "No proposal has this id";
+    GuildProposalExecuted = 921, "error_guild_proposal_executed",
+        "The proposal was already paid out";
+
+    RaidNotFound = 1000, "error_raid_not_found", "No raid has this id";
+    RaidNameInvalid = 1001, "error_raid_name_invalid", "Boss name must be 1 to 32 characters";
+    RaidHpZero = 1002, "error_raid_hp_zero", "Boss HP can't be zero";
+    RaidEndBeforeStart = 1003, "error_raid_end_before_start", "Raid must end after it starts";
+    RaidNotStarted = 1004, "error_raid_not_started", "The raid has not started";
+    RaidAttackCooldown = 1005, "error_raid_attack_cooldown",
+        "The kart attacked this raid too recently";
+    RaidNotEnded = 1006, "error_raid_not_ended", "The raid has not ended";
+    RaidRewardAlreadyClaimed = 1007, "error_raid_reward_already_claimed",
+        "The raid reward was already claimed";
+    NoRaidReward = 1008, "error_no_raid_reward", "The account dealt no damage in this raid";
+    RaidEnded = 1009, "error_raid_ended", "The raid has ended";
+    RaidPoolOwed = 1010, "error_raid_pool_owed",
+        "Karts dealt damage in this raid, its pool is theirs";
+    RaidPoolEmpty = 1011, "error_raid_pool_empty", "The raid has no reward pool left";
+}
+
+impl KartError {
+    pub fn from_code(code: u32) -> Option<KartError> {
+        return KartError::ALL.iter().copied().find(|e| e.code() == code);
+    }
+
+    /// The message the contract panics with.
+    pub fn panic_message(&self) -> String {
+        return format!("{}:{}:{}", KART_ERR_PREFIX, self.code(), self.description());
+    }
+
+    /// Parse a contract panic message, or an execution error that contains one.
+    pub fn from_panic_message(message: &str) -> Option<KartError> {
+        let start = message.find(KART_ERR_PREFIX)? + KART_ERR_PREFIX.len() + 1;
+        let code = message.get(start..)?.split(':').next()?;
+        return KartError::from_code(code.parse().ok()?);
+    }
+}
+
+impl fmt::Display for KartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.panic_message())
+    }
+}
+
+/// One entry of `error_catalog`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ErrorInfo {
+    pub code: u32,
+    pub id: String,
+    pub description: String,
+}
+
+/// Every error with its codes, ordered by code.
+pub fn error_catalog() -> Vec<ErrorInfo> {
+    let mut catalog: Vec<ErrorInfo> = KartError::ALL
+        .iter()
+        .map(|e| ErrorInfo {
+            code: e.code(),
+            id: e.id().to_string(),
+            description: e.description().to_string(),
+        })
+        .collect();
+    catalog.sort_by_key(|info| info.code);
+    return catalog;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_and_ids_are_unique() {
+        let catalog = error_catalog();
+        for (i, a) in catalog.iter().enumerate() {
+            assert!(a.id.starts_with("error_"), "{}", a.id);
+            for b in catalog[i + 1..].iter() {
+                assert_ne!(a.code, b.code, "{} and {}", a.id, b.id);
+                assert_ne!(a.id, b.id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_codes_are_stable() {
+        // Clients depend on these, never renumber
+        assert_eq!(KartError::TokenNotFound.code(), 100);
+        assert_eq!(KartError::InvalidKartData.code(), 200);
+        assert_eq!(KartError::InvalidCid.code(), 300);
+        assert_eq!(KartError::MintPaymentTooLow.code(), 400);
+        assert_eq!(KartError::NoLastBattle.code(), 500);
+        assert_eq!(KartError::SeasonEndBeforeStart.code(), 600);
+        assert_eq!(KartError::LootTableEmpty.code(), 700);
+        assert_eq!(KartError::RelayerNotAllowed.code(), 800);
+        assert_eq!(KartError::GuildNotFound.code(), 900);
+        assert_eq!(KartError::RaidNotFound.code(), 1000);
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let e = KartError::PriceZero;
+        assert_eq!(
+            e.panic_message(),
+            "KART_ERR:403:Mint and upgrade prices can't be zero"
+        );
+        assert_eq!(
+            KartError::from_panic_message(&format!(
+                "Smart contract panicked: {}",
+                e.panic_message()
+            )),
+            Some(e)
+        );
+        assert_eq!(KartError::from_panic_message("KART_ERR:99999:nope"), None);
+        assert_eq!(KartError::from_panic_message("error_price_zero"), None);
+    }
+}
@@ -0,0 +1,591 @@
+/*
+NEP-297 events.
+NOTES:
+  - Every event is logged as `EVENT_JSON:` followed by `{"standard", "version", "event", "data"}`
+    where `data` is always an array.
+  - Token lifecycle events (`nft_mint`, `nft_transfer`, `nft_burn`) use the `nep171` standard so
+    wallets and marketplaces pick them up. Game events use the `nearkarts` standard.
+*/
+use crate::SimpleBattle;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// This is the name of the NFT standard we're using
+pub const NFT_STANDARD_NAME: &str = "nep171";
+pub const NFT_EVENT_VERSION: &str = "1.0.0";
+
+/// Standard name and version of the game's own events
+pub const NEARKARTS_STANDARD_NAME: &str = "nearkarts";
+pub const NEARKARTS_EVENT_VERSION: &str = "1.0.0";
+
+/// Enum that represents the data type of the EventLog.
+/// NFT events use the nep171 standard, game events the nearkarts standard.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    NftMint(Vec<NftMintLog>),
+    NftTransfer(Vec<NftTransferLog>),
+    NftBurn(Vec<NftBurnLog>),
+    Configure(Vec<KartMeta>),
+    Upgrade(Vec<KartMeta>),
+    LevelUp(Vec<LevelUpLog>),
+    Battle(Vec<SimpleBattle>),
+    Prize(Vec<PrizeLog>),
+    AchievementUnlocked(Vec<AchievementLog>),
+    DailyQuestCompleted(Vec<DailyQuestLog>),
+    OperatorApprove(Vec<OperatorLog>),
+    OperatorRevoke(Vec<OperatorLog>),
+    GuildBattle(Vec<GuildBattleLog>),
+    RaidAttack(Vec<RaidAttackLog>),
+    Defense(Vec<DefenseLog>),
+    KartRename(Vec<KartRenameLog>),
+    CosmeticUnlock(Vec<CosmeticUnlockLog>),
+}
+
+impl EventLogVariant {
+    fn standard(&self) -> (&'static str, &'static str) {
+        match self {
+            EventLogVariant::NftMint(_)
+            | EventLogVariant::NftTransfer(_)
+            | EventLogVariant::NftBurn(_) => (NFT_STANDARD_NAME, NFT_EVENT_VERSION),
+            _ => (NEARKARTS_STANDARD_NAME, NEARKARTS_EVENT_VERSION),
+        }
+    }
+}
+
+/// Interface to capture data about an event
+///
+/// Arguments:
+/// * `standard`: name of standard e.g. nep171
+/// * `version`: e.g. 1.0.0
+/// * `event`: associate event data
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+
+    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
+    #[serde(flatten)]
+    pub event: EventLogVariant,
+}
+
+impl EventLog {
+    /// Wrap the event with the standard and version it belongs to.
+    pub fn new(event: EventLogVariant) -> Self {
+        let (standard, version) = event.standard();
+        Self {
+            standard: standard.to_string(),
+            version: version.to_string(),
+            event: event,
+        }
+    }
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+/// An event log to capture token minting
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_ids`: ["1", "abc"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NftMintLog {
+    pub owner_id: String,
+    pub token_ids: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture token transfer
+///
+/// Arguments
+/// * `authorized_id`: approved account to transfer
+/// * `old_owner_id`: "owner.near"
+/// * `new_owner_id`: "receiver.near"
+/// * `token_ids`: ["1", "12345abc"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NftTransferLog {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<String>,
+
+    pub old_owner_id: String,
+    pub new_owner_id: String,
+    pub token_ids: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture token burning
+///
+/// Arguments
+/// * `owner_id`: owner of the burned tokens
+/// * `authorized_id`: approved account to burn
+/// * `token_ids`: ["1", "abc"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NftBurnLog {
+    pub owner_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<String>,
+
+    pub token_ids: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Name and media of a kart, logged when it is configured on mint or upgraded
+///
+/// Arguments
+/// * `token_id`: "megakart"
+/// * `name`: kart title
+/// * `media`: image CID
+/// * `reference`: unused, always empty
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct KartMeta {
+    pub token_id: String,
+    pub name: String,
+    pub media: String,
+    pub reference: String,
+}
+
+/// An event log to capture a kart gaining a level
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_id`: "megakart"
+/// * `level`: the new level
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LevelUpLog {
+    pub owner_id: String,
+    pub token_id: String,
+    pub level: u32,
+}
+
+/// An event log to capture a battle prize
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_id`: "megakart"
+/// * `decal`: unlocked decal, "0" if none
+/// * `repair_kits`: repair kits won
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PrizeLog {
+    pub owner_id: String,
+    pub token_id: String,
+    pub decal: String,
+    pub repair_kits: u32,
+}
+
+/// An event log to capture an achievement being unlocked
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_id`: "megakart"
+/// * `achievement_id`: "win_10"
+/// * `reward_repair_kits`: repair kits granted to the kart
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AchievementLog {
+    pub owner_id: String,
+    pub token_id: String,
+    pub achievement_id: String,
+    pub reward_repair_kits: u32,
+}
+
+/// An event log to capture a kart being renamed
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_id`: "megakart"
+/// * `old_name`: the title before, "Mega Kart"
+/// * `new_name`: "Turbo Kart"
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct KartRenameLog {
+    pub owner_id: String,
+    pub token_id: String,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+/// An event log to capture a palette or paint finish bought for a kart
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_id`: "megakart"
+/// * `kind`: "palette" or "finish"
+/// * `index`: index in `PALETTES` or `FINISHES`
+/// * `name`: "neon"
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CosmeticUnlockLog {
+    pub owner_id: String,
+    pub token_id: String,
+    pub kind: String,
+    pub index: u8,
+    pub name: String,
+}
+
+/// An event log to capture the daily quest being completed
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_id`: kart that completed the quest
+/// * `day`: days since the unix epoch
+/// * `reward_repair_kits`: repair kits granted to the kart
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DailyQuestLog {
+    pub owner_id: String,
+    pub token_id: String,
+    pub day: u64,
+    pub reward_repair_kits: u32,
+}
+
+/// An event log to capture an operator being approved for, or revoked from, a kart
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_id`: "megakart"
+/// * `operator_id`: "bot.near"
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct OperatorLog {
+    pub owner_id: String,
+    pub token_id: String,
+    pub operator_id: String,
+}
+
+/// An event log to capture a resolved guild team battle
+///
+/// Arguments
+/// * `challenge_id`: id of the accepted challenge
+/// * `challenger_guild_id`, `defender_guild_id`, `winner_guild_id`: guild ids
+/// * `wager`: yoctoNEAR staked by each guild, as a base-10 string
+/// * `rounds`: every round in order, home is the challenger's kart
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GuildBattleLog {
+    pub challenge_id: u64,
+    pub challenger_guild_id: u64,
+    pub defender_guild_id: u64,
+    pub winner_guild_id: u64,
+    pub wager: String,
+    pub rounds: Vec<SimpleBattle>,
+}
+
+/// An event log to capture a kart attacking a raid boss
+///
+/// Arguments
+/// * `raid_id`: id of the raid
+/// * `owner_id`: "account.near", credited with the damage
+/// * `token_id`: "megakart"
+/// * `damage`: HP taken off the boss
+/// * `boss_hp`: HP the boss has left
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RaidAttackLog {
+    pub raid_id: u64,
+    pub owner_id: String,
+    pub token_id: String,
+    pub damage: u64,
+    pub boss_hp: u64,
+}
+
+/// An event log to capture a kart being attacked by another account's kart, for its owner
+///
+/// Arguments
+/// * `owner_id`: "account.near", owner of the defending kart
+/// * `token_id`: "megakart", the defending kart
+/// * `attacker_token_id`: "fluffykart"
+/// * `battle_id`: id of the battle
+/// * `defended`: the defending kart won
+/// * `reward_repair_kits`: repair kits the defending kart got
+/// * `attacker_energy_lost`: energy the attacking kart lost, 0 when it won
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DefenseLog {
+    pub owner_id: String,
+    pub token_id: String,
+    pub attacker_token_id: String,
+    pub battle_id: u64,
+    pub defended: bool,
+    pub reward_repair_kits: u32,
+    pub attacker_energy_lost: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_json(event: EventLogVariant) -> String {
+        EventLog::new(event).to_string()
+    }
+
+    #[test]
+    fn test_nft_mint_event() {
+        let json = event_json(EventLogVariant::NftMint(vec![NftMintLog {
+            owner_id: "alice.near".to_string(),
+            token_ids: vec!["megakart".to_string()],
+            memo: None,
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"alice.near","token_ids":["megakart"]}]}"#
+        );
+    }
+
+    #[test]
+    fn test_nft_transfer_event() {
+        let json = event_json(EventLogVariant::NftTransfer(vec![NftTransferLog {
+            authorized_id: Some("market.near".to_string()),
+            old_owner_id: "alice.near".to_string(),
+            new_owner_id: "bob.near".to_string(),
+            token_ids: vec!["megakart".to_string()],
+            memo: Some("gg".to_string()),
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"authorized_id":"market.near","old_owner_id":"alice.near","new_owner_id":"bob.near","token_ids":["megakart"],"memo":"gg"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_nft_burn_event() {
+        let json = event_json(EventLogVariant::NftBurn(vec![NftBurnLog {
+            owner_id: "alice.near".to_string(),
+            authorized_id: None,
+            token_ids: vec!["megakart".to_string()],
+            memo: None,
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"alice.near","token_ids":["megakart"]}]}"#
+        );
+    }
+
+    #[test]
+    fn test_configure_event() {
+        let json = event_json(EventLogVariant::Configure(vec![KartMeta {
+            token_id: "megakart".to_string(),
+            name: "MegaKart".to_string(),
+            media: "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm".to_string(),
+            reference: "".to_string(),
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"configure","data":[{"token_id":"megakart","name":"MegaKart","media":"bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm","reference":""}]}"#
+        );
+    }
+
+    #[test]
+    fn test_upgrade_event() {
+        let json = event_json(EventLogVariant::Upgrade(vec![KartMeta {
+            token_id: "megakart".to_string(),
+            name: "MegaKart".to_string(),
+            media: "bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm".to_string(),
+            reference: "".to_string(),
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"upgrade","data":[{"token_id":"megakart","name":"MegaKart","media":"bafkreic6ngsuiw43wzwrp6ocvd5zpddyac55ll6pbkhuqlwo7zft2g6bcm","reference":""}]}"#
+        );
+    }
+
+    #[test]
+    fn test_level_up_event() {
+        let json = event_json(EventLogVariant::LevelUp(vec![LevelUpLog {
+            owner_id: "alice.near".to_string(),
+            token_id: "megakart".to_string(),
+            level: 5,
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"level_up","data":[{"owner_id":"alice.near","token_id":"megakart","level":5}]}"#
+        );
+    }
+
+    #[test]
+    fn test_battle_event() {
+        let json = event_json(EventLogVariant::Battle(vec![SimpleBattle {
+            home_token_id: "megakart".to_string(),
+            away_token_id: "fluffykart".to_string(),
+            winner: 0,
+            battle: 1234,
+            prize: "3".to_string(),
+            extra: "".to_string(),
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"battle","data":[{"home_token_id":"megakart","away_token_id":"fluffykart","winner":0,"battle":1234,"prize":"3","extra":""}]}"#
+        );
+    }
+
+    #[test]
+    fn test_prize_event() {
+        let json = event_json(EventLogVariant::Prize(vec![PrizeLog {
+            owner_id: "alice.near".to_string(),
+            token_id: "megakart".to_string(),
+            decal: "0".to_string(),
+            repair_kits: 1,
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"prize","data":[{"owner_id":"alice.near","token_id":"megakart","decal":"0","repair_kits":1}]}"#
+        );
+    }
+
+    #[test]
+    fn test_achievement_unlocked_event() {
+        let json = event_json(EventLogVariant::AchievementUnlocked(vec![AchievementLog {
+            owner_id: "alice.near".to_string(),
+            token_id: "megakart".to_string(),
+            achievement_id: "win_10".to_string(),
+            reward_repair_kits: 1,
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"achievement_unlocked","data":[{"owner_id":"alice.near","token_id":"megakart","achievement_id":"win_10","reward_repair_kits":1}]}"#
+        );
+    }
+
+    #[test]
+    fn test_daily_quest_completed_event() {
+        let json = event_json(EventLogVariant::DailyQuestCompleted(vec![DailyQuestLog {
+            owner_id: "alice.near".to_string(),
+            token_id: "megakart".to_string(),
+            day: 19000,
+            reward_repair_kits: 1,
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"daily_quest_completed","data":[{"owner_id":"alice.near","token_id":"megakart","day":19000,"reward_repair_kits":1}]}"#
+        );
+    }
+
+    #[test]
+    fn test_operator_events() {
+        let operator_log = || OperatorLog {
+            owner_id: "alice.near".to_string(),
+            token_id: "megakart".to_string(),
+            operator_id: "bot.near".to_string(),
+        };
+        assert_eq!(
+            event_json(EventLogVariant::OperatorApprove(vec![operator_log()])),
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"operator_approve","data":[{"owner_id":"alice.near","token_id":"megakart","operator_id":"bot.near"}]}"#
+        );
+        assert_eq!(
+            event_json(EventLogVariant::OperatorRevoke(vec![operator_log()])),
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"operator_revoke","data":[{"owner_id":"alice.near","token_id":"megakart","operator_id":"bot.near"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_guild_battle_event() {
+        let json = event_json(EventLogVariant::GuildBattle(vec![GuildBattleLog {
+            challenge_id: 3,
+            challenger_guild_id: 0,
+            defender_guild_id: 1,
+            winner_guild_id: 1,
+            wager: "100".to_string(),
+            rounds: vec![SimpleBattle {
+                home_token_id: "megakart".to_string(),
+                away_token_id: "minikart".to_string(),
+                winner: 1,
+                battle: 7,
+                prize: "0".to_string(),
+                extra: "guild".to_string(),
+            }],
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"guild_battle","data":[{"challenge_id":3,"challenger_guild_id":0,"defender_guild_id":1,"winner_guild_id":1,"wager":"100","rounds":[{"home_token_id":"megakart","away_token_id":"minikart","winner":1,"battle":7,"prize":"0","extra":"guild"}]}]}"#
+        );
+    }
+
+    #[test]
+    fn test_raid_attack_event() {
+        let json = event_json(EventLogVariant::RaidAttack(vec![RaidAttackLog {
+            raid_id: 2,
+            owner_id: "alice.near".to_string(),
+            token_id: "megakart".to_string(),
+            damage: 42,
+            boss_hp: 958,
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"raid_attack","data":[{"raid_id":2,"owner_id":"alice.near","token_id":"megakart","damage":42,"boss_hp":958}]}"#
+        );
+    }
+
+    #[test]
+    fn test_defense_event() {
+        let json = event_json(EventLogVariant::Defense(vec![DefenseLog {
+            owner_id: "bob.near".to_string(),
+            token_id: "fluffykart".to_string(),
+            attacker_token_id: "megakart".to_string(),
+            battle_id: 12,
+            defended: true,
+            reward_repair_kits: 1,
+            attacker_energy_lost: 2,
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"defense","data":[{"owner_id":"bob.near","token_id":"fluffykart","attacker_token_id":"megakart","battle_id":12,"defended":true,"reward_repair_kits":1,"attacker_energy_lost":2}]}"#
+        );
+    }
+
+    #[test]
+    fn test_kart_rename_event() {
+        let json = event_json(EventLogVariant::KartRename(vec![KartRenameLog {
+            owner_id: "alice.near".to_string(),
+            token_id: "megakart".to_string(),
+            old_name: "Mega Kart".to_string(),
+            new_name: "Turbo Kart".to_string(),
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"kart_rename","data":[{"owner_id":"alice.near","token_id":"megakart","old_name":"Mega Kart","new_name":"Turbo Kart"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_cosmetic_unlock_event() {
+        let json = event_json(EventLogVariant::CosmeticUnlock(vec![CosmeticUnlockLog {
+            owner_id: "alice.near".to_string(),
+            token_id: "megakart".to_string(),
+            kind: "finish".to_string(),
+            index: 1,
+            name: "metallic".to_string(),
+        }]));
+        assert_eq!(
+            json,
+            r#"EVENT_JSON:{"standard":"nearkarts","version":"1.0.0","event":"cosmetic_unlock","data":[{"owner_id":"alice.near","token_id":"megakart","kind":"finish","index":1,"name":"metallic"}]}"#
+        );
+    }
+}
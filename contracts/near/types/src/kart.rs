@@ -0,0 +1,472 @@
+use crate::cosmetics::{STANDARD_FINISH, STANDARD_PALETTE};
+#[cfg(feature = "msgpack")]
+use crate::error::KartError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::cmp;
+use serde::{Deserialize, Serialize};
+
+pub const NUM_DECALS: u32 = 7;
+pub const NUM_WEAPONS: u8 = 6;
+pub const NUM_WEAPONS_MELEE: u8 = 5;
+pub const NUM_SHIELDS: u8 = 2;
+pub const NUM_SKINS: u8 = 4;
+pub const NUM_TRANSPORTS: u8 = 3;
+
+pub const MAX_DURABILITY: u8 = 100;
+pub const WEAPON_WEAR: u8 = 2;
+// Wear per battle by skin / transport index, better parts last longer
+pub const SKIN_WEAR: [u8; NUM_SKINS as usize] = [4, 3, 2, 1];
+pub const TRANSPORT_WEAR: [u8; NUM_TRANSPORTS as usize] = [3, 2, 1];
+
+// Kart configuration is serialized and extra field of the NFT metadata
+//
+// It is serialized in Rust MessagePack format.
+#[derive(Default, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NearKart {
+    pub version: u8,
+    pub level: u32,
+    pub left: u8,
+    pub right: u8,
+    pub top: u8,
+    pub front: u8,
+    pub skin: u8,
+    pub transport: u8,
+    pub color1: u32,
+    pub color2: u32,
+    pub ex1: u8,
+    pub ex2: u32,
+    pub locked: bool,
+    pub decal1: String,
+    pub decal2: String,
+    pub decal3: String,
+    pub extra1: String,
+    pub extra2: String,
+    pub extra3: String,
+    // Durability of each part, fields are appended so karts minted before
+    // durability existed deserialize as fully repaired
+    #[serde(default = "full_durability")]
+    pub dur_left: u8,
+    #[serde(default = "full_durability")]
+    pub dur_right: u8,
+    #[serde(default = "full_durability")]
+    pub dur_front: u8,
+    #[serde(default = "full_durability")]
+    pub dur_skin: u8,
+    #[serde(default = "full_durability")]
+    pub dur_transport: u8,
+    #[serde(default)]
+    pub repair_kits: u32,
+}
+
+fn full_durability() -> u8 {
+    MAX_DURABILITY
+}
+
+impl NearKart {
+    pub fn new() -> Self {
+        let mut kart = Self::default();
+        kart.level = 1;
+        kart.repair();
+        return kart;
+    }
+
+    /// Initialize any fields the user is not allowed to set on mint.
+    pub fn reset_for_mint(&mut self) {
+        self.version = 1;
+        self.level = 1;
+        self.ex1 = 0;
+        self.ex2 = 0;
+        self.locked = true;
+        self.extra1 = String::from("7"); // Everyone gets the NEAR decal
+        self.extra2 = String::new();
+        self.extra3 = String::new();
+        self.repair_kits = 0;
+        self.repair();
+    }
+
+    /// Fit the parts, colors and decals of an upgrade request and go up a level.
+    ///
+    /// Newly fitted parts come at full durability, everything else is kept from this kart.
+    pub fn apply_upgrade(&mut self, near_kart_new: &NearKart) {
+        if near_kart_new.front != self.front {
+            self.dur_front = MAX_DURABILITY;
+        }
+        if near_kart_new.left != self.left {
+            self.dur_left = MAX_DURABILITY;
+        }
+        if near_kart_new.right != self.right {
+            self.dur_right = MAX_DURABILITY;
+        }
+        if near_kart_new.skin != self.skin {
+            self.dur_skin = MAX_DURABILITY;
+        }
+        if near_kart_new.transport != self.transport {
+            self.dur_transport = MAX_DURABILITY;
+        }
+
+        self.apply_config(near_kart_new);
+        self.level = self.level + 1;
+        self.locked = true;
+    }
+
+    /// Fit the parts, paint and decals of a configure request, keeping the level.
+    ///
+    /// Durability stays with the slot, so swapping parts back and forth doesn't repair them.
+    pub fn apply_config(&mut self, near_kart_new: &NearKart) {
+        self.apply_cosmetics(near_kart_new);
+        self.front = near_kart_new.front;
+        self.left = near_kart_new.left;
+        self.right = near_kart_new.right;
+        self.skin = near_kart_new.skin;
+        self.transport = near_kart_new.transport;
+    }
+
+    /// Take the paint, finish and decals of a request, leaving the parts as they are.
+    pub fn apply_cosmetics(&mut self, near_kart_new: &NearKart) {
+        self.color1 = near_kart_new.color1;
+        self.color2 = near_kart_new.color2;
+        self.ex1 = near_kart_new.ex1;
+        self.decal1 = near_kart_new.decal1.clone();
+        self.decal2 = near_kart_new.decal2.clone();
+        self.decal3 = near_kart_new.decal3.clone();
+    }
+
+    /// The request fits other parts than this kart has.
+    pub fn parts_differ(&self, near_kart_new: &NearKart) -> bool {
+        return self.front != near_kart_new.front
+            || self.left != near_kart_new.left
+            || self.right != near_kart_new.right
+            || self.skin != near_kart_new.skin
+            || self.transport != near_kart_new.transport;
+    }
+
+    #[cfg(feature = "msgpack")]
+    pub fn from_data(data: &String) -> Result<Self, KartError> {
+        let mut s = Self::default();
+        s.deserialize(data)?;
+        Ok(s)
+    }
+
+    #[cfg(feature = "msgpack")]
+    pub fn serialize(&self) -> String {
+        let sj_message_pack = rmp_serde::encode::to_vec(self).unwrap();
+        let sj_hex = hex::encode(&sj_message_pack);
+        return sj_hex;
+    }
+
+    /// Decode the hex MessagePack from `extra`, data of 16 characters or less is no kart yet.
+    #[cfg(feature = "msgpack")]
+    pub fn deserialize(&mut self, data: &String) -> Result<&Self, KartError> {
+        if String::len(data) > 16 {
+            let sj_vec = hex::decode(data).map_err(|_| KartError::InvalidKartData)?;
+            let sj_old: NearKart =
+                rmp_serde::decode::from_slice(&sj_vec).map_err(|_| KartError::InvalidKartData)?;
+            self.clone_from(&sj_old);
+        }
+        Ok(self)
+    }
+
+    /// Add a decal to the unlocked list in `extra1`.
+    ///
+    /// Returns false if the decal was already unlocked.
+    pub fn unlock_decal(&mut self, decal: &String) -> bool {
+        let mut unlocks: Vec<String> = self.extra1.split(",").map(|s| s.to_string()).collect();
+
+        if unlocks.contains(decal) {
+            return false;
+        }
+
+        if unlocks.len() == 1 && unlocks[0] == "" {
+            unlocks[0] = decal.clone();
+        } else {
+            unlocks.push(decal.clone());
+        }
+        self.extra1 = unlocks.join(",");
+
+        return true;
+    }
+
+    /// The standard palette or one in the CSV list of unlocked palettes in `extra2`.
+    pub fn has_palette(&self, palette: u8) -> bool {
+        if palette == STANDARD_PALETTE {
+            return true;
+        }
+
+        let palette = palette.to_string();
+        return self.extra2.split(",").any(|unlocked| unlocked == palette);
+    }
+
+    /// Add a palette to the unlocked list in `extra2`.
+    ///
+    /// Returns false if the palette was already unlocked.
+    pub fn unlock_palette(&mut self, palette: u8) -> bool {
+        if self.has_palette(palette) {
+            return false;
+        }
+
+        if self.extra2 == "" {
+            self.extra2 = palette.to_string();
+        } else {
+            self.extra2 = format!("{},{}", self.extra2, palette);
+        }
+
+        return true;
+    }
+
+    /// The standard finish or one of the finishes owned in the `ex2` bit set.
+    pub fn has_finish(&self, finish: u8) -> bool {
+        if finish == STANDARD_FINISH {
+            return true;
+        }
+        return finish < 32 && self.ex2 & (1 << finish) != 0;
+    }
+
+    /// Add a finish to the owned bit set in `ex2`.
+    ///
+    /// Returns false if the finish was already owned.
+    pub fn unlock_finish(&mut self, finish: u8) -> bool {
+        if finish >= 32 || self.has_finish(finish) {
+            return false;
+        }
+
+        self.ex2 = self.ex2 | (1 << finish);
+        return true;
+    }
+
+    /// Number of equipped parts whose durability has run out.
+    ///
+    /// Empty weapon slots never wear, the skin and transport are always equipped.
+    pub fn worn_part_count(&self) -> u8 {
+        let mut count = 0;
+        let parts = [
+            (self.left, self.dur_left),
+            (self.right, self.dur_right),
+            (self.front, self.dur_front),
+        ];
+
+        for (index, durability) in parts.iter() {
+            if *index != 0 && *durability == 0 {
+                count += 1;
+            }
+        }
+
+        if self.dur_skin == 0 {
+            count += 1;
+        }
+        if self.dur_transport == 0 {
+            count += 1;
+        }
+
+        return count;
+    }
+
+    /// Apply the wear of a single battle to every equipped part.
+    pub fn apply_wear(&mut self) {
+        if self.left != 0 {
+            self.dur_left = self.dur_left.saturating_sub(WEAPON_WEAR);
+        }
+        if self.right != 0 {
+            self.dur_right = self.dur_right.saturating_sub(WEAPON_WEAR);
+        }
+        if self.front != 0 {
+            self.dur_front = self.dur_front.saturating_sub(WEAPON_WEAR);
+        }
+
+        let skin_wear = SKIN_WEAR.get(self.skin as usize).copied().unwrap_or(1);
+        self.dur_skin = self.dur_skin.saturating_sub(skin_wear);
+
+        let transport_wear = TRANSPORT_WEAR
+            .get(self.transport as usize)
+            .copied()
+            .unwrap_or(1);
+        self.dur_transport = self.dur_transport.saturating_sub(transport_wear);
+    }
+
+    /// Total durability points missing across all parts.
+    pub fn repair_points_needed(&self) -> u32 {
+        let durabilities = [
+            self.dur_left,
+            self.dur_right,
+            self.dur_front,
+            self.dur_skin,
+            self.dur_transport,
+        ];
+
+        durabilities
+            .iter()
+            .map(|d| (MAX_DURABILITY - cmp::min(*d, MAX_DURABILITY)) as u32)
+            .sum()
+    }
+
+    /// Restore every part to full durability.
+    pub fn repair(&mut self) {
+        self.dur_left = MAX_DURABILITY;
+        self.dur_right = MAX_DURABILITY;
+        self.dur_front = MAX_DURABILITY;
+        self.dur_skin = MAX_DURABILITY;
+        self.dur_transport = MAX_DURABILITY;
+    }
+}
+
+#[cfg(all(test, feature = "msgpack"))]
+mod tests {
+    use super::*;
+
+    // Kart as configured by nft_mint, matches DEFAULT_EXTRA in the contract tests
+    const DEFAULT_EXTRA: &str = "dc0019010100000000000000000000c3a0a0a0a137a0a0646464646400";
+    // Kart minted before durability and repair kits were added
+    const LEGACY_EXTRA: &str = "dc0013010100000000000000000000c3a137a0a0a137a0a0";
+
+    fn minted_kart() -> NearKart {
+        let mut nk = NearKart::new();
+        nk.reset_for_mint();
+        nk
+    }
+
+    #[test]
+    fn test_serialize_pins_hex() {
+        assert_eq!(minted_kart().serialize(), DEFAULT_EXTRA);
+    }
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let mut nk = minted_kart();
+        nk.left = 4;
+        nk.color2 = 0xff00ff;
+        nk.decal2 = String::from("3");
+        nk.dur_left = 42;
+        nk.repair_kits = 2;
+
+        let decoded = NearKart::from_data(&nk.serialize()).unwrap();
+        assert_eq!(decoded.serialize(), nk.serialize());
+        assert_eq!(decoded.dur_left, 42);
+        assert_eq!(decoded.repair_kits, 2);
+    }
+
+    #[test]
+    fn test_deserialize_legacy_kart() {
+        let nk = NearKart::from_data(&String::from(LEGACY_EXTRA)).unwrap();
+
+        assert_eq!(nk.level, 1);
+        assert_eq!(nk.decal1, "7");
+        assert_eq!(nk.dur_skin, MAX_DURABILITY);
+        assert_eq!(nk.repair_kits, 0);
+    }
+
+    #[test]
+    fn test_deserialize_short_data_is_default() {
+        let nk = NearKart::from_data(&String::from("")).unwrap();
+
+        assert_eq!(nk.level, 0);
+        assert_eq!(nk.extra1, "");
+    }
+
+    #[test]
+    fn test_deserialize_invalid_data() {
+        let not_hex = String::from("not a kart, not even hex");
+        assert_eq!(
+            NearKart::from_data(&not_hex).err(),
+            Some(KartError::InvalidKartData)
+        );
+
+        let not_msgpack = String::from("c1c1c1c1c1c1c1c1c1c1");
+        assert_eq!(
+            NearKart::from_data(&not_msgpack).err(),
+            Some(KartError::InvalidKartData)
+        );
+    }
+
+    #[test]
+    fn test_borsh_round_trip() {
+        let nk = minted_kart();
+        let bytes = nk.try_to_vec().unwrap();
+
+        assert_eq!(
+            NearKart::try_from_slice(&bytes).unwrap().serialize(),
+            nk.serialize()
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let nk = minted_kart();
+        let json = serde_json::to_string(&nk).unwrap();
+        let decoded: NearKart = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded.serialize(), DEFAULT_EXTRA);
+    }
+
+    #[test]
+    fn test_unlock_decal() {
+        let mut nk = NearKart::new();
+
+        assert!(nk.unlock_decal(&String::from("7")));
+        assert!(nk.unlock_decal(&String::from("3")));
+        assert!(!nk.unlock_decal(&String::from("7")));
+        assert_eq!(nk.extra1, "7,3");
+    }
+
+    #[test]
+    fn test_unlock_palettes_and_finishes() {
+        let mut nk = minted_kart();
+
+        assert!(nk.has_palette(STANDARD_PALETTE));
+        assert!(!nk.unlock_palette(STANDARD_PALETTE));
+        assert!(nk.unlock_palette(2));
+        assert!(nk.unlock_palette(1));
+        assert!(!nk.unlock_palette(2));
+        assert_eq!(nk.extra2, "2,1");
+
+        assert!(nk.has_finish(STANDARD_FINISH));
+        assert!(!nk.has_finish(3));
+        assert!(nk.unlock_finish(3));
+        assert!(!nk.unlock_finish(3));
+        assert!(nk.has_finish(3));
+        assert_eq!(nk.ex2, 0b1000);
+
+        // Unlocks survive the round trip through the metadata
+        let decoded = NearKart::from_data(&nk.serialize()).unwrap();
+        assert!(decoded.has_palette(1));
+        assert!(decoded.has_finish(3));
+    }
+
+    #[test]
+    fn test_apply_upgrade() {
+        let mut nk = minted_kart();
+        nk.locked = false;
+        nk.dur_left = 10;
+        nk.dur_skin = 10;
+
+        let mut near_kart_new = nk.clone();
+        near_kart_new.left = 2;
+        near_kart_new.color1 = 0x00ff00;
+        near_kart_new.level = 9;
+        nk.apply_upgrade(&near_kart_new);
+
+        assert_eq!(nk.level, 2);
+        assert!(nk.locked);
+        assert_eq!(nk.left, 2);
+        assert_eq!(nk.color1, 0x00ff00);
+        assert_eq!(nk.dur_left, MAX_DURABILITY);
+        assert_eq!(nk.dur_skin, 10);
+    }
+
+    #[test]
+    fn test_apply_config_keeps_level_and_durability() {
+        let mut nk = minted_kart();
+        nk.dur_left = 10;
+
+        let mut near_kart_new = nk.clone();
+        near_kart_new.left = 2;
+        near_kart_new.level = 9;
+        nk.apply_config(&near_kart_new);
+
+        assert_eq!(nk.level, 1);
+        assert_eq!(nk.left, 2);
+        assert_eq!(nk.dur_left, 10);
+    }
+}
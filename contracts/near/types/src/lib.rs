@@ -0,0 +1,45 @@
+/*
+Types shared by the NEAR Karts contract and off-chain tools.
+NOTES:
+  - `no_std` compatible with `default-features = false`, only `alloc` is needed for the kart,
+    battle and event types and their serde / borsh encodings.
+  - The `msgpack` feature adds `NearKart::serialize` / `NearKart::deserialize`, the hex encoded
+    MessagePack stored in the token metadata `extra` field.
+  - `equip::validate_equip` holds the equip rules the contract enforces on mint, configure and
+    upgrade, returning the same error codes it panics with. Paint colors and finishes are checked
+    against the `cosmetics` catalog there too.
+  - `error::KartError` is every error the contract reports, with stable numeric and string codes.
+    The contract panics with `KART_ERR:<code>:<message>`, see `error::error_catalog`.
+  - `media::validate_media` checks a token media CID and the sha256 of the image, and
+    `media::media_message` is what the media signer signs for them.
+  - `name::normalize_kart_name` holds the kart name rules, so a name can be checked before it is
+    minted with or renamed to.
+  - `replay::battle_rounds` is the canonical round list of a battle, pinned by the golden
+    vectors in `vectors/replay.json` that other clients test their replay against.
+  - The `schema` feature derives JSON Schema for every type, see `schema::json_schemas`.
+*/
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod battle;
+pub mod cosmetics;
+pub mod equip;
+pub mod error;
+pub mod events;
+pub mod kart;
+pub mod media;
+pub mod name;
+pub mod replay;
+#[cfg(feature = "schema")]
+pub mod schema;
+
+pub use crate::battle::*;
+pub use crate::cosmetics::*;
+pub use crate::equip::*;
+pub use crate::error::*;
+pub use crate::events::*;
+pub use crate::kart::*;
+pub use crate::media::*;
+pub use crate::name::*;
+pub use crate::replay::*;
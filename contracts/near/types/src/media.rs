@@ -0,0 +1,224 @@
+use crate::error::KartError;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// IPFS gateway set as `nft_metadata.base_uri`, token `media` is a CID relative to it.
+pub const IPFS_GATEWAY: &str = "https://ipfs.io/ipfs";
+
+pub const CODEC_RAW: u64 = 0x55;
+pub const CODEC_DAG_PB: u64 = 0x70;
+const MULTIHASH_SHA2_256: u64 = 0x12;
+pub const MEDIA_HASH_LENGTH: usize = 32;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// A parsed IPFS CID, only sha2-256 multihashes are accepted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cid {
+    pub version: u8,
+    pub codec: u64,
+    pub digest: [u8; MEDIA_HASH_LENGTH],
+}
+
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+
+    for c in s.bytes() {
+        let value = BASE32_ALPHABET.iter().position(|a| *a == c)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    // Leftover bits are padding and must be zero
+    if bits >= 5 || buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    return Some(out);
+}
+
+fn base58_decode(s: &str) -> Option<Vec<u8>> {
+    // Big endian base 256 digits, least significant last
+    let mut out: Vec<u8> = Vec::with_capacity(s.len());
+
+    for c in s.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| *a == c)? as u32;
+        for byte in out.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            out.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let zeros = s.bytes().take_while(|c| *c == b'1').count();
+    let mut decoded = alloc::vec![0u8; zeros];
+    decoded.extend(out);
+    return Some(decoded);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..63).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    return None;
+}
+
+fn read_digest(data: &[u8], pos: &mut usize) -> Result<[u8; MEDIA_HASH_LENGTH], KartError> {
+    let hash = read_varint(data, pos).ok_or(KartError::InvalidCid)?;
+    let length = read_varint(data, pos).ok_or(KartError::InvalidCid)?;
+    if hash != MULTIHASH_SHA2_256 || length != MEDIA_HASH_LENGTH as u64 {
+        return Err(KartError::UnsupportedCidHash);
+    }
+    if data.len() != *pos + MEDIA_HASH_LENGTH {
+        return Err(KartError::InvalidCid);
+    }
+
+    let mut digest = [0u8; MEDIA_HASH_LENGTH];
+    digest.copy_from_slice(&data[*pos..]);
+    return Ok(digest);
+}
+
+/// Parse a CIDv0 (base58btc `Qm...`) or a CIDv1 (multibase base32 `b...` or base58btc `z...`)
+/// with the raw or dag-pb codec.
+pub fn parse_cid(cid: &str) -> Result<Cid, KartError> {
+    if cid.len() == 46 && cid.starts_with("Qm") {
+        let data = base58_decode(cid).ok_or(KartError::InvalidCid)?;
+        let digest = read_digest(&data, &mut 0)?;
+        return Ok(Cid {
+            version: 0,
+            codec: CODEC_DAG_PB,
+            digest,
+        });
+    }
+
+    let data = match cid.get(..1) {
+        Some("b") => base32_decode(&cid[1..]),
+        Some("z") => base58_decode(&cid[1..]),
+        _ => None,
+    }
+    .ok_or(KartError::InvalidCid)?;
+
+    let mut pos = 0;
+    if read_varint(&data, &mut pos) != Some(1) {
+        return Err(KartError::InvalidCid);
+    }
+    let codec = read_varint(&data, &mut pos).ok_or(KartError::InvalidCid)?;
+    if codec != CODEC_RAW && codec != CODEC_DAG_PB {
+        return Err(KartError::UnsupportedCidCodec);
+    }
+    let digest = read_digest(&data, &mut pos)?;
+
+    return Ok(Cid {
+        version: 1,
+        codec,
+        digest,
+    });
+}
+
+/// Check a media CID against the sha256 of the media file.
+///
+/// A raw CID is the sha256 of the file itself, so it has to match. A dag-pb CID hashes the
+/// UnixFS encoding instead, only the hash length can be checked.
+pub fn validate_media(cid: &str, media_hash: &[u8]) -> Result<Cid, KartError> {
+    if media_hash.len() != MEDIA_HASH_LENGTH {
+        return Err(KartError::InvalidMediaHash);
+    }
+    let parsed = parse_cid(cid)?;
+    if parsed.codec == CODEC_RAW && parsed.digest[..] != *media_hash {
+        return Err(KartError::MediaHashMismatch);
+    }
+    return Ok(parsed);
+}
+
+/// Message signed by the media signer, the CID and the hex sha256 of the media file.
+pub fn media_message(cid: &str, media_hash: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut message = String::with_capacity(cid.len() + 1 + media_hash.len() * 2);
+    message.push_str(cid);
+    message.push(':');
+    for byte in media_hash {
+        message.push(HEX[(byte >> 4) as usize] as char);
+        message.push(HEX[(byte & 15) as usize] as char);
+    }
+    return message;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sha256("hello")
+    const HELLO_SHA256: [u8; 32] = [
+        0x2c, 0xf2, 0x4d, 0xba, 0x5f, 0xb0, 0xa3, 0x0e, 0x26, 0xe8, 0x3b, 0x2a, 0xc5, 0xb9, 0xe2,
+        0x9e, 0x1b, 0x16, 0x1e, 0x5c, 0x1f, 0xa7, 0x42, 0x5e, 0x73, 0x04, 0x33, 0x62, 0x93, 0x8b,
+        0x98, 0x24,
+    ];
+    // `echo -n hello | ipfs add --cid-version 1 --raw-leaves`
+    const HELLO_RAW_CID: &str = "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq";
+    const HELLO_V0_CID: &str = "QmWfVY9y3xjsixTgbd9AorQxH7VtMpzfx2HaWtsoUYecaX";
+
+    #[test]
+    fn test_parse_raw_cid() {
+        let cid = parse_cid(HELLO_RAW_CID).unwrap();
+        assert_eq!(cid.version, 1);
+        assert_eq!(cid.codec, CODEC_RAW);
+        assert_eq!(cid.digest, HELLO_SHA256);
+    }
+
+    #[test]
+    fn test_parse_v0_cid() {
+        let cid = parse_cid(HELLO_V0_CID).unwrap();
+        assert_eq!(cid.version, 0);
+        assert_eq!(cid.codec, CODEC_DAG_PB);
+    }
+
+    #[test]
+    fn test_invalid_cids() {
+        assert_eq!(parse_cid(""), Err(KartError::InvalidCid));
+        assert_eq!(parse_cid("bla.png"), Err(KartError::InvalidCid));
+        assert_eq!(parse_cid(&HELLO_RAW_CID[..40]), Err(KartError::InvalidCid));
+        assert_eq!(
+            parse_cid("QmWfVY9y3xjsixTgbd9AorQxH7VtMpzfx2HaWtsoUYeca0"),
+            Err(KartError::InvalidCid)
+        );
+        // dag-cbor
+        assert_eq!(
+            parse_cid("bafyreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq"),
+            Err(KartError::UnsupportedCidCodec)
+        );
+    }
+
+    #[test]
+    fn test_validate_media() {
+        assert!(validate_media(HELLO_RAW_CID, &HELLO_SHA256).is_ok());
+        assert_eq!(
+            validate_media(HELLO_RAW_CID, &[0; 32]),
+            Err(KartError::MediaHashMismatch)
+        );
+        assert_eq!(
+            validate_media(HELLO_RAW_CID, &[0; 4]),
+            Err(KartError::InvalidMediaHash)
+        );
+        // The dag-pb digest isn't the file hash
+        assert!(validate_media(HELLO_V0_CID, &[0; 32]).is_ok());
+    }
+
+    #[test]
+    fn test_media_message() {
+        assert_eq!(media_message("bafy", &[0x01, 0xab]), "bafy:01ab");
+    }
+}
@@ -0,0 +1,87 @@
+use crate::error::KartError;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+pub const MIN_KART_NAME_LENGTH: usize = 3;
+pub const MAX_KART_NAME_LENGTH: usize = 24;
+
+/// Title prefix of early mints, the web strips it when showing a name.
+pub const LEGACY_NAME_PREFIX: &str = "A NEAR Kart Called ";
+
+/// The name a kart is registered and titled with.
+///
+/// Runs of whitespace become a single space and the legacy title prefix is dropped. Names are
+/// 3 to 24 ASCII letters, digits, spaces, `-` or `_`.
+pub fn normalize_kart_name(name: &str) -> Result<String, KartError> {
+    let name = name.trim();
+    let name = name.strip_prefix(LEGACY_NAME_PREFIX).unwrap_or(name);
+    let name = name.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_');
+    if !valid_chars || name.len() < MIN_KART_NAME_LENGTH || name.len() > MAX_KART_NAME_LENGTH {
+        return Err(KartError::KartNameInvalid);
+    }
+    return Ok(name);
+}
+
+/// Key names are unique by, `normalize_kart_name` in lowercase.
+pub fn kart_name_key(name: &str) -> Result<String, KartError> {
+    return Ok(normalize_kart_name(name)?.to_ascii_lowercase());
+}
+
+/// Letters and digits of a name in lowercase, what the blocklist is matched against so
+/// separators can't hide a blocked word.
+pub fn compact_kart_name(name: &str) -> String {
+    return name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_kart_name() {
+        assert_eq!(normalize_kart_name("  Mega   Kart ").unwrap(), "Mega Kart");
+        assert_eq!(
+            normalize_kart_name("A NEAR Kart Called Hamakua").unwrap(),
+            "Hamakua"
+        );
+        assert_eq!(normalize_kart_name("turbo_kart-2").unwrap(), "turbo_kart-2");
+    }
+
+    #[test]
+    fn test_invalid_kart_names() {
+        for name in [
+            "ab",
+            "   ",
+            "a kart name that is way too long",
+            "Kart!",
+            "Kärt",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                normalize_kart_name(name),
+                Err(KartError::KartNameInvalid),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_kart_name_keys() {
+        assert_eq!(kart_name_key("Mega  KART").unwrap(), "mega kart");
+        assert_eq!(
+            kart_name_key("mega kart").unwrap(),
+            kart_name_key("MEGA KART").unwrap()
+        );
+        assert_eq!(compact_kart_name("Mega-Kart_2"), "megakart2");
+    }
+}
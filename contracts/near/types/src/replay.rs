@@ -0,0 +1,265 @@
+use crate::battle::SimpleBattle;
+use crate::equip::SHIELD_START_INDEX;
+use crate::kart::NearKart;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+pub const SHIELD_CHANCE: u32 = 5;
+pub const BUMP_CHANCE_BY_WEAPON_COUNT: [u32; 3] = [2, 4, 8];
+pub const BOTTOM_SCORE: u32 = 10;
+pub const TOP_SCORE: u32 = 30;
+/// Hits scoring at least this much get an exclamation in the battle text.
+pub const POWER_SCORE: u32 = 26;
+pub const WINNER_SCORE: u32 = 100;
+pub const LOSER_SCORE: u32 = 90;
+
+/// Weapon of a bump, the attack used by a kart without weapons or when it misses its turn.
+pub const WEAPON_GENERAL: &str = "general";
+/// Shield of a blocked attack when the victim has no shield.
+pub const SHIELD_EVADE: &str = "evade";
+
+// Part names by index as the battle text uses them, `config.js` ids without the prefix
+const WEAPONS_RANGE: [&str; 6] = ["", "laser", "rocket", "fist", "flamethrower", "aceed"];
+const WEAPONS_MELEE: [&str; 5] = ["", "flipper", "sword", "axe", "hammer"];
+const SHIELDS_SIDE: [&str; 2] = ["kitten", "kevlar"];
+
+/// One attack in a battle replay.
+///
+/// `shield` is set when the attack was blocked, `score` is 0 then.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BattleRound {
+    pub aggressor: u8,
+    pub weapon: String,
+    pub shield: Option<String>,
+    pub score: u32,
+}
+
+/// mulberry32, small enough to port exactly to any client. JS: `Math.imul` and `>>> 0`.
+pub struct ReplayRng {
+    state: u32,
+}
+
+impl ReplayRng {
+    pub fn new(seed: u32) -> Self {
+        ReplayRng { state: seed }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state = self.state.wrapping_add(0x6d2b_79f5);
+        let a = self.state;
+        let mut t = (a ^ (a >> 15)).wrapping_mul(a | 1);
+        t ^= t.wrapping_add((t ^ (t >> 7)).wrapping_mul(t | 61));
+        return t ^ (t >> 14);
+    }
+
+    /// Integer in `min..=max`.
+    pub fn range(&mut self, min: u32, max: u32) -> u32 {
+        return min + self.next_u32() % (max - min + 1);
+    }
+
+    /// Random list entry, always draws a number so empty lists keep the sequence in step.
+    pub fn pick<'a>(&mut self, list: &[&'a str]) -> Option<&'a str> {
+        let rand = self.next_u32() as usize;
+        if list.is_empty() {
+            return None;
+        }
+        return Some(list[rand % list.len()]);
+    }
+}
+
+/// Weapons and shields a kart fights with, in left, right, front order.
+pub fn kart_arms(nk: &NearKart) -> (Vec<&'static str>, Vec<&'static str>) {
+    let mut weapons = Vec::new();
+    let mut shields = Vec::new();
+
+    for side in [nk.left, nk.right].iter() {
+        if *side >= SHIELD_START_INDEX {
+            if let Some(shield) = SHIELDS_SIDE.get((*side - SHIELD_START_INDEX) as usize) {
+                shields.push(*shield);
+            }
+        } else if let Some(weapon) = WEAPONS_RANGE.get(*side as usize) {
+            if !weapon.is_empty() {
+                weapons.push(*weapon);
+            }
+        }
+    }
+    if let Some(weapon) = WEAPONS_MELEE.get(nk.front as usize) {
+        if !weapon.is_empty() {
+            weapons.push(*weapon);
+        }
+    }
+
+    return (weapons, shields);
+}
+
+fn attacks(
+    rng: &mut ReplayRng,
+    aggressor: u8,
+    weapons: &[&str],
+    shields: &[&str],
+    target: u32,
+) -> Vec<BattleRound> {
+    let mut rounds = Vec::new();
+    let mut total = 0;
+
+    while total < target {
+        let mut score = 0;
+        if rng.range(0, SHIELD_CHANCE) != 0 {
+            score = rng.range(BOTTOM_SCORE, TOP_SCORE);
+            total += score;
+        }
+
+        let mut weapon = rng.pick(weapons);
+        let bump_index = weapons.len().min(BUMP_CHANCE_BY_WEAPON_COUNT.len() - 1);
+        if weapon.is_some() && rng.range(0, BUMP_CHANCE_BY_WEAPON_COUNT[bump_index]) == 0 {
+            weapon = None;
+        }
+
+        let mut shield = None;
+        if score == 0 {
+            shield = Some(rng.pick(shields).unwrap_or(SHIELD_EVADE).to_string());
+        }
+
+        rounds.push(BattleRound {
+            aggressor,
+            weapon: weapon.unwrap_or(WEAPON_GENERAL).to_string(),
+            shield,
+            score,
+        });
+    }
+    return rounds;
+}
+
+/// Canonical round list of a battle, the same for every client.
+///
+/// The winner attacks until it reaches `WINNER_SCORE`, the loser until `LOSER_SCORE` without its
+/// last attack. Both lists are shuffled together and the winner's last attack ends the battle.
+/// The rng is seeded with `battle.battle`.
+pub fn battle_rounds(battle: &SimpleBattle, home: &NearKart, away: &NearKart) -> Vec<BattleRound> {
+    let mut rng = ReplayRng::new(battle.battle);
+    let winner = battle.winner.min(1);
+    let loser = 1 - winner;
+    let arms = [kart_arms(home), kart_arms(away)];
+    let (winner_arms, loser_arms) = (&arms[winner as usize], &arms[loser as usize]);
+
+    let mut winner_rounds = attacks(
+        &mut rng,
+        winner,
+        &winner_arms.0,
+        &loser_arms.1,
+        WINNER_SCORE,
+    );
+    let mut loser_rounds = attacks(&mut rng, loser, &loser_arms.0, &winner_arms.1, LOSER_SCORE);
+    loser_rounds.pop();
+    let winning_round = winner_rounds.pop();
+
+    let mut rounds = winner_rounds;
+    rounds.append(&mut loser_rounds);
+    for i in (1..rounds.len()).rev() {
+        let j = rng.range(0, i as u32) as usize;
+        rounds.swap(i, j);
+    }
+    rounds.extend(winning_round);
+
+    return rounds;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[derive(Deserialize)]
+    struct Parts {
+        left: u8,
+        right: u8,
+        front: u8,
+    }
+
+    #[derive(Deserialize)]
+    struct Vector {
+        battle: u32,
+        winner: u8,
+        home: Parts,
+        away: Parts,
+        rounds: Vec<BattleRound>,
+    }
+
+    fn kart(parts: &Parts) -> NearKart {
+        let mut nk = NearKart::new();
+        nk.left = parts.left;
+        nk.right = parts.right;
+        nk.front = parts.front;
+        return nk;
+    }
+
+    fn simple_battle(battle: u32, winner: u8) -> SimpleBattle {
+        SimpleBattle {
+            home_token_id: "home".into(),
+            away_token_id: "away".into(),
+            winner,
+            battle,
+            prize: "0".into(),
+            extra: "".into(),
+        }
+    }
+
+    #[test]
+    fn test_rng() {
+        // Reference values from the JS mulberry32
+        let mut rng = ReplayRng::new(1);
+        assert_eq!(rng.next_u32(), 2_693_262_067);
+        assert_eq!(rng.next_u32(), 11_749_833);
+    }
+
+    #[test]
+    fn test_kart_arms() {
+        let mut nk = NearKart::new();
+        nk.left = 1;
+        nk.right = SHIELD_START_INDEX + 1;
+        nk.front = 4;
+        assert_eq!(kart_arms(&nk), (vec!["laser", "hammer"], vec!["kevlar"]));
+        assert_eq!(kart_arms(&NearKart::new()), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_scores() {
+        for seed in 0..200 {
+            let battle = simple_battle(seed, (seed % 2) as u8);
+            let rounds = battle_rounds(&battle, &NearKart::new(), &NearKart::new());
+            let winner: u32 = rounds
+                .iter()
+                .filter(|r| r.aggressor == battle.winner)
+                .map(|r| r.score)
+                .sum();
+            let loser: u32 = rounds
+                .iter()
+                .filter(|r| r.aggressor != battle.winner)
+                .map(|r| r.score)
+                .sum();
+
+            assert!(winner >= WINNER_SCORE);
+            assert!(loser < LOSER_SCORE);
+            assert_eq!(rounds.last().unwrap().aggressor, battle.winner);
+            assert!(rounds.iter().all(|r| (r.score == 0) == r.shield.is_some()));
+        }
+    }
+
+    #[test]
+    fn test_golden_vectors() {
+        let vectors: Vec<Vector> =
+            serde_json::from_str(include_str!("../vectors/replay.json")).unwrap();
+        assert!(!vectors.is_empty());
+
+        for v in vectors.iter() {
+            let rounds = battle_rounds(
+                &simple_battle(v.battle, v.winner),
+                &kart(&v.home),
+                &kart(&v.away),
+            );
+            assert_eq!(rounds, v.rounds, "battle {}", v.battle);
+        }
+    }
+}
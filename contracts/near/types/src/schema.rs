@@ -0,0 +1,41 @@
+use crate::{BattleRound, ErrorInfo, EventLog, KartMeta, NearKart, SimpleBattle};
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use std::collections::BTreeMap;
+
+/// JSON Schema of every shared type, keyed by type name.
+pub fn json_schemas() -> BTreeMap<&'static str, RootSchema> {
+    let mut schemas = BTreeMap::new();
+    schemas.insert("NearKart", schema_for!(NearKart));
+    schemas.insert("SimpleBattle", schema_for!(SimpleBattle));
+    schemas.insert("BattleRound", schema_for!(BattleRound));
+    schemas.insert("KartMeta", schema_for!(KartMeta));
+    schemas.insert("EventLog", schema_for!(EventLog));
+    schemas.insert("ErrorInfo", schema_for!(ErrorInfo));
+    schemas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_near_kart_schema() {
+        let schemas = json_schemas();
+        let json = serde_json::to_value(&schemas["NearKart"]).unwrap();
+        let properties = json["properties"].as_object().unwrap();
+
+        assert_eq!(properties.len(), 25);
+        assert!(properties.contains_key("repair_kits"));
+        assert_eq!(json["properties"]["dur_left"]["default"], 100);
+    }
+
+    #[test]
+    fn test_event_log_schema() {
+        let schemas = json_schemas();
+        let json = serde_json::to_string(&schemas["EventLog"]).unwrap();
+
+        assert!(json.contains("nft_transfer"));
+        assert!(json.contains("level_up"));
+    }
+}
@@ -48,9 +48,32 @@ export function handleReceipt(
             }
           }
         }
-        else if(eventStr == 'configure_on_mint' || eventStr == 'configure_on_upgrade') {
+        else if(eventStr == 'nft_transfer') {
           let data = jo.get('data');
-          let kartMeta = data ? data.toObject() : null;
+          let dataObj = data ? data.toArray() : null;
+          let transferInfoJsonValue = dataObj ? dataObj[0] : null;
+          let transferInfo = transferInfoJsonValue ? transferInfoJsonValue.toObject() : null;
+
+          if(transferInfo) {
+            let tokenIdJsonValue = transferInfo.get('token_ids');
+            let tokenId = tokenIdJsonValue ? tokenIdJsonValue.toArray()[0].toString() : '';
+
+            let newOwnerJsonValue = transferInfo.get('new_owner_id');
+            let newOwner = newOwnerJsonValue ? newOwnerJsonValue.toString() : '';
+
+            let nearKart = NearKart.load(tokenId);
+
+            if(nearKart && newOwner) {
+              nearKart.ownerId = newOwner;
+              nearKart.save();
+            }
+          }
+        }
+        else if(eventStr == 'configure' || eventStr == 'upgrade') {
+          let data = jo.get('data');
+          let dataArr = data ? data.toArray() : null;
+          let kartMetaJsonValue = dataArr ? dataArr[0] : null;
+          let kartMeta = kartMetaJsonValue ? kartMetaJsonValue.toObject() : null;
 
           if(kartMeta) {
             let tokenIdJsonValue = kartMeta.get('token_id');
@@ -74,9 +97,11 @@ export function handleReceipt(
             }
           }
         }
-        else if(eventStr == 'game_simple_battle') {
+        else if(eventStr == 'battle') {
           let data = jo.get('data');
-          let dataObj = data ? data.toObject() : null;
+          let dataArr = data ? data.toArray() : null;
+          let battleJsonValue = dataArr ? dataArr[0] : null;
+          let dataObj = battleJsonValue ? battleJsonValue.toObject() : null;
 
           if(dataObj) {
             let entity = new NearKartsSimpleBattle(receiptWithOutcome.receipt.id.toHex())
@@ -9,6 +9,7 @@ import * as nearAPI from 'near-api-js';
 import BrButton from './js/components/lib/BrButton';
 import { initNear } from './js/helpers/near';
 import { localLog } from './js/helpers/helpers';
+import { parseContractError } from './js/helpers/errors';
 import NearKarts from './js/components/NearKarts';
 import getText from './data/world/text';
 import bigInt from 'big-integer';
@@ -32,6 +33,10 @@ const nearContractConfig = {
       'get_num_karts',
       'get_token_id_by_index',
       'get_last_battle',
+      'karts_by_ids',
+      'karts_for_owner',
+      'last_battle_with_karts',
+      'get_error_catalog',
     ],
     changeMethods: ['nft_mint', 'upgrade', 'game_simple_battle'],
   },
@@ -88,6 +93,7 @@ function App() {
   const [battleConfig, setBattleConfig] = useState({});
   const [lastBattle, setLastBattle] = useState({});
   const [screen, setScreen] = useState(screens.GARAGE);
+  const [errorIds, setErrorIds] = useState({});
 
   function toast(message, type = 'info') {
     toasty[type](message, {
@@ -112,6 +118,17 @@ function App() {
     );
   }
 
+  function errorToast(message, e) {
+    let contractError = parseContractError(e);
+    if (contractError) {
+      // Panics carry the English message, the catalog maps the code to a text id
+      let id = errorIds[contractError.code];
+      doubleToast(message, id ? getText(id) : contractError.message, 'error');
+    } else {
+      toast(message, 'error');
+    }
+  }
+
   useEffect(() => {
     if (showingHighScores) {
       let period = 0;
@@ -289,6 +306,7 @@ function App() {
                 name,
                 near_kart_new: data.nftData,
                 cid: data.cid,
+                media_hash: data.mediaHash,
                 sig: data.sigHex,
                 pub_key: data.pubKeyHex,
               },
@@ -298,7 +316,7 @@ function App() {
 
             reloadTokens = true;
           } catch (e) {
-            toast(getText('error_mint_kart'), 'error');
+            errorToast(getText('error_mint_kart'), e);
           }
         }
       }
@@ -323,6 +341,7 @@ function App() {
                   token_id: tokenId,
                   near_kart_new: data.nftData,
                   cid: data.cid,
+                  media_hash: data.mediaHash,
                   sig: data.sigHex,
                   pub_key: data.pubKeyHex,
                 },
@@ -332,7 +351,7 @@ function App() {
 
               reloadTokens = true;
             } catch (e) {
-              toast(getText('error_upgrade_kart'), 'error');
+              errorToast(getText('error_upgrade_kart'), e);
               localLog(e);
             }
           }
@@ -343,7 +362,7 @@ function App() {
           if (!tokenId) {
             toast(getText('error_no_active_kart'), 'error');
           } else {
-            let result = await nftContract.game_simple_battle(
+            await nftContract.game_simple_battle(
               {
                 token_id: tokenId,
               },
@@ -351,28 +370,15 @@ function App() {
               '0'
             );
 
-            let homeKart = await nftContract.near_kart_get_config({
-              token_id: result.home_token_id,
-            });
-            let awayKart = await nftContract.near_kart_get_config({
-              token_id: result.away_token_id,
-            });
-
-            let homeMetadata = await nftContract.nft_get_token_metadata({
-              token_id: result.home_token_id,
+            let result = await nftContract.last_battle_with_karts({
+              account_id: wallet.getAccountId(),
             });
-            let awayMetadata = await nftContract.nft_get_token_metadata({
-              token_id: result.away_token_id,
-            });
-
-            result.karts = [homeKart, awayKart];
-            result.metadata = [homeMetadata, awayMetadata];
             setLastBattle(result);
             viewBattle(result);
             reloadTokens = true;
           }
         } catch (e) {
-          toast(getText('error_starting_battle'), 'error');
+          errorToast(getText('error_starting_battle'), e);
           console.log(e);
         }
       } else if (action === 'selectNFT') {
@@ -411,14 +417,11 @@ function App() {
       (async () => {
         for (let token of nftList) {
           if (token.token_id === tokenId) {
-            let nftData = await nftContract.near_kart_get_config({
-              token_id: tokenId,
+            let [kart] = await nftContract.karts_by_ids({
+              token_ids: [tokenId],
             });
-            setNFTData(nftData);
-            let _tokenMetadata = await nftContract.nft_get_token_metadata({
-              token_id: tokenId,
-            });
-            setNFTMetadata(_tokenMetadata);
+            setNFTData(kart.near_kart);
+            setNFTMetadata(kart.metadata);
             setActiveKart(token);
           }
         }
@@ -447,31 +450,30 @@ function App() {
     }
   }, [nftList, tokensLoaded, newKart, selectNFT]);
 
+  useEffect(() => {
+    if (nftContract) {
+      (async () => {
+        try {
+          let catalog = await nftContract.get_error_catalog();
+          let _errorIds = {};
+          for (let entry of catalog) {
+            _errorIds[entry.code] = entry.id;
+          }
+          setErrorIds(_errorIds);
+        } catch (e) {
+          console.log('Error loading error catalog', e);
+        }
+      })();
+    }
+  }, [nftContract]);
+
   useEffect(() => {
     if (nftContract && wallet) {
       (async () => {
         try {
-          let result = await nftContract.get_last_battle({
+          let result = await nftContract.last_battle_with_karts({
             account_id: wallet.getAccountId(),
           });
-
-          let homeKart = await nftContract.near_kart_get_config({
-            token_id: result.home_token_id,
-          });
-          let awayKart = await nftContract.near_kart_get_config({
-            token_id: result.away_token_id,
-          });
-
-          let homeMetadata = await nftContract.nft_get_token_metadata({
-            token_id: result.home_token_id,
-          });
-          let awayMetadata = await nftContract.nft_get_token_metadata({
-            token_id: result.away_token_id,
-          });
-
-          result.karts = [homeKart, awayKart];
-          result.metadata = [homeMetadata, awayMetadata];
-
           setLastBattle(result);
         } catch (e) {
           console.log('Error loading last battle', e);
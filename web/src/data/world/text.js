@@ -149,9 +149,28 @@ const text = {
     "error_image_upload_failed": `Image upload failed`,
     "error_upgrade_kart_locked": `${text_consts.nft_name} is locked for upgrades`,
     "error_signature_verification_failed": `Signature verification of cid failed`,
+    "error_invalid_cid": `Image cid is not a valid IPFS cid`,
+    "error_unsupported_cid_codec": `Image cid codec is not supported`,
+    "error_unsupported_cid_hash": `Image cid hash is not supported`,
+    "error_invalid_media_hash": `Image hash must be a sha256`,
+    "error_media_hash_mismatch": `Image hash does not match the cid`,
     "error_pubkey_is_not_signer": `Pub Key is not a registered signer`,
     "error_mint_payment_too_low": `Minting requires an attached deposit of at least ${text_consts.mint_price}`,
-    "error_upgrade_payment_too_low": `Upgrade requires an attached deposit of at least ${text_consts.upgrade_price}`
+    "error_upgrade_payment_too_low": `Upgrade requires an attached deposit of at least ${text_consts.upgrade_price}`,
+    "error_token_not_found": `${text_consts.nft_name} not found`,
+    "error_not_token_owner": `You don't own this ${text_consts.nft_name}`,
+    "error_not_token_owner_or_operator": `You don't own or operate this ${text_consts.nft_name}`,
+    "error_cannot_upgrade_while_kart_is_locked": `${text_consts.nft_name} is locked for upgrades`,
+    "error_invalid_signature": `Image signature is not valid`,
+    "error_invalid_public_key": `Signer key is not valid`,
+    "error_kart_name_invalid": `Names are 3 to 24 letters, digits, spaces, - or _`,
+    "error_kart_name_taken": `Another ${text_consts.nft_name} already has this name`,
+    "error_kart_name_blocked": `This name is not allowed`,
+    "error_color1_is_not_unlocked": `Primary color is not in an unlocked palette`,
+    "error_color2_is_not_unlocked": `Secondary color is not in an unlocked palette`,
+    "error_finish_is_not_owned": `${text_consts.nft_name} does not own this paint finish`,
+    "error_cosmetic_payment_too_low": `Attached deposit is below the price`,
+    "error_kart_out_of_energy": `${text_consts.nft_name} is out of energy, try again later`
   }
 };
 
@@ -1,7 +1,8 @@
 import { cloneObj } from "./helpers";
 import getText, { getBattleText, exclamation } from "../../data/world/text";
 import seedrandom from 'seedrandom';
-import { getRandomInt, getRandomListEntry, shuffleArray } from "./math";
+import { getRandomListEntry } from "./math";
+import { battleRounds, POWER_SCORE } from "./replay";
 
 const battleText = getBattleText();
 
@@ -44,126 +45,20 @@ export default class Battle {
     this.finished = false;
     this.rounds = [];
     this.roundIndex = 0;
-    this._initWeapons();
     this.generate();
   }
 
-  _initWeapons() {
-    let weapons = [[], []];
-    let shields = [[], []];
-
-    for(let i = 0; i < 2; i++) {
-      let kartConfig = this.battleResult.kartConfigs[i];
-
-      for(let part of ['left', 'right', 'front']) {
-        if(kartConfig[part] && !kartConfig[part].endsWith('Empty')) {
-          if(kartConfig[part].startsWith('Weapon')) {
-            weapons[i].push(kartConfig[part].replace('Weapon', '').toLowerCase());
-          }
-          else if(kartConfig[part].startsWith('Shield')) {
-            shields[i].push(kartConfig[part].replace('Shield', '').toLowerCase());
-          }
-        }
-      }
-    }
-
-    this.kartWeapons = weapons;
-    this.kartShields = shields;
-  }
-
   kartName(kartTitle) {
     return kartTitle.replace('A NEAR Kart Called ', '');
   }
 
   generate() {
+    // Only the battle text is picked with seedrandom, the rounds are the canonical replay
     let battleSeed = this.battleResult.battle.toString();
     this.rng = seedrandom(battleSeed); 
 
     this.rounds = [];
-    let winner = this.battleResult.winner;
-    let loser = 1 - winner;
-
-    let score1 = 0;
-    let roundScores1 = [];
-    let score2= 0;
-    let roundScores2 = [];
-
-    let roundIndex = 0;
-
-    let SHIELD_CHANCE = 5;
-    let BUMP_CHANCE_BY_WEAPON_COUNT = [2, 4, 8];
-    let BOTTOM_SCORE = 10;
-    let TOP_SCORE = 30;
-    let POWER_SCORE = 26;
-
-    do {
-      let roundScore = 0;
-      let miss1 = getRandomInt(0, SHIELD_CHANCE, this.rng) === 0;
-
-      if(!miss1) {
-        roundScore = getRandomInt(BOTTOM_SCORE, TOP_SCORE, this.rng);
-        score1 += roundScore;
-      }
-
-      let weapon = getRandomListEntry(this.kartWeapons[winner], this.rng);
-
-      let bumpChance = BUMP_CHANCE_BY_WEAPON_COUNT[this.kartWeapons[winner].length];
-      if(!weapon || getRandomInt(0, bumpChance, this.rng) === 0) {
-        weapon = 'general';
-      }
-      
-      let shield;
-
-      if(roundScore === 0) {
-        shield = getRandomListEntry(this.kartShields[loser], this.rng);
-
-        if(!shield) {
-          shield = 'evade';
-        }
-      }
-
-      roundScores1[roundIndex++] = { aggressor: winner, score: roundScore, weapon, shield };
-
-    } while(score1 < 100);
-
-    roundIndex = 0;
-    do {
-      let roundScore = 0;
-      let miss1 = getRandomInt(0, SHIELD_CHANCE, this.rng) === 0;
-
-      if(!miss1) {
-        roundScore = getRandomInt(BOTTOM_SCORE, TOP_SCORE, this.rng);
-        score2 += roundScore;
-      }
-
-      let weapon = getRandomListEntry(this.kartWeapons[loser], this.rng);
-
-      let bumpChance = BUMP_CHANCE_BY_WEAPON_COUNT[this.kartWeapons[loser].length];
-      if(!weapon || getRandomInt(0, bumpChance, this.rng) === 0) {
-        weapon = 'general';
-      }
-
-      let shield;
-
-      if(roundScore === 0) {
-        shield = getRandomListEntry(this.kartShields[winner], this.rng);
-
-        if(!shield) {
-          shield = 'evade';
-        }
-      }
-
-      roundScores2[roundIndex++] = { aggressor: loser, score: roundScore, weapon, shield }; 
-
-    } while(score2 < 90);
-
-    roundScores2.pop();
-
-    let winningRound = roundScores1.pop();
-
-    let rounds = [...roundScores1, ...roundScores2];
-    shuffleArray(rounds, this.rng);
-    rounds.push(winningRound);
+    let rounds = battleRounds(this.battleResult, this.karts[0], this.karts[1]);
 
     let playIndex = 0;
     let totals = [0, 0];
@@ -0,0 +1,12 @@
+// Contract panics are `KART_ERR:<code>:<message>`, see `error::KartError` in contracts/near/types.
+// The contract's `get_error_catalog` maps `code` to the text key of the localized message.
+const KART_ERR = /KART_ERR:(\d+):([^"\n]*)/;
+
+export function parseContractError(e) {
+  let message = e && e.message !== undefined ? e.message : String(e);
+  let match = KART_ERR.exec(message);
+  if (!match) {
+    return null;
+  }
+  return { code: parseInt(match[1], 10), message: match[2] };
+}
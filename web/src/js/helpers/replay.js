@@ -0,0 +1,104 @@
+// Port of `replay::battle_rounds` in contracts/near/types, the canonical round list of a battle.
+// Must stay in step with the Rust version, `js/test/replay.test.js` checks it against the
+// golden vectors in contracts/near/types/vectors/replay.json.
+
+export const SHIELD_CHANCE = 5;
+export const BUMP_CHANCE_BY_WEAPON_COUNT = [2, 4, 8];
+export const BOTTOM_SCORE = 10;
+export const TOP_SCORE = 30;
+export const POWER_SCORE = 26;
+export const WINNER_SCORE = 100;
+export const LOSER_SCORE = 90;
+
+const SHIELD_START_INDEX = 200;
+const WEAPONS_RANGE = ['', 'laser', 'rocket', 'fist', 'flamethrower', 'aceed'];
+const WEAPONS_MELEE = ['', 'flipper', 'sword', 'axe', 'hammer'];
+const SHIELDS_SIDE = ['kitten', 'kevlar'];
+
+// mulberry32, returns unsigned 32 bit integers
+export function replayRng(seed) {
+  let state = seed >>> 0;
+
+  let next = () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = Math.imul(state ^ (state >>> 15), state | 1);
+    t = (t + Math.imul(t ^ (t >>> 7), t | 61)) ^ t;
+    return (t ^ (t >>> 14)) >>> 0;
+  };
+
+  return {
+    next,
+    range: (min, max) => min + (next() % (max - min + 1)),
+    pick: (list) => {
+      let rand = next();
+      return list.length ? list[rand % list.length] : undefined;
+    },
+  };
+}
+
+export function kartArms(nftData) {
+  let weapons = [];
+  let shields = [];
+
+  for (let side of [nftData.left, nftData.right]) {
+    if (side >= SHIELD_START_INDEX) {
+      let shield = SHIELDS_SIDE[side - SHIELD_START_INDEX];
+      if (shield) shields.push(shield);
+    } else if (WEAPONS_RANGE[side]) {
+      weapons.push(WEAPONS_RANGE[side]);
+    }
+  }
+  if (WEAPONS_MELEE[nftData.front]) {
+    weapons.push(WEAPONS_MELEE[nftData.front]);
+  }
+
+  return { weapons, shields };
+}
+
+function attacks(rng, aggressor, weapons, shields, target) {
+  let rounds = [];
+  let total = 0;
+
+  while (total < target) {
+    let score = 0;
+    if (rng.range(0, SHIELD_CHANCE) !== 0) {
+      score = rng.range(BOTTOM_SCORE, TOP_SCORE);
+      total += score;
+    }
+
+    let weapon = rng.pick(weapons);
+    let bumpIndex = Math.min(weapons.length, BUMP_CHANCE_BY_WEAPON_COUNT.length - 1);
+    if (weapon && rng.range(0, BUMP_CHANCE_BY_WEAPON_COUNT[bumpIndex]) === 0) {
+      weapon = undefined;
+    }
+
+    let shield = null;
+    if (score === 0) {
+      shield = rng.pick(shields) || 'evade';
+    }
+
+    rounds.push({ aggressor, weapon: weapon || 'general', shield, score });
+  }
+  return rounds;
+}
+
+export function battleRounds(battle, homeKart, awayKart) {
+  let rng = replayRng(battle.battle);
+  let winner = Math.min(battle.winner, 1);
+  let loser = 1 - winner;
+  let arms = [kartArms(homeKart), kartArms(awayKart)];
+
+  let winnerRounds = attacks(rng, winner, arms[winner].weapons, arms[loser].shields, WINNER_SCORE);
+  let loserRounds = attacks(rng, loser, arms[loser].weapons, arms[winner].shields, LOSER_SCORE);
+  loserRounds.pop();
+  let winningRound = winnerRounds.pop();
+
+  let rounds = [...winnerRounds, ...loserRounds];
+  for (let i = rounds.length - 1; i > 0; i--) {
+    let j = rng.range(0, i);
+    [rounds[i], rounds[j]] = [rounds[j], rounds[i]];
+  }
+  rounds.push(winningRound);
+
+  return rounds;
+}
@@ -0,0 +1,30 @@
+import { parseContractError } from '../helpers/errors';
+
+test('parses contract errors', () => {
+  let e = new Error(
+    "Smart contract panicked: KART_ERR:403:Mint and upgrade prices can't be zero"
+  );
+  expect(parseContractError(e)).toEqual({
+    code: 403,
+    message: "Mint and upgrade prices can't be zero",
+  });
+  expect(parseContractError('KART_ERR:100:No kart has this token id')).toEqual({
+    code: 100,
+    message: 'No kart has this token id',
+  });
+});
+
+test('stops at the end of a quoted execution error', () => {
+  let e = new Error(
+    '{"ExecutionError":"Smart contract panicked: KART_ERR:100:No kart has this token id"}'
+  );
+  expect(parseContractError(e)).toEqual({
+    code: 100,
+    message: 'No kart has this token id',
+  });
+});
+
+test('ignores other errors', () => {
+  expect(parseContractError(new Error('Exceeded the prepaid gas'))).toBeNull();
+  expect(parseContractError(undefined)).toBeNull();
+});
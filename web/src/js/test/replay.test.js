@@ -0,0 +1,17 @@
+import { battleRounds, replayRng } from '../helpers/replay';
+import vectors from '../../../../contracts/near/types/vectors/replay.json';
+
+test('rng matches the contract types', () => {
+  let rng = replayRng(1);
+  expect(rng.next()).toBe(2693262067);
+  expect(rng.next()).toBe(11749833);
+});
+
+test('replays the golden vectors', () => {
+  expect(vectors.length).toBeGreaterThan(0);
+
+  for (let v of vectors) {
+    let rounds = battleRounds({ battle: v.battle, winner: v.winner }, v.home, v.away);
+    expect(rounds).toEqual(v.rounds);
+  }
+});